@@ -0,0 +1,11 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The gRPC service is generated from the proto definition (requires `protoc`)
+    #[cfg(feature = "grpc")]
+    tonic_prost_build::compile_protos("proto/segmenter.proto")?;
+
+    // Node.js addons need platform-specific linker arguments
+    #[cfg(feature = "node")]
+    napi_build::setup();
+
+    Ok(())
+}
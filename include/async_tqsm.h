@@ -0,0 +1,58 @@
+/*
+ * C interface to async-tqsm, built with `cargo build --release --features capi`.
+ *
+ * Every function returns a TQSM_* status code. Sentences are delivered through a
+ * callback as UTF-8 pointer/length pairs that are only valid during the callback.
+ */
+#ifndef ASYNC_TQSM_H
+#define ASYNC_TQSM_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define TQSM_OK 0
+#define TQSM_ERR_INVALID_ARGUMENT 1
+#define TQSM_ERR_INVALID_UTF8 2
+#define TQSM_ERR_UNSUPPORTED_LANGUAGE 3
+#define TQSM_ERR_BUFFER_OVERFLOW 4
+#define TQSM_ERR_INTERNAL 5
+
+typedef struct TqsmSegmenter TqsmSegmenter;
+
+typedef void (*TqsmSentenceCallback)(const char *sentence, size_t len, void *user_data);
+
+int tqsm_segmenter_new(const char *language, size_t lookahead, size_t max_buffer,
+                       TqsmSegmenter **out);
+void tqsm_segmenter_free(TqsmSegmenter *segmenter);
+
+/* A UTF-8 character may be split between two calls to tqsm_feed. */
+int tqsm_feed(TqsmSegmenter *segmenter, const char *text, size_t len,
+              TqsmSentenceCallback callback, void *user_data);
+int tqsm_flush(TqsmSegmenter *segmenter, TqsmSentenceCallback callback, void *user_data);
+
+/* Message of the last failed call on this handle, or NULL. */
+const char *tqsm_last_error(const TqsmSegmenter *segmenter);
+
+/* Stable code of the last failed call on this handle, or 0. Codes never change. */
+unsigned int tqsm_last_error_code(const TqsmSegmenter *segmenter);
+
+#define TQSM_CODE_UNSUPPORTED_LANGUAGE 1
+#define TQSM_CODE_UNSUPPORTED_BACKEND 2
+#define TQSM_CODE_LANGUAGE_LOAD 3
+#define TQSM_CODE_INVALID_OPTIONS 4
+#define TQSM_CODE_BUFFER_OVERFLOW 5
+#define TQSM_CODE_MEMORY_BUDGET 6
+#define TQSM_CODE_IO 7
+#define TQSM_CODE_UTF8 8
+#define TQSM_CODE_STREAM 9
+#define TQSM_CODE_SEGMENTATION 10
+#define TQSM_CODE_DECODE 11
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ASYNC_TQSM_H */
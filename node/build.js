@@ -0,0 +1,18 @@
+// Builds the native addon into `async-tqsm.node`. The crate is an rlib unless asked otherwise,
+// so the cdylib is requested from `cargo rustc` here rather than declared in Cargo.toml.
+const { execFileSync } = require("child_process");
+const fs = require("fs");
+const path = require("path");
+
+const root = path.join(__dirname, "..");
+execFileSync(
+  "cargo",
+  ["rustc", "--release", "--lib", "--features", "node", "--crate-type", "cdylib"],
+  { cwd: root, stdio: "inherit" },
+);
+
+const library =
+  { darwin: "libasync_tqsm.dylib", win32: "async_tqsm.dll" }[process.platform] ??
+  "libasync_tqsm.so";
+const target = process.env.CARGO_TARGET_DIR ?? path.join(root, "target");
+fs.copyFileSync(path.join(target, "release", library), path.join(__dirname, "async-tqsm.node"));
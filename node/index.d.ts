@@ -0,0 +1,21 @@
+import type { Readable } from "stream";
+
+export interface SegmenterOptions {
+  language?: string;
+  lookahead?: number;
+  maxBuffer?: number;
+}
+
+export class SentenceSegmenter {
+  constructor(options?: SegmenterOptions);
+  /** Appends a chunk and returns the sentences it completed. */
+  feed(chunk: string): string[];
+  /** Ends the input and returns the remaining sentences, including the unterminated tail. */
+  flush(): string[];
+}
+
+/** Yields sentences from a Readable stream as soon as they are complete. */
+export function sentences(
+  readable: Readable | AsyncIterable<string | Buffer>,
+  options?: SegmenterOptions,
+): AsyncGenerator<string, void, undefined>;
@@ -0,0 +1,27 @@
+"use strict";
+
+const { StringDecoder } = require("string_decoder");
+const { SentenceSegmenter } = require("./async-tqsm.node");
+
+/**
+ * Yields sentences from a Readable stream (or any async iterable of strings/Buffers) as soon as
+ * they are complete, then the remaining ones once the stream ends.
+ */
+async function* sentences(readable, options) {
+  const segmenter = new SentenceSegmenter(options);
+  // Buffers may split multi-byte characters; the decoder carries them over
+  const decoder = new StringDecoder("utf8");
+
+  for await (const chunk of readable) {
+    const text = typeof chunk === "string" ? chunk : decoder.write(chunk);
+    yield* segmenter.feed(text);
+  }
+
+  const rest = decoder.end();
+  if (rest) {
+    yield* segmenter.feed(rest);
+  }
+  yield* segmenter.flush();
+}
+
+module.exports = { SentenceSegmenter, sentences };
@@ -0,0 +1,18 @@
+"""Asynchronous, streaming sentence segmentation based on tqsm."""
+
+from ._native import Segmenter, segment_str
+
+__all__ = ["Segmenter", "segment_str", "asentences"]
+
+
+async def asentences(chunks, language="en", lookahead=10, max_buffer=8192):
+    """Yield sentences from an async iterable of text chunks as soon as they are complete.
+
+    The sentences still buffered are flushed once ``chunks`` is exhausted.
+    """
+    segmenter = Segmenter(language=language, lookahead=lookahead, max_buffer=max_buffer)
+    async for chunk in chunks:
+        for sentence in segmenter.feed(chunk):
+            yield sentence
+    for sentence in segmenter.flush():
+        yield sentence
@@ -0,0 +1,173 @@
+//! Abbreviation lists loaded from files, shared by every segmenter that was given them and
+//! replaced atomically when the files change, so long-lived server sessions pick up rule
+//! tweaks without a restart.
+//!
+//! Files use the Moses non-breaking prefix format: one abbreviation per line (with or without
+//! the trailing period) and `#` starts a comment. An entry marked `#NUMERIC_ONLY#` only keeps
+//! the sentence going when a digit follows (`No. 5`).
+
+use crate::error::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// A reloadable set of abbreviations, cheap to clone; clones share the same list.
+#[derive(Debug, Clone, Default)]
+pub struct SharedAbbreviations {
+    files: Arc<Vec<PathBuf>>,
+    current: Arc<RwLock<Lists>>,
+    generation: Arc<AtomicU64>,
+}
+
+/// The entries of one load, swapped together.
+#[derive(Debug, Default)]
+struct Lists {
+    words: Arc<HashSet<String>>,
+    /// Entries marked `#NUMERIC_ONLY#`.
+    numeric_only: Arc<HashSet<String>>,
+}
+
+impl SharedAbbreviations {
+    /// Loads the abbreviations in `files`.
+    pub fn load<I, P>(files: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        let shared = Self {
+            files: Arc::new(files.into_iter().map(Into::into).collect()),
+            ..Self::default()
+        };
+        shared.reload()?;
+        Ok(shared)
+    }
+
+    /// Re-reads all files and swaps in the new list. On error the previous list stays in
+    /// effect.
+    pub fn reload(&self) -> Result<()> {
+        let mut words = HashSet::new();
+        let mut numeric_only = HashSet::new();
+        for path in self.files.iter() {
+            for (entry, numeric) in read_file(path)? {
+                if numeric {
+                    numeric_only.insert(entry);
+                } else {
+                    words.insert(entry);
+                }
+            }
+        }
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Lists {
+            words: Arc::new(words),
+            numeric_only: Arc::new(numeric_only),
+        };
+        self.generation.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+
+    /// The list in effect, with trailing periods removed. Entries marked `#NUMERIC_ONLY#` are
+    /// left out.
+    pub fn current(&self) -> Arc<HashSet<String>> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .words
+            .clone()
+    }
+
+    /// Number of successful (re)loads so far.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Whether `word` (without its trailing period) is in the list. Entries marked
+    /// `#NUMERIC_ONLY#` are left out; see [`applies`](Self::applies).
+    pub fn contains(&self, word: &str) -> bool {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .words
+            .contains(word)
+    }
+
+    /// Whether the period after `word` is an abbreviation's when `after` follows it: `word` is
+    /// in the list, or marked `#NUMERIC_ONLY#` and `after` starts with a digit.
+    pub fn applies(&self, word: &str, after: &str) -> bool {
+        let lists = self.current.read().unwrap_or_else(|e| e.into_inner());
+        lists.words.contains(word)
+            || (lists.numeric_only.contains(word)
+                && after.trim_start().starts_with(|c: char| c.is_ascii_digit()))
+    }
+
+    /// Polls the files every `interval` and reloads the list whenever one of them changes,
+    /// reading them on tokio's blocking pool. A failed reload is logged through `tracing` and
+    /// keeps the previous list. Runs until the returned task is aborted.
+    #[cfg(feature = "tokio")]
+    pub fn watch(&self, interval: std::time::Duration) -> tokio::task::JoinHandle<()> {
+        let shared = self.clone();
+        tokio::spawn(async move {
+            let mut stamps = shared.stamps().await;
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let latest = shared.stamps().await;
+                if latest == stamps {
+                    continue;
+                }
+                stamps = latest;
+                let reloading = shared.clone();
+                let reloaded = tokio::task::spawn_blocking(move || reloading.reload())
+                    .await
+                    .unwrap_or_else(|e| Err(std::io::Error::from(e).into()));
+                match reloaded {
+                    Ok(()) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::info!(
+                            generation = shared.generation(),
+                            entries = shared.current().len(),
+                            "reloaded abbreviation files"
+                        );
+                    }
+                    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+                    Err(e) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(error = %e, "keeping previous abbreviations");
+                    }
+                }
+            }
+        })
+    }
+
+    /// Modification time and length of every file; missing files count as `None`.
+    #[cfg(feature = "tokio")]
+    async fn stamps(&self) -> Vec<Option<(std::time::SystemTime, u64)>> {
+        let mut stamps = Vec::with_capacity(self.files.len());
+        for path in self.files.iter() {
+            let stamp = tokio::fs::metadata(path)
+                .await
+                .ok()
+                .and_then(|meta| Some((meta.modified().ok()?, meta.len())));
+            stamps.push(stamp);
+        }
+        stamps
+    }
+}
+
+/// Reads one Moses-style non-breaking prefix file: each entry, and whether it is marked
+/// `#NUMERIC_ONLY#`.
+fn read_file(path: &Path) -> Result<Vec<(String, bool)>> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(contents
+        .lines()
+        .map(|line| {
+            let numeric = line.contains("#NUMERIC_ONLY#");
+            let entry = line.split('#').next().unwrap_or_default().trim();
+            (
+                entry.strip_suffix('.').unwrap_or(entry).to_string(),
+                numeric,
+            )
+        })
+        .filter(|(entry, _)| !entry.is_empty())
+        .collect())
+}
@@ -0,0 +1,129 @@
+//! Length-based sentence alignment for parallel corpora (bitext).
+//!
+//! Implements the classic Gale–Church dynamic program: each sentence pair is scored by how well
+//! the character lengths of both sides agree, and the cheapest sequence of 1-1, 1-0, 0-1, 2-1,
+//! 1-2 and 2-2 "beads" across both documents wins.
+
+use crate::config::SegmentOptions;
+use crate::error::Result;
+use crate::segmenter::{segment_document, Segment};
+use std::ops::Range;
+
+/// Expected target characters per source character.
+const LENGTH_RATIO: f64 = 1.0;
+/// Variance of the length difference per source character.
+const LENGTH_VARIANCE: f64 = 6.8;
+
+/// Allowed bead shapes with their prior probabilities, as `(source, target, probability)`.
+const BEADS: [(usize, usize, f64); 6] = [
+    (1, 1, 0.89),
+    (1, 0, 0.005),
+    (0, 1, 0.005),
+    (2, 1, 0.0445),
+    (1, 2, 0.0445),
+    (2, 2, 0.011),
+];
+
+/// A group of source sentences aligned to a group of target sentences. Either side may be empty
+/// when a sentence has no counterpart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bead {
+    /// Indices into the source sentence list.
+    pub source: Range<usize>,
+    /// Indices into the target sentence list.
+    pub target: Range<usize>,
+}
+
+/// Aligned sentences from both sides of a parallel text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlignedPair {
+    pub source: Vec<Segment>,
+    pub target: Vec<Segment>,
+}
+
+/// Aligns two sentence lists by length, returning beads that cover both lists in order.
+pub fn align<S: AsRef<str>, T: AsRef<str>>(source: &[S], target: &[T]) -> Vec<Bead> {
+    let source_lens: Vec<usize> = source.iter().map(|s| s.as_ref().chars().count()).collect();
+    let target_lens: Vec<usize> = target.iter().map(|t| t.as_ref().chars().count()).collect();
+    let (n, m) = (source_lens.len(), target_lens.len());
+
+    // cost[i][j]: cheapest alignment of the first i source and j target sentences
+    let mut cost = vec![vec![f64::INFINITY; m + 1]; n + 1];
+    let mut back = vec![vec![(0, 0); m + 1]; n + 1];
+    cost[0][0] = 0.0;
+
+    for i in 0..=n {
+        for j in 0..=m {
+            for &(di, dj, prior) in &BEADS {
+                if di > i || dj > j || cost[i - di][j - dj].is_infinite() {
+                    continue;
+                }
+                let ls: usize = source_lens[i - di..i].iter().sum();
+                let lt: usize = target_lens[j - dj..j].iter().sum();
+                let candidate = cost[i - di][j - dj] + bead_cost(ls, lt, prior);
+                if candidate < cost[i][j] {
+                    cost[i][j] = candidate;
+                    back[i][j] = (di, dj);
+                }
+            }
+        }
+    }
+
+    let mut beads = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        let (di, dj) = back[i][j];
+        beads.push(Bead {
+            source: i - di..i,
+            target: j - dj..j,
+        });
+        i -= di;
+        j -= dj;
+    }
+    beads.reverse();
+    beads
+}
+
+/// Segments both sides of a parallel text and aligns the resulting sentences.
+pub fn segment_bitext(
+    source: &str,
+    target: &str,
+    source_options: SegmentOptions,
+    target_options: SegmentOptions,
+) -> Result<Vec<AlignedPair>> {
+    let source = segment_document(source, source_options)?;
+    let target = segment_document(target, target_options)?;
+    let source_texts: Vec<&str> = source.iter().map(|s| s.text.as_str()).collect();
+    let target_texts: Vec<&str> = target.iter().map(|s| s.text.as_str()).collect();
+
+    Ok(align(&source_texts, &target_texts)
+        .into_iter()
+        .map(|bead| AlignedPair {
+            source: source[bead.source].to_vec(),
+            target: target[bead.target].to_vec(),
+        })
+        .collect())
+}
+
+/// Negative log probability of a bead with the given side lengths.
+fn bead_cost(source_len: usize, target_len: usize, prior: f64) -> f64 {
+    let (ls, lt) = (source_len as f64, target_len as f64);
+    let mean = (ls + lt / LENGTH_RATIO) / 2.0;
+    let delta = if mean == 0.0 {
+        0.0
+    } else {
+        (lt - ls * LENGTH_RATIO) / (mean * LENGTH_VARIANCE).sqrt()
+    };
+    // Two-tailed probability of a length difference at least this large
+    let p_delta = erfc(delta.abs() / std::f64::consts::SQRT_2).max(f64::MIN_POSITIVE);
+    -(prior.ln() + p_delta.ln())
+}
+
+/// Complementary error function (Abramowitz & Stegun 7.1.26, |error| < 1.5e-7).
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    poly * (-x * x).exp()
+}
@@ -0,0 +1,49 @@
+//! [`AsyncIterator`] support for sentence streams, for early adopters of `for await` on
+//! nightly Rust. Enabled with the `nightly` feature.
+
+use futures::stream::Stream;
+use std::async_iter::AsyncIterator;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Wraps a sentence stream (e.g. from [`crate::sentences_stream`]) as an [`AsyncIterator`].
+pub struct SentenceAsyncIter<S> {
+    inner: Pin<Box<S>>,
+}
+
+impl<S: Stream> SentenceAsyncIter<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            inner: Box::pin(stream),
+        }
+    }
+
+    /// Returns the wrapped stream.
+    pub fn into_inner(self) -> Pin<Box<S>> {
+        self.inner
+    }
+}
+
+impl<S: Stream> AsyncIterator for SentenceAsyncIter<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().inner.as_mut().poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// [`crate::sentences_stream`] as an [`AsyncIterator`].
+#[cfg(feature = "tokio")]
+pub fn sentences_async_iter<'a, R>(
+    reader: R,
+    options: crate::SegmentOptions,
+) -> SentenceAsyncIter<impl Stream<Item = crate::Result<String>> + 'a>
+where
+    R: tokio::io::AsyncRead + Unpin + Send + 'a,
+{
+    SentenceAsyncIter::new(crate::sentences_stream(reader, options))
+}
@@ -0,0 +1,118 @@
+//! A record of every sentence boundary the segmenter accepted and why, for teams that have to
+//! justify how a published corpus was segmented.
+//!
+//! Set [`SegmentOptions::audit`](crate::SegmentOptions::audit) to an [`Audit`] sink; the CLI
+//! writes the records as JSON lines with `--audit FILE`.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Which rule produced a boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum BoundaryRule {
+    /// A sentence terminator confirmed by the language's context checks.
+    Terminator,
+    /// A terminator followed by a numbered reference (e.g. `.[3]`), accepted without lookahead.
+    NumberedReference,
+    /// Terminal punctuation closing a quotation.
+    ClosingQuote,
+    /// Terminal punctuation followed by a footnote marker such as `[12]` or `¹`, which stays
+    /// with the sentence.
+    FootnoteMarker,
+    /// Terminal punctuation followed by a parenthetical citation, which stays with the
+    /// sentence.
+    Citation,
+    /// A space between two words of a script that marks sentences with spaces (Thai, Lao), or
+    /// a terminator the language rules do not know (Khmer `។`, Urdu `۔`).
+    ScriptRule,
+    /// A line break ending a chat message.
+    LineBreak,
+    /// A run of emoji followed by the start of the next sentence, in chat mode.
+    Emoji,
+    /// A chat message, or the start of one, made only of emoji or an interjection (`lol`).
+    Standalone,
+    /// The end of the input closed the last sentence.
+    EndOfInput,
+    /// A sentence reached [`max_sentence_len`](crate::SegmentOptions::max_sentence_len), or the
+    /// buffer filled up without a boundary under a splitting
+    /// [`OverflowPolicy`](crate::OverflowPolicy), and the text was split before a boundary.
+    ForcedSplit,
+    /// The input paused for [`flush_after`](crate::SegmentOptions::flush_after), and the
+    /// buffered text was emitted without waiting for a terminator.
+    Idle,
+    /// A split rule of [`custom_boundaries`](crate::SegmentOptions::custom_boundaries).
+    Custom,
+    /// A blank line ending a paragraph, when segmenting by
+    /// [`unit`](crate::SegmentOptions::unit) `paragraph` or `nested`.
+    ParagraphBreak,
+}
+
+impl BoundaryRule {
+    /// Short, stable name, e.g. for log fields.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BoundaryRule::Terminator => "terminator",
+            BoundaryRule::NumberedReference => "numbered_reference",
+            BoundaryRule::ClosingQuote => "closing_quote",
+            BoundaryRule::FootnoteMarker => "footnote_marker",
+            BoundaryRule::Citation => "citation",
+            BoundaryRule::ScriptRule => "script_rule",
+            BoundaryRule::LineBreak => "line_break",
+            BoundaryRule::Emoji => "emoji",
+            BoundaryRule::Standalone => "standalone",
+            BoundaryRule::EndOfInput => "end_of_input",
+            BoundaryRule::ForcedSplit => "forced_split",
+            BoundaryRule::Idle => "idle",
+            BoundaryRule::Custom => "custom",
+            BoundaryRule::ParagraphBreak => "paragraph_break",
+        }
+    }
+}
+
+/// One accepted boundary.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Boundary {
+    /// Index of the sentence the boundary ends.
+    pub index: usize,
+    /// Absolute byte offset of the boundary in the input.
+    pub offset: usize,
+    pub rule: BoundaryRule,
+    /// Bytes of input after the boundary that were available when it was accepted.
+    pub lookahead: usize,
+    /// Rough certainty between 0 and 1: 1 when the configured lookahead was available (or not
+    /// needed), down to 0.5 for a terminator accepted at the end of the input with no
+    /// lookahead at all.
+    pub confidence: f32,
+}
+
+/// Where boundary records go; cheap to clone, clones report to the same place.
+#[derive(Clone)]
+pub struct Audit {
+    sink: Arc<dyn Fn(Boundary) + Send + Sync>,
+}
+
+impl Audit {
+    /// Calls `sink` for every accepted boundary, on the thread that segments.
+    pub fn new(sink: impl Fn(Boundary) + Send + Sync + 'static) -> Self {
+        Self {
+            sink: Arc::new(sink),
+        }
+    }
+
+    pub(crate) fn record(&self, boundary: Boundary) {
+        (self.sink)(boundary);
+    }
+}
+
+impl fmt::Debug for Audit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Audit").finish_non_exhaustive()
+    }
+}
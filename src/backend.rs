@@ -0,0 +1,194 @@
+//! The rule engine behind a [`Segmenter`](crate::Segmenter): it proposes candidate boundaries
+//! and decides which of them end a sentence, while the segmenter does the buffering, lookahead,
+//! protections and offsets around it.
+//!
+//! [`Segmenter::new`](crate::Segmenter::new) uses the `libtqsm` rules of the configured
+//! language, through [`TqsmBackend`]. To use another engine, e.g. one tuned to a domain-specific
+//! corpus, implement [`SegmentationBackend`] and pass it to
+//! [`Segmenter::with_backend`](crate::Segmenter::with_backend).
+//!
+//! With the `icu` feature, [`IcuBackend`] applies the Unicode sentence-break rules (UAX #29) of
+//! ICU4X instead. It is selected with [`Backend::Icu`], and is what languages without `libtqsm`
+//! rules fall back to.
+
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use libtqsm::{GraphemeCursor, Language};
+use regex::{Match, Regex};
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Which rule engine [`Segmenter::new`](crate::Segmenter::new) uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Backend {
+    /// The `libtqsm` rules of the language. A language without them uses the
+    /// `fallback_language`'s if set, or else ICU4X with the `icu` feature, or else fails with
+    /// [`UnsupportedLanguage`](SegmenterError::UnsupportedLanguage).
+    #[default]
+    Tqsm,
+    /// ICU4X's Unicode sentence-break rules for every language. Needs the `icu` feature.
+    Icu,
+}
+
+/// The backend `options` select, resolving `options.language` to the fallback language if
+/// that is used instead.
+pub(crate) fn for_options(options: &mut SegmentOptions) -> Result<Box<dyn SegmentationBackend>> {
+    if options.backend == Backend::Icu {
+        return icu_backend(&options.language);
+    }
+    match crate::scripts::resolve_language(options) {
+        Err(SegmenterError::UnsupportedLanguage(_))
+            if cfg!(feature = "icu") && options.fallback_language.is_none() =>
+        {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(language = %options.language, "language not supported, using ICU4X");
+            if let Some(warnings) = &options.warnings {
+                warnings.report(
+                    crate::warnings::WarningKind::LanguageFallback,
+                    0,
+                    format!(
+                        "no rules for language '{}', using ICU4X sentence breaks",
+                        options.language
+                    ),
+                );
+            }
+            icu_backend(&options.language)
+        }
+        language => Ok(Box::new(TqsmBackend::new(language?))),
+    }
+}
+
+#[cfg(feature = "icu")]
+fn icu_backend(language: &str) -> Result<Box<dyn SegmentationBackend>> {
+    Ok(Box::new(IcuBackend::for_language(language)?))
+}
+
+#[cfg(not(feature = "icu"))]
+fn icu_backend(_language: &str) -> Result<Box<dyn SegmentationBackend>> {
+    Err(SegmenterError::InvalidOptions(
+        "the ICU4X backend needs the `icu` feature".to_string(),
+    ))
+}
+
+/// Finds sentence boundaries in the buffered text of a [`Segmenter`](crate::Segmenter).
+///
+/// The text passed in starts at the beginning of the next sentence and ends wherever the input
+/// has got to. Offsets are byte offsets into it.
+pub trait SegmentationBackend: Send + Sync {
+    /// Regex matching candidate sentence-final punctuation, e.g. `[.!?]+`.
+    fn sentence_break_regex(&self) -> &Regex;
+
+    /// Byte ranges of `text` no sentence ends inside, such as quotations.
+    fn skippable_ranges(&self, _text: &str) -> Vec<(usize, usize)> {
+        Vec::new()
+    }
+
+    /// Whether punctuation right at the end of a skippable range ends a sentence.
+    fn is_punctuation_between_quotes(&self) -> bool {
+        false
+    }
+
+    /// Whether the candidate `mtch` of the break regex ends a sentence. Returns where the next
+    /// sentence starts (usually after the whitespace following the punctuation) and whether
+    /// the boundary is a numbered reference (`.[3]`), which is accepted without lookahead;
+    /// `None` rejects the candidate.
+    fn find_boundary(&self, text: &str, mtch: Match<'_>) -> Option<(usize, bool)>;
+}
+
+/// The `libtqsm` rules for one language.
+#[derive(Clone, Copy)]
+pub struct TqsmBackend {
+    language: &'static (dyn Language + Send + Sync),
+}
+
+impl TqsmBackend {
+    /// The rules for `language`, if `libtqsm` or a related rule set covers it.
+    pub fn for_language(language: &str) -> Option<Self> {
+        crate::scripts::language_rules(language).map(Self::new)
+    }
+
+    pub(crate) fn new(language: &'static (dyn Language + Send + Sync)) -> Self {
+        Self { language }
+    }
+}
+
+impl SegmentationBackend for TqsmBackend {
+    fn sentence_break_regex(&self) -> &Regex {
+        self.language.sentence_break_regex()
+    }
+
+    fn skippable_ranges(&self, text: &str) -> Vec<(usize, usize)> {
+        self.language.get_skippable_ranges(text)
+    }
+
+    fn is_punctuation_between_quotes(&self) -> bool {
+        self.language.is_punctuation_between_quotes()
+    }
+
+    fn find_boundary(&self, text: &str, mtch: Match<'_>) -> Option<(usize, bool)> {
+        let grapheme_indices: HashMap<usize, &str> = text.grapheme_indices(false).collect();
+        let mut grapheme_offsets: Vec<usize> = grapheme_indices.keys().copied().collect();
+        grapheme_offsets.sort_unstable();
+        let cursor = GraphemeCursor::new(grapheme_offsets);
+        self.language
+            .find_boundary(text, &grapheme_indices, &cursor, mtch)
+    }
+}
+
+/// Candidate punctuation for [`IcuBackend`]: the Unicode sentence terminators, and `;`, which
+/// ends a question in Greek.
+#[cfg(feature = "icu")]
+const ICU_TERMINATORS: &str = r"[\p{Sentence_Break=STerm}\p{Sentence_Break=ATerm};]+";
+
+/// ICU4X's Unicode sentence-break rules (UAX #29), tailored to the language where ICU4X has
+/// tailorings. They know no abbreviations, so only the segmenter's own lists and protections
+/// keep a sentence going after one.
+#[cfg(feature = "icu")]
+pub struct IcuBackend {
+    segmenter: icu_segmenter::SentenceSegmenter,
+    terminators: Regex,
+}
+
+#[cfg(feature = "icu")]
+impl IcuBackend {
+    /// The rules for `language`; a code ICU4X cannot parse gets the untailored rules.
+    pub fn for_language(language: &str) -> Result<Self> {
+        let locale = language.parse::<icu_locale_core::LanguageIdentifier>().ok();
+        let mut options = icu_segmenter::options::SentenceBreakOptions::default();
+        options.content_locale = locale.as_ref();
+        let segmenter = icu_segmenter::SentenceSegmenter::try_new(options).map_err(|e| {
+            SegmenterError::InvalidOptions(format!("ICU4X rules for '{}': {}", language, e))
+        })?;
+        Ok(Self {
+            segmenter,
+            terminators: Regex::new(ICU_TERMINATORS).expect("valid terminator pattern"),
+        })
+    }
+}
+
+#[cfg(feature = "icu")]
+impl SegmentationBackend for IcuBackend {
+    fn sentence_break_regex(&self) -> &Regex {
+        &self.terminators
+    }
+
+    /// Accepts the candidate if the Unicode rules break after it, before the next word. The
+    /// breaks they put after every line break are not used.
+    fn find_boundary(&self, text: &str, mtch: Match<'_>) -> Option<(usize, bool)> {
+        let end = self
+            .segmenter
+            .as_borrowed()
+            .segment_str(text)
+            .find(|&end| end >= mtch.end())?;
+        text[mtch.end()..end]
+            .chars()
+            .all(|c| !c.is_alphanumeric())
+            .then_some((end, false))
+    }
+}
@@ -1,21 +1,329 @@
-use async_tqsm::config::CliArgs;
-use async_tqsm::{sentences_stream, SegmentOptions, SegmenterError};
+use async_tqsm::abbreviations::SharedAbbreviations;
+use async_tqsm::audit::Audit;
+use async_tqsm::compare::Backend;
+use async_tqsm::config::{CliArgs, Command, OutputFormat, RulesFormat};
+use async_tqsm::dedup::NearDuplicates;
+use async_tqsm::duration::estimate_duration;
+use async_tqsm::ids::{fingerprint, sentence_id};
+use async_tqsm::logging::init_logging;
+use async_tqsm::protect::read_rules_file;
+use async_tqsm::rules::RuleSet;
+use async_tqsm::sample::Sampler;
+use async_tqsm::subtitles::{SubtitleOptions, Subtitles};
+use async_tqsm::warnings::Warnings;
+use async_tqsm::{items_stream, Segment, SegmentOptions, SegmenterError, StreamItem};
 use clap::Parser;
 use futures::StreamExt; // Required for stream.next()
+use std::path::Path;
 use std::process::exit;
 use tokio::fs::File;
 use tokio::io::{self, AsyncRead, AsyncWriteExt, BufReader, BufWriter}; // For exiting with error code
 
+/// One `--output-format jsonl` line.
+#[derive(serde::Serialize)]
+struct Record<'a> {
+    /// Source file in `--input-dir` and `--comments` mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a Path>,
+    doc_id: &'a str,
+    /// `doc_id#index`.
+    id: String,
+    #[serde(flatten)]
+    segment: &'a Segment,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+impl<'a> Record<'a> {
+    fn new(args: &CliArgs, doc_id: &'a str, segment: &'a Segment, language: &str) -> Self {
+        Self {
+            file: None,
+            doc_id,
+            id: sentence_id(doc_id, segment.index),
+            segment,
+            duration_ms: args.durations.then(|| {
+                let language = segment.language.as_deref().unwrap_or(language);
+                estimate_duration(&segment.text, language, args.wpm).as_millis()
+            }),
+            hash: args
+                .content_hash
+                .map(|algorithm| fingerprint(&segment.text, algorithm, args.hash_prefix)),
+        }
+    }
+}
+
+/// Subtitle cue state for `--output-format srt`.
+fn subtitles(args: &CliArgs, language: &str) -> Subtitles {
+    Subtitles::new(SubtitleOptions {
+        width: args.subtitle_width,
+        max_lines: args.subtitle_lines,
+        language: language.to_string(),
+        rate: args.wpm,
+    })
+}
+
+/// Which sentences are written: `--dedup` and `--sample`.
+struct Selection {
+    near_duplicates: Option<NearDuplicates>,
+    sampler: Option<Sampler>,
+}
+
+impl Selection {
+    fn new(args: &CliArgs) -> Result<Self, SegmenterError> {
+        Ok(Self {
+            near_duplicates: args
+                .dedup
+                .map(|bits| NearDuplicates::new(bits, args.dedup_window)),
+            sampler: args
+                .sample
+                .map(|rate| Sampler::new(rate, args.seed))
+                .transpose()?,
+        })
+    }
+
+    /// Whether to leave out `segment` of `doc_id`. Near-duplicates are filtered first,
+    /// so the sample is drawn from the deduplicated sentences.
+    fn skip(&mut self, doc_id: &str, segment: &Segment) -> bool {
+        let duplicate = self
+            .near_duplicates
+            .as_mut()
+            .is_some_and(|filter| filter.is_duplicate(&segment.text));
+        duplicate
+            || self
+                .sampler
+                .is_some_and(|sampler| !sampler.keep(&sentence_id(doc_id, segment.index)))
+    }
+}
+
+/// The SRT blocks for `text`, without the newline that ends the last one.
+fn srt(subtitles: &mut Subtitles, text: &str) -> String {
+    let mut blocks: String = subtitles
+        .cues(text)
+        .iter()
+        .map(|cue| cue.to_string())
+        .collect();
+    blocks.pop();
+    blocks
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Use the struct via the library path
     let args = CliArgs::parse();
+    if let Err(e) = init_logging(args.log_format) {
+        eprintln!("Error setting up logging: {}", e);
+        exit(1);
+    }
     // Pass the args directly to convert into options
-    let options = SegmentOptions::from(args.clone());
+    let mut options = SegmentOptions::from(args.clone());
+    options.warnings = Some(Warnings::new(|warning| {
+        tracing::warn!(
+            warning_kind = warning.kind.as_str(),
+            offset = warning.offset,
+            "{}",
+            warning.message
+        );
+    }));
+    if !args.abbrev_file.is_empty() {
+        let shared = match SharedAbbreviations::load(&args.abbrev_file) {
+            Ok(shared) => shared,
+            Err(e) => fatal("Error loading abbreviation files", e, None, None),
+        };
+        if args.reload_interval > 0 {
+            shared.watch(std::time::Duration::from_secs(args.reload_interval));
+        }
+        options.shared_abbreviations = Some(shared);
+    }
+    for path in &args.rules_file {
+        match read_rules_file(path, &options.language) {
+            Ok(patterns) => options.protections.patterns.extend(patterns),
+            Err(e) => fatal("Error loading rules file", e, None, None),
+        }
+    }
+    if let Some(path) = &args.audit {
+        match audit_file(path) {
+            Ok(audit) => options.audit = Some(audit),
+            Err(e) => fatal("Error opening audit file", e.into(), None, None),
+        }
+    }
+
+    if let Some(Command::Compare { input, backends }) = &args.command {
+        if let Err(e) = print_comparison(input, backends, &options).await {
+            fatal("Compare error", e, None, None);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::ExportRules { format }) = &args.command {
+        let rules = match RuleSet::for_options(&options) {
+            Ok(rules) => rules,
+            Err(e) => {
+                fatal("Export error", e, None, None);
+            }
+        };
+        match format {
+            RulesFormat::Srx => print!("{}", rules.to_srx()),
+            RulesFormat::Json => println!("{}", serde_json::to_string_pretty(&rules)?),
+        }
+        return Ok(());
+    }
+
+    #[cfg(feature = "rpc")]
+    if args.stdio_rpc {
+        if let Err(e) = async_tqsm::server::rpc::serve_stdio(options.clone()).await {
+            fatal("JSON-RPC error", e, None, None);
+        }
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    if let Some(path) = &args.serve_unix {
+        if let Err(e) =
+            async_tqsm::server::unix::serve_with(path, options.clone(), args.limits()).await
+        {
+            fatal("Unix socket server error", e, None, None);
+        }
+        return Ok(());
+    }
+
+    #[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+    let auth = match args.auth() {
+        Ok(auth) => auth,
+        Err(e) => fatal("Error loading auth tokens", e, None, None),
+    };
+    #[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+    let policy = args.policy();
+
+    #[cfg(feature = "http")]
+    if let Some(addr) = &args.serve {
+        use async_tqsm::server::http;
+        let (addr, options, limits) = (addr.as_str(), options.clone(), args.limits());
+        let (auth, policy) = (auth.clone(), policy.clone());
+        #[cfg(feature = "tls")]
+        let result = match args.tls() {
+            Some(tls) => http::serve_tls(addr, options, limits, auth, policy, tls).await,
+            None => http::serve_with(addr, options, limits, auth, policy).await,
+        };
+        #[cfg(not(feature = "tls"))]
+        let result = http::serve_with(addr, options, limits, auth, policy).await;
+        if let Err(e) = result {
+            fatal("HTTP server error", e, None, None);
+        }
+        return Ok(());
+    }
+
+    #[cfg(any(feature = "grpc", feature = "ws"))]
+    let metrics = async_tqsm::server::metrics::Metrics::new();
+    #[cfg(any(feature = "grpc", feature = "ws"))]
+    if let Some(addr) = args.admin_addr.clone() {
+        let metrics = metrics.clone();
+        let health = async_tqsm::server::health::Health::new(options.clone());
+        let auth = auth.clone();
+        tokio::spawn(async move {
+            let result =
+                async_tqsm::server::admin::serve(addr.as_str(), metrics, health, auth).await;
+            if let Err(e) = result {
+                fatal("Admin endpoint error", e, None, None);
+            }
+        });
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(addr) = args.serve_grpc {
+        use async_tqsm::server::grpc;
+        let (options, metrics, limits) = (options.clone(), metrics.clone(), args.limits());
+        let (auth, policy) = (auth.clone(), policy.clone());
+        #[cfg(feature = "tls")]
+        let result = match args.tls() {
+            Some(tls) => grpc::serve_tls(addr, options, metrics, limits, auth, policy, tls).await,
+            None => grpc::serve_with(addr, options, metrics, limits, auth, policy).await,
+        };
+        #[cfg(not(feature = "tls"))]
+        let result = grpc::serve_with(addr, options, metrics, limits, auth, policy).await;
+        if let Err(e) = result {
+            fatal("gRPC server error", e, None, None);
+        }
+        return Ok(());
+    }
+
+    #[cfg(feature = "ws")]
+    if let Some(addr) = &args.serve_ws {
+        use async_tqsm::server::ws;
+        let (addr, options, limits) = (addr.as_str(), options.clone(), args.limits());
+        let (auth, policy) = (auth.clone(), policy.clone());
+        #[cfg(feature = "tls")]
+        let result = match args.tls() {
+            Some(tls) => ws::serve_tls(addr, options, metrics, limits, auth, policy, tls).await,
+            None => ws::serve_with(addr, options, metrics, limits, auth, policy).await,
+        };
+        #[cfg(not(feature = "tls"))]
+        let result = ws::serve_with(addr, options, metrics, limits, auth, policy).await;
+        if let Err(e) = result {
+            fatal("WebSocket server error", e, None, None);
+        }
+        return Ok(());
+    }
+
+    // Message-bus service modes replace the file/stdin pipeline entirely
+    #[cfg(feature = "nats")]
+    if let (Some(url), Some(input), Some(output)) =
+        (&args.nats_url, &args.nats_input, &args.nats_output)
+    {
+        let result =
+            async_tqsm::connectors::nats::connect_and_segment(url, input, output, options.clone())
+                .await;
+        if let Err(e) = result {
+            fatal("NATS connector error", e, None, None);
+        }
+        return Ok(());
+    }
+
+    #[cfg(feature = "redis")]
+    if let (Some(url), Some(input), Some(output)) =
+        (&args.redis_url, &args.redis_input, &args.redis_output)
+    {
+        let result =
+            async_tqsm::connectors::redis::connect_and_segment(url, input, output, options.clone())
+                .await;
+        if let Err(e) = result {
+            fatal("Redis connector error", e, None, None);
+        }
+        return Ok(());
+    }
+
+    if let (Some(target_path), Some(source_path)) = (&args.bitext, &args.input_file) {
+        if let Err(e) = write_bitext(&args, source_path, target_path, options.clone()).await {
+            fatal("Bitext alignment error", e, None, None);
+        }
+        return Ok(());
+    }
+
+    if args.comments {
+        if let Err(e) = write_comments(&args, options.clone()).await {
+            fatal("Comment extraction error", e, None, None);
+        }
+        return Ok(());
+    }
+
+    if let Some(dir) = &args.input_dir {
+        if let Err(e) = write_dir(&args, dir, options.clone()).await {
+            fatal("Directory error", e, Some(&dir.display().to_string()), None);
+        }
+        return Ok(());
+    }
+
+    // Identifies the input in log events
+    let doc_id = match (&args.doc_id, &args.input_file) {
+        (Some(id), _) => id.clone(),
+        (None, Some(path)) => path.display().to_string(),
+        (None, None) => "stdin".to_string(),
+    };
 
     // Get the input reader
     let reader_result: Result<Box<dyn AsyncRead + Unpin + Send>, SegmenterError> =
-        match args.input_file {
+        match &args.input_file {
             Some(path) => match File::open(&path).await {
                 Ok(file) => Ok(Box::new(file)), // Wrap file directly
                 Err(e) => Err(SegmenterError::IoError(e)),
@@ -26,14 +334,13 @@ async fn main() -> anyhow::Result<()> {
     let reader = match reader_result {
         Ok(r) => BufReader::new(r), // <<< Wrap input in BufReader here
         Err(e) => {
-            eprintln!("Error opening input: {}", e);
-            exit(1);
+            fatal("Error opening input", e, Some(&doc_id), None);
         }
     };
 
     // Get the output writer
     let writer_result: Result<Box<dyn tokio::io::AsyncWrite + Unpin + Send>, SegmenterError> =
-        match args.output_file {
+        match &args.output_file {
             Some(path) => match File::create(&path).await {
                 Ok(file) => Ok(Box::new(file)),
                 Err(e) => Err(SegmenterError::IoError(e)),
@@ -44,43 +351,339 @@ async fn main() -> anyhow::Result<()> {
     let mut writer = match writer_result {
         Ok(w) => BufWriter::new(w),
         Err(e) => {
-            eprintln!("Error opening output: {}", e);
-            exit(1);
+            fatal("Error opening output", e, Some(&doc_id), None);
         }
     };
 
     // Create and process the stream
     // Pass the BufReader<impl AsyncRead> to the stream function
-    let stream = sentences_stream(reader, options);
+    let language = options.language.clone();
+    let mut subtitles = subtitles(&args, &language);
+    let mut selection = Selection::new(&args)?;
+    let stream = items_stream(reader, options);
     futures::pin_mut!(stream);
+    // End offset of the last sentence written
+    let mut offset = 0;
 
-    while let Some(sentence_result) = stream.next().await {
-        match sentence_result {
-            Ok(sentence) => {
-                if let Err(e) = writer.write_all(sentence.as_bytes()).await {
-                    eprintln!("Error writing to output: {}", e);
-                    exit(1);
+    while let Some(item_result) = stream.next().await {
+        match item_result {
+            Ok(item) => {
+                let segment = match item {
+                    StreamItem::Segment(segment) => segment,
+                    end @ StreamItem::End { .. } => {
+                        if args.end_marker && args.output_format == OutputFormat::Jsonl {
+                            let line = serde_json::to_string(&end)? + "\n";
+                            if let Err(e) = writer.write_all(line.as_bytes()).await {
+                                fatal(
+                                    "Error writing to output",
+                                    e.into(),
+                                    Some(&doc_id),
+                                    Some(offset),
+                                );
+                            }
+                        }
+                        continue;
+                    }
+                };
+                offset = segment.end;
+                if selection.skip(&doc_id, &segment) {
+                    continue;
+                }
+                let line = match args.output_format {
+                    OutputFormat::Text => segment.text,
+                    OutputFormat::Jsonl => {
+                        serde_json::to_string(&Record::new(&args, &doc_id, &segment, &language))?
+                    }
+                    OutputFormat::Srt => srt(&mut subtitles, &segment.text),
+                };
+                if let Err(e) = writer.write_all(line.as_bytes()).await {
+                    fatal(
+                        "Error writing to output",
+                        e.into(),
+                        Some(&doc_id),
+                        Some(offset),
+                    );
                 }
                 if let Err(e) = writer.write_all(b"\n").await {
-                    eprintln!("Error writing newline to output: {}", e);
-                    exit(1);
+                    fatal(
+                        "Error writing newline to output",
+                        e.into(),
+                        Some(&doc_id),
+                        Some(offset),
+                    );
                 }
                 if let Err(e) = writer.flush().await {
-                    eprintln!("Error flushing output: {}", e);
-                    exit(1);
+                    fatal(
+                        "Error flushing output",
+                        e.into(),
+                        Some(&doc_id),
+                        Some(offset),
+                    );
                 }
             }
             Err(e) => {
-                eprintln!("Error during segmentation: {}", e);
-                exit(1);
+                fatal("Error during segmentation", e, Some(&doc_id), Some(offset));
             }
         }
     }
 
     if let Err(e) = writer.flush().await {
-        eprintln!("Error flushing output buffer: {}", e);
-        exit(1);
+        fatal(
+            "Error flushing output buffer",
+            e.into(),
+            Some(&doc_id),
+            Some(offset),
+        );
+    }
+
+    Ok(())
+}
+
+/// Logs a fatal error with its kind and, for the segmentation pipeline, the document and offset
+/// it occurred at, then exits.
+fn fatal(context: &str, error: SegmenterError, doc_id: Option<&str>, offset: Option<usize>) -> ! {
+    // Prefer the exact position the error carries over the caller's last known offset
+    let offset = error.offset().or(offset);
+    tracing::error!(
+        error_kind = error.kind(),
+        doc_id,
+        offset,
+        "{}: {}",
+        context,
+        error
+    );
+    exit(1);
+}
+
+/// Segments and aligns a source file with its translation, writing one pair per line.
+async fn write_bitext(
+    args: &CliArgs,
+    source_path: &Path,
+    target_path: &Path,
+    options: SegmentOptions,
+) -> Result<(), SegmenterError> {
+    let source = tokio::fs::read_to_string(source_path).await?;
+    let target = tokio::fs::read_to_string(target_path).await?;
+    let target_options = SegmentOptions {
+        language: args
+            .target_language
+            .clone()
+            .unwrap_or_else(|| options.language.clone()),
+        ..options.clone()
+    };
+    let pairs = async_tqsm::align::segment_bitext(&source, &target, options, target_options)?;
+
+    let mut writer: Box<dyn tokio::io::AsyncWrite + Unpin + Send> = match &args.output_file {
+        Some(path) => Box::new(File::create(path).await?),
+        None => Box::new(io::stdout()),
+    };
+    for pair in pairs {
+        let line = format!(
+            "{}\t{}\t{}\t{}\n",
+            join_ids(&pair.source),
+            join_ids(&pair.target),
+            join_text(&pair.source),
+            join_text(&pair.target),
+        );
+        writer.write_all(line.as_bytes()).await?;
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Segments the comments of a source file, writing their sentences with file positions.
+async fn write_comments(args: &CliArgs, options: SegmentOptions) -> Result<(), SegmenterError> {
+    use async_tqsm::comments::{segment_source, CommentPreset};
+    use tokio::io::AsyncReadExt;
+
+    let source = match &args.input_file {
+        Some(path) => tokio::fs::read_to_string(path).await?,
+        None => {
+            let mut source = String::new();
+            io::stdin().read_to_string(&mut source).await?;
+            source
+        }
+    };
+    let preset = args.comment_syntax.unwrap_or_else(|| {
+        args.input_file
+            .as_deref()
+            .and_then(Path::extension)
+            .and_then(|extension| CommentPreset::for_extension(&extension.to_string_lossy()))
+            .unwrap_or(CommentPreset::C)
+    });
+    let mut syntax = preset.syntax();
+    syntax.line.extend(args.line_comment.iter().cloned());
+    syntax.block.extend(args.block_comment.iter().cloned());
+    let language = options.language.clone();
+    let sentences = segment_source(&source, &syntax, &options)?;
+    let mut subtitles = subtitles(args, &language);
+    let mut selection = Selection::new(args)?;
+
+    let doc_id = match (&args.doc_id, &args.input_file) {
+        (Some(id), _) => id.clone(),
+        (None, Some(path)) => path.display().to_string(),
+        (None, None) => "stdin".to_string(),
+    };
+    let mut writer: Box<dyn tokio::io::AsyncWrite + Unpin + Send> = match &args.output_file {
+        Some(path) => Box::new(File::create(path).await?),
+        None => Box::new(io::stdout()),
+    };
+    for segment in &sentences {
+        if selection.skip(&doc_id, segment) {
+            continue;
+        }
+        let line = match args.output_format {
+            OutputFormat::Text => segment.text.clone(),
+            OutputFormat::Jsonl => {
+                let record = Record {
+                    file: args.input_file.as_deref(),
+                    ..Record::new(args, &doc_id, segment, &language)
+                };
+                serde_json::to_string(&record)
+                    .map_err(|e| SegmenterError::StreamError(e.to_string()))?
+            }
+            OutputFormat::Srt => srt(&mut subtitles, &segment.text),
+        };
+        writer.write_all(line.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Segments every file below `dir`, `--jobs` at a time, and writes the sentences in path
+/// order, or in completion order with `--unordered`.
+async fn write_dir(
+    args: &CliArgs,
+    dir: &Path,
+    options: SegmentOptions,
+) -> Result<(), SegmenterError> {
+    use async_tqsm::dir::{segment_dir_parallel, segment_dir_unordered};
+
+    let language = options.language.clone();
+    let mut subtitles = subtitles(args, &language);
+    let mut selection = Selection::new(args)?;
+    let stream = if args.unordered {
+        segment_dir_unordered(dir, options, args.jobs).left_stream()
+    } else {
+        segment_dir_parallel(dir, options, args.jobs).right_stream()
+    };
+    futures::pin_mut!(stream);
+
+    let mut writer: Box<dyn tokio::io::AsyncWrite + Unpin + Send> = match &args.output_file {
+        Some(path) => Box::new(File::create(path).await?),
+        None => Box::new(io::stdout()),
+    };
+    while let Some(item) = stream.next().await {
+        let (file, segment) = item?;
+        let doc_id = file
+            .strip_prefix(dir)
+            .unwrap_or(&file)
+            .display()
+            .to_string();
+        if selection.skip(&doc_id, &segment) {
+            continue;
+        }
+        let line = match args.output_format {
+            OutputFormat::Text => segment.text,
+            OutputFormat::Jsonl => {
+                let record = Record {
+                    file: Some(&file),
+                    ..Record::new(args, &doc_id, &segment, &language)
+                };
+                serde_json::to_string(&record)
+                    .map_err(|e| SegmenterError::StreamError(e.to_string()))?
+            }
+            OutputFormat::Srt => srt(&mut subtitles, &segment.text),
+        };
+        writer.write_all(line.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
     }
+    writer.flush().await?;
+    Ok(())
+}
+
+fn join_ids(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|s| s.index.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn join_text(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Characters of context shown on each side of a differing boundary.
+const COMPARE_CONTEXT: usize = 30;
+
+/// Prints per-backend sentence counts followed by every boundary the backends disagree on.
+async fn print_comparison(
+    input: &Path,
+    backends: &[Backend],
+    options: &SegmentOptions,
+) -> Result<(), SegmenterError> {
+    let text = tokio::fs::read_to_string(input).await?;
+    let comparison = async_tqsm::compare::compare(&text, backends, options)?;
 
+    for (backend, count) in &comparison.counts {
+        println!("{:<8}{} sentences", backend.name(), count);
+    }
+    println!(
+        "{} shared boundaries, {} differing",
+        comparison.shared,
+        comparison.differences.len()
+    );
+
+    for diff in &comparison.differences {
+        let found: Vec<&str> = backends
+            .iter()
+            .map(|b| {
+                if diff.backends.contains(b) {
+                    b.name()
+                } else {
+                    "-"
+                }
+            })
+            .collect();
+        let before: String = text[..diff.offset]
+            .chars()
+            .rev()
+            .take(COMPARE_CONTEXT)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        let after: String = text[diff.offset..].chars().take(COMPARE_CONTEXT).collect();
+        println!(
+            "{:>8}  {:<16}{}|{}",
+            diff.offset,
+            found.join(" "),
+            before.replace('\n', " "),
+            after.replace('\n', " ")
+        );
+    }
     Ok(())
 }
+
+/// An audit sink writing each boundary as a JSON line to `path`.
+fn audit_file(path: &Path) -> std::io::Result<Audit> {
+    let file = std::io::LineWriter::new(std::fs::File::create(path)?);
+    let file = std::sync::Mutex::new(file);
+    Ok(Audit::new(move |boundary| {
+        use std::io::Write;
+
+        let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+        let written = serde_json::to_string(&boundary)
+            .map_err(std::io::Error::from)
+            .and_then(|line| writeln!(file, "{}", line));
+        if let Err(e) = written {
+            tracing::error!(error = %e, "failed to write audit record");
+        }
+    }))
+}
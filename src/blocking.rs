@@ -0,0 +1,134 @@
+//! Synchronous counterpart of the sentence streams for callers without an async runtime, such
+//! as scripts or Rayon batch jobs: [`sentences_iter`] reads any [`std::io::Read`] and applies the
+//! same decoding and segmentation rules.
+//!
+//! ```no_run
+//! # fn run() -> async_tqsm::Result<()> {
+//! use async_tqsm::{blocking, SegmentOptions};
+//!
+//! let file = std::fs::File::open("story.txt")?;
+//! for sentence in blocking::sentences_iter(file, SegmentOptions::default())? {
+//!     println!("{}", sentence?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! For text already in memory, [`Segmenter::segment_all`] needs no reader at all.
+
+use crate::chunking::ChunkSizer;
+use crate::config::SegmentOptions;
+use crate::decode::Utf8Decoder;
+use crate::error::{Result, SegmenterError};
+use crate::retry::RetryPolicy;
+use crate::segmenter::{Segment, Segmenter};
+use std::collections::VecDeque;
+use std::io::Read;
+
+/// Reads and segments `reader` on the calling thread, yielding one sentence per call to `next`.
+pub fn sentences_iter<R: Read>(
+    reader: R,
+    options: SegmentOptions,
+) -> Result<impl Iterator<Item = Result<String>>> {
+    Ok(SegmentsIter::new(reader, options)?.map(|segment| segment.map(|segment| segment.text)))
+}
+
+/// Like [`sentences_iter`], but yields each sentence as a [`Segment`] with its offsets.
+///
+/// Errors follow the streams: an I/O error, or undecodable input under
+/// [`Utf8Policy::Strict`](crate::decode::Utf8Policy::Strict), ends the input without flushing;
+/// a segmentation error is returned once and reading goes on. Reads are retried per
+/// [`SegmentOptions::retry`], sleeping the thread between attempts.
+pub struct SegmentsIter<R> {
+    reader: R,
+    segmenter: Segmenter,
+    decoder: Utf8Decoder,
+    sizer: ChunkSizer,
+    retry: RetryPolicy,
+    buffer: Vec<u8>,
+    /// Sentences found but not yet returned.
+    ready: VecDeque<Segment>,
+    /// Set once the reader is exhausted or failed; only `ready` is left.
+    done: bool,
+}
+
+impl<R: Read> SegmentsIter<R> {
+    pub fn new(reader: R, options: SegmentOptions) -> Result<Self> {
+        let sizer = ChunkSizer::for_options(&options);
+        Ok(Self {
+            reader,
+            decoder: Utf8Decoder::for_options(&options),
+            retry: options.retry.clone(),
+            buffer: vec![0; sizer.max_size()],
+            sizer,
+            segmenter: Segmenter::new(options)?,
+            ready: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    /// Reads one chunk, retrying transient errors, and segments it.
+    fn read_chunk(&mut self) -> Result<()> {
+        let mut failures = 0;
+        loop {
+            let started = std::time::Instant::now();
+            let size = self.sizer.size();
+            match self.reader.read(&mut self.buffer[..size]) {
+                Ok(0) => return self.finish(),
+                Ok(n) => {
+                    self.sizer.record(n, started.elapsed());
+                    return match self.decoder.decode(&self.buffer[..n]) {
+                        Ok(text) => self.feed(&text),
+                        Err(e) => {
+                            self.done = true;
+                            Err(e)
+                        }
+                    };
+                }
+                Err(e) => {
+                    failures += 1;
+                    if self.retry.should_retry(e.kind(), failures) {
+                        std::thread::sleep(self.retry.delay(failures));
+                        continue;
+                    }
+                    self.done = true;
+                    return Err(SegmenterError::IoError(e));
+                }
+            }
+        }
+    }
+
+    fn feed(&mut self, text: &str) -> Result<()> {
+        self.ready.extend(self.segmenter.feed_segments(text)?);
+        Ok(())
+    }
+
+    /// Ends the input: decodes a held-back partial character and flushes the segmenter.
+    fn finish(&mut self) -> Result<()> {
+        self.done = true;
+        let fed = match self.decoder.finish()? {
+            Some(tail) => self.feed(&tail),
+            None => Ok(()),
+        };
+        self.ready.extend(self.segmenter.finish_segments()?);
+        fed
+    }
+}
+
+impl<R: Read> Iterator for SegmentsIter<R> {
+    type Item = Result<Segment>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(segment) = self.ready.pop_front() {
+                return Some(Ok(segment));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(e) = self.read_chunk() {
+                return Some(Err(e));
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Adapters that segment HTTP response bodies directly, handling the frame-to-text conversion
+//! and characters split between frames internally.
+
+use crate::config::SegmentOptions;
+use crate::error::Result;
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+
+/// Segments any [`http_body::Body`] (hyper, axum, `http::Response<reqwest::Body>`, ...) into
+/// sentences. Trailers are ignored; a body error ends the stream like an I/O error.
+#[cfg(feature = "http-body")]
+pub fn sentences_from_body<'a, B>(
+    body: B,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<String>> + 'a
+where
+    B: http_body::Body + Send + 'a,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    use http_body_util::BodyExt;
+
+    let chunks = body
+        .into_data_stream()
+        .map(|frame| frame.map(copy_to_vec).map_err(std::io::Error::other));
+    crate::sentences_from_chunks(chunks, options)
+}
+
+/// Segments the body of a [`reqwest::Response`] as it downloads.
+#[cfg(feature = "reqwest")]
+pub fn sentences_from_response<'a>(
+    response: reqwest::Response,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<String>> + 'a {
+    sentences_from_bytes_stream(response.bytes_stream(), options)
+}
+
+/// Segments a stream of [`Bytes`] chunks, the shape most HTTP clients expose a body as (e.g.
+/// `reqwest::Response::bytes_stream`). A UTF-8 character split between two chunks is held back
+/// until the rest arrives; a chunk error ends the stream like an I/O error.
+pub fn sentences_from_bytes_stream<'a, S, E>(
+    chunks: S,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<String>> + 'a
+where
+    S: Stream<Item = std::result::Result<Bytes, E>> + Send + 'a,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let chunks = chunks.map(|chunk| chunk.map(Vec::from).map_err(std::io::Error::other));
+    crate::sentences_from_chunks(chunks, options)
+}
+
+#[cfg(feature = "http-body")]
+fn copy_to_vec<D: bytes::Buf>(mut data: D) -> Vec<u8> {
+    let mut bytes = vec![0; data.remaining()];
+    data.copy_to_slice(&mut bytes);
+    bytes
+}
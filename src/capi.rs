@@ -0,0 +1,212 @@
+//! Stable C ABI, enabled with the `capi` feature. The matching header is
+//! `include/async_tqsm.h`.
+//!
+//! All functions return one of the `TQSM_*` status codes. Sentences are delivered through a
+//! callback as a pointer/length pair that is only valid for the duration of the call.
+
+use crate::config::SegmentOptions;
+use crate::error::SegmenterError;
+use crate::segmenter::Segmenter;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+pub const TQSM_OK: c_int = 0;
+pub const TQSM_ERR_INVALID_ARGUMENT: c_int = 1;
+pub const TQSM_ERR_INVALID_UTF8: c_int = 2;
+pub const TQSM_ERR_UNSUPPORTED_LANGUAGE: c_int = 3;
+pub const TQSM_ERR_BUFFER_OVERFLOW: c_int = 4;
+pub const TQSM_ERR_INTERNAL: c_int = 5;
+
+/// Receives one sentence (UTF-8, not NUL-terminated) and the caller's `user_data`.
+pub type TqsmSentenceCallback =
+    extern "C" fn(sentence: *const c_char, len: usize, user_data: *mut c_void);
+
+/// Opaque segmenter handle owned by the C caller.
+pub struct TqsmSegmenter {
+    inner: Segmenter,
+    last_error: Option<CString>,
+    /// [`SegmenterError::code`] of the last failed call, 0 if it succeeded or panicked.
+    last_error_code: u32,
+}
+
+fn error_code(e: &SegmenterError) -> c_int {
+    match e {
+        SegmenterError::UnsupportedLanguage(_) => TQSM_ERR_UNSUPPORTED_LANGUAGE,
+        SegmenterError::BufferOverflow { .. } | SegmenterError::MemoryBudgetExceeded { .. } => {
+            TQSM_ERR_BUFFER_OVERFLOW
+        }
+        SegmenterError::Utf8Error { .. } => TQSM_ERR_INVALID_UTF8,
+        _ => TQSM_ERR_INTERNAL,
+    }
+}
+
+fn deliver(sentences: Vec<String>, callback: TqsmSentenceCallback, user_data: *mut c_void) {
+    for sentence in sentences {
+        callback(
+            sentence.as_ptr() as *const c_char,
+            sentence.len(),
+            user_data,
+        );
+    }
+}
+
+/// Runs `f` against the handle, recording the error message and turning panics into
+/// `TQSM_ERR_INTERNAL` so they never unwind into C.
+fn with_segmenter(
+    segmenter: *mut TqsmSegmenter,
+    f: impl FnOnce(&mut Segmenter) -> crate::Result<()>,
+) -> c_int {
+    // SAFETY: callers guarantee `segmenter` is null or a live handle from `tqsm_segmenter_new`
+    let Some(handle) = (unsafe { segmenter.as_mut() }) else {
+        return TQSM_ERR_INVALID_ARGUMENT;
+    };
+    match catch_unwind(AssertUnwindSafe(|| f(&mut handle.inner))) {
+        Ok(Ok(())) => {
+            handle.last_error = None;
+            handle.last_error_code = 0;
+            TQSM_OK
+        }
+        Ok(Err(e)) => {
+            let code = error_code(&e);
+            handle.last_error = CString::new(e.to_string()).ok();
+            handle.last_error_code = e.code();
+            code
+        }
+        Err(_) => {
+            handle.last_error = CString::new("panic inside async-tqsm").ok();
+            handle.last_error_code = 0;
+            TQSM_ERR_INTERNAL
+        }
+    }
+}
+
+/// Creates a segmenter and stores its handle in `*out`.
+///
+/// # Safety
+///
+/// `language` must be a valid NUL-terminated string and `out` a valid pointer to writable
+/// storage for one handle.
+#[no_mangle]
+pub unsafe extern "C" fn tqsm_segmenter_new(
+    language: *const c_char,
+    lookahead: usize,
+    max_buffer: usize,
+    out: *mut *mut TqsmSegmenter,
+) -> c_int {
+    if language.is_null() || out.is_null() {
+        return TQSM_ERR_INVALID_ARGUMENT;
+    }
+    *out = ptr::null_mut();
+    let Ok(language) = CStr::from_ptr(language).to_str() else {
+        return TQSM_ERR_INVALID_UTF8;
+    };
+    let options = SegmentOptions {
+        language: language.to_string(),
+        lookahead,
+        max_buffer,
+        ..Default::default()
+    };
+    match catch_unwind(AssertUnwindSafe(|| Segmenter::new(options))) {
+        Ok(Ok(inner)) => {
+            *out = Box::into_raw(Box::new(TqsmSegmenter {
+                inner,
+                last_error: None,
+                last_error_code: 0,
+            }));
+            TQSM_OK
+        }
+        Ok(Err(e)) => error_code(&e),
+        Err(_) => TQSM_ERR_INTERNAL,
+    }
+}
+
+/// Destroys a segmenter. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `segmenter` must be null or a handle from `tqsm_segmenter_new` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn tqsm_segmenter_free(segmenter: *mut TqsmSegmenter) {
+    if !segmenter.is_null() {
+        drop(Box::from_raw(segmenter));
+    }
+}
+
+/// Feeds `len` bytes of UTF-8 text and invokes `callback` for every completed sentence. A
+/// character split between two calls is held back until the rest of it arrives.
+///
+/// # Safety
+///
+/// `segmenter` must be a live handle and `text` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tqsm_feed(
+    segmenter: *mut TqsmSegmenter,
+    text: *const c_char,
+    len: usize,
+    callback: Option<TqsmSentenceCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    if text.is_null() && len > 0 {
+        return TQSM_ERR_INVALID_ARGUMENT;
+    }
+    let Some(callback) = callback else {
+        return TQSM_ERR_INVALID_ARGUMENT;
+    };
+    let bytes = if len == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(text as *const u8, len)
+    };
+    with_segmenter(segmenter, |inner| {
+        deliver(inner.feed_bytes(bytes)?, callback, user_data);
+        Ok(())
+    })
+}
+
+/// Ends the input: invokes `callback` with the sentences still buffered, if any. Fails with
+/// `TQSM_ERR_INVALID_UTF8` if the input ends in the middle of a character.
+///
+/// # Safety
+///
+/// `segmenter` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn tqsm_flush(
+    segmenter: *mut TqsmSegmenter,
+    callback: Option<TqsmSentenceCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    let Some(callback) = callback else {
+        return TQSM_ERR_INVALID_ARGUMENT;
+    };
+    with_segmenter(segmenter, |inner| {
+        deliver(inner.finish()?, callback, user_data);
+        Ok(())
+    })
+}
+
+/// Returns the message of the last failed call on this handle, or null. The string stays valid
+/// until the next call on the same handle.
+///
+/// # Safety
+///
+/// `segmenter` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn tqsm_last_error(segmenter: *const TqsmSegmenter) -> *const c_char {
+    match segmenter.as_ref().and_then(|h| h.last_error.as_ref()) {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Returns the stable code of the last failed call on this handle (see
+/// [`SegmenterError::code`]), or 0 if it succeeded, panicked, or `segmenter` is null. Unlike the
+/// `TQSM_ERR_*` status, it tells apart every kind of segmentation error.
+///
+/// # Safety
+///
+/// `segmenter` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn tqsm_last_error_code(segmenter: *const TqsmSegmenter) -> u32 {
+    segmenter.as_ref().map_or(0, |h| h.last_error_code)
+}
@@ -0,0 +1,118 @@
+//! Rules for chat and instant-messaging input ([`Mode::Chat`](crate::config::Mode::Chat)),
+//! where prose rules do badly: every line is a message of its own, emoji end sentences as often
+//! as punctuation does, sentences start in lower case, and URLs, @mentions and #hashtags are
+//! full of periods.
+//!
+//! Messages made only of emoji (`👍👍`) or of an interjection (`lol`, `hahaha`) never end in
+//! punctuation; they are emitted as soon as they are complete instead of waiting for more
+//! input, and split off the text that follows them.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// URLs, @mentions and #hashtags, which no sentence ends inside.
+pub(crate) const PROTECTED_PATTERNS: [&str; 3] = [
+    r#"(?:https?://|www\.)[^\s<>"]*[^\s<>".,!?;:)'’”]"#,
+    r"\B@[\w.-]*\w",
+    r"\B#\w+",
+];
+
+/// Whether `c` is an emoji or part of an emoji sequence (joiners, variation selectors, skin
+/// tones, keycaps).
+pub(crate) fn is_emoji(c: char) -> bool {
+    matches!(c,
+        '\u{1F000}'..='\u{1FAFF}'
+        | '\u{2600}'..='\u{27BF}'
+        | '\u{2B00}'..='\u{2BFF}'
+        | '\u{200D}'
+        | '\u{FE0F}'
+        | '\u{20E3}'
+        | '\u{E0020}'..='\u{E007F}')
+}
+
+/// End of the first line that has text on it, including the line break and any blank lines
+/// or indentation after it.
+pub(crate) fn line_break(text: &str) -> Option<usize> {
+    let start = text.len() - text.trim_start().len();
+    let newline = start + text[start..].find('\n')?;
+    Some(text.len() - text[newline..].trim_start().len())
+}
+
+/// End of the first run of emoji that is followed by whitespace and the capitalized start of
+/// the next sentence (`Loved it 😍 See you`), including that whitespace.
+pub(crate) fn emoji_boundary(text: &str) -> Option<usize> {
+    let mut search = 0;
+    while let Some(found) = text[search..].find(is_emoji) {
+        let after = text[search + found..].trim_start_matches(is_emoji);
+        let next = after.trim_start();
+        let starts_sentence = next.chars().next().is_some_and(char::is_uppercase);
+        if after.len() > next.len() && starts_sentence {
+            return Some(text.len() - next.len());
+        }
+        search = text.len() - after.len();
+    }
+    None
+}
+
+/// A boundary after the punctuation at `punctuation` when it is followed by whitespace, even
+/// if the next sentence starts in lower case. Punctuation after a word with periods inside
+/// (`e.g.`) or after a single letter is left to the language rules, as those are most likely
+/// abbreviations.
+pub(crate) fn lowercase_boundary(text: &str, punctuation: std::ops::Range<usize>) -> Option<usize> {
+    let after = &text[punctuation.end..];
+    if !after.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let word = text[..punctuation.start]
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or_default();
+    if word.contains('.') || word.chars().count() < 2 {
+        return None;
+    }
+    Some(text.len() - after.trim_start().len())
+}
+
+/// Whether `word` is a chat interjection such as `lol`, `lmao`, `hahaha` or `ugh`, optionally
+/// followed by punctuation.
+fn is_interjection(word: &str) -> bool {
+    static INTERJECTION: OnceLock<Regex> = OnceLock::new();
+    INTERJECTION
+        .get_or_init(|| {
+            Regex::new(
+                r"(?i)^(?:lo+l+|lmf?ao+|rofl|(?:ha){2,}h?|(?:he){2,}|omg+|wo+w|ya+y|u+gh|hm{2,}|brb|ttyl|thx)[!?.]*$",
+            )
+            .expect("valid interjection pattern")
+        })
+        .is_match(word)
+}
+
+/// Whether `text` is nothing but emoji (and spaces), not cut off in the middle of a sequence.
+fn is_emoji_only(text: &str) -> bool {
+    let text = text.trim();
+    !text.is_empty()
+        && !text.ends_with('\u{200D}')
+        && text.chars().all(|c| is_emoji(c) || c.is_whitespace())
+}
+
+/// End of a standalone message or message start: all of `text` when it is only emoji or a
+/// single interjection, or a leading run of emoji or an interjection followed by whitespace
+/// and the rest of the message (which must start with a capital letter or an emoji after an
+/// interjection, as in `lol Nice one`).
+pub(crate) fn standalone_boundary(text: &str) -> Option<usize> {
+    let content = text.trim();
+    if is_emoji_only(content) || is_interjection(content) {
+        return Some(text.len());
+    }
+    let start = text.len() - text.trim_start().len();
+    let first = text[start..].split(char::is_whitespace).next()?;
+    let next = text[start + first.len()..].trim_start();
+    if next.is_empty() {
+        return None;
+    }
+    let continues = next
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_uppercase() || is_emoji(c));
+    (is_emoji_only(first) || (is_interjection(first) && continues)).then(|| text.len() - next.len())
+}
@@ -0,0 +1,136 @@
+//! Size of the reads the sentence streams make. A fixed size is wrong for one of the two common
+//! inputs: small reads keep interactive pipes cheap, large ones keep fast files from costing a
+//! read and a segmenter call per few kilobytes. [`ReadSize::Adaptive`] measures how long each
+//! read takes and how full it comes back, and adjusts the size between reads.
+
+use crate::config::SegmentOptions;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A read that returns within this time found its data already waiting.
+const FAST_READ: Duration = Duration::from_millis(1);
+
+/// A read that takes longer than this waited for input to arrive.
+const SLOW_READ: Duration = Duration::from_millis(20);
+
+/// How much the sentence streams ask for per read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ReadSize {
+    /// Always this many bytes.
+    Fixed(usize),
+    /// Between `min` and `max` bytes, doubling after reads that filled the buffer without
+    /// waiting and halving after reads that waited for input or came back mostly empty.
+    Adaptive { min: usize, max: usize },
+}
+
+impl Default for ReadSize {
+    fn default() -> Self {
+        ReadSize::Fixed(4096)
+    }
+}
+
+impl ReadSize {
+    /// Adaptive sizing between 512 bytes and 256 KiB.
+    pub fn adaptive() -> Self {
+        ReadSize::Adaptive {
+            min: 512,
+            max: 256 * 1024,
+        }
+    }
+}
+
+/// Parses `auto` as [`ReadSize::adaptive`] and a byte count as [`ReadSize::Fixed`].
+impl FromStr for ReadSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ReadSize::adaptive()),
+            _ => match s.parse() {
+                Ok(0) | Err(_) => Err(format!("expected `auto` or a byte count, got `{}`", s)),
+                Ok(bytes) => Ok(ReadSize::Fixed(bytes)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ReadSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadSize::Fixed(bytes) => write!(f, "{}", bytes),
+            ReadSize::Adaptive { min, max } => write!(f, "auto ({}..{})", min, max),
+        }
+    }
+}
+
+/// Picks the size of each read according to a [`ReadSize`].
+#[derive(Debug, Clone)]
+pub struct ChunkSizer {
+    policy: ReadSize,
+    size: usize,
+}
+
+impl ChunkSizer {
+    /// Adaptive sizing starts at 4 KiB, within its bounds.
+    pub fn new(policy: ReadSize) -> Self {
+        let size = match policy {
+            ReadSize::Fixed(bytes) => bytes.max(1),
+            ReadSize::Adaptive { min, max } => 4096.clamp(min.max(1), max.max(min).max(1)),
+        };
+        Self { policy, size }
+    }
+
+    /// A sizer for `options.read_size`. Adaptive reads are kept to half of `max_buffer`, so a
+    /// chunk always fits next to the text still waiting for a boundary.
+    pub fn for_options(options: &SegmentOptions) -> Self {
+        let policy = match options.read_size {
+            ReadSize::Adaptive { min, max } => {
+                let limit = (options.max_buffer / 2).max(1);
+                ReadSize::Adaptive {
+                    min: min.min(limit),
+                    max: max.min(limit),
+                }
+            }
+            fixed => fixed,
+        };
+        Self::new(policy)
+    }
+
+    /// Bytes to ask for in the next read.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The largest size this sizer will ever ask for, for allocating the read buffer.
+    pub fn max_size(&self) -> usize {
+        match self.policy {
+            ReadSize::Fixed(_) => self.size,
+            ReadSize::Adaptive { min, max } => max.max(min).max(1),
+        }
+    }
+
+    /// Accounts for a read that returned `bytes` after `elapsed`.
+    pub fn record(&mut self, bytes: usize, elapsed: Duration) {
+        let ReadSize::Adaptive { min, .. } = self.policy else {
+            return;
+        };
+        let size = if bytes == self.size && elapsed <= FAST_READ {
+            (self.size * 2).min(self.max_size())
+        } else if elapsed >= SLOW_READ || bytes < self.size / 4 {
+            (self.size / 2).max(min.max(1))
+        } else {
+            self.size
+        };
+        #[cfg(feature = "tracing")]
+        if size != self.size {
+            tracing::trace!(from = self.size, to = size, "read size adjusted");
+        }
+        self.size = size;
+    }
+}
@@ -0,0 +1,86 @@
+//! A [`tokio_util::codec::Decoder`] that frames a byte stream into sentences, for pipelines
+//! already built on codecs (TCP sockets, framed files) rather than on
+//! [`segments_stream`](crate::segments_stream).
+//!
+//! ```no_run
+//! # async fn run(socket: tokio::net::TcpStream) -> async_tqsm::Result<()> {
+//! use async_tqsm::{codec::framed_sentences, SegmentOptions};
+//! use futures::StreamExt;
+//!
+//! let mut sentences = framed_sentences(socket, SegmentOptions::default())?;
+//! while let Some(segment) = sentences.next().await {
+//!     println!("{}", segment?.text);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::config::SegmentOptions;
+use crate::decode::Utf8Decoder;
+use crate::error::{Result, SegmenterError};
+use crate::segmenter::{Segment, Segmenter};
+use bytes::BytesMut;
+use std::collections::VecDeque;
+use tokio::io::AsyncRead;
+use tokio_util::codec::{Decoder, FramedRead};
+
+/// Decodes bytes into [`Segment`]s.
+///
+/// Every call takes all bytes available, so the read buffer never grows beyond one read; a
+/// character split across reads is held back until its remaining bytes arrive. Sentences wait
+/// for the lookahead like in the streams, and [`decode_eof`](Decoder::decode_eof) emits the
+/// rest once the input ends.
+pub struct SentenceDecoder {
+    segmenter: Segmenter,
+    decoder: Utf8Decoder,
+    /// Sentences found but not yet returned.
+    ready: VecDeque<Segment>,
+    /// Set once the end of the input has been handled.
+    finished: bool,
+}
+
+impl SentenceDecoder {
+    pub fn new(options: SegmentOptions) -> Result<Self> {
+        Ok(Self {
+            decoder: Utf8Decoder::for_options(&options),
+            segmenter: Segmenter::new(options)?,
+            ready: VecDeque::new(),
+            finished: false,
+        })
+    }
+}
+
+impl Decoder for SentenceDecoder {
+    type Item = Segment;
+    type Error = SegmenterError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Segment>> {
+        if self.ready.is_empty() && !src.is_empty() {
+            let text = self.decoder.decode(&src.split())?;
+            self.ready.extend(self.segmenter.feed_segments(&text)?);
+        }
+        Ok(self.ready.pop_front())
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Segment>> {
+        if let Some(segment) = self.decode(src)? {
+            return Ok(Some(segment));
+        }
+        if !self.finished {
+            self.finished = true;
+            if let Some(tail) = self.decoder.finish()? {
+                self.ready.extend(self.segmenter.feed_segments(&tail)?);
+            }
+            self.ready.extend(self.segmenter.finish_segments()?);
+        }
+        Ok(self.ready.pop_front())
+    }
+}
+
+/// `reader` framed by a [`SentenceDecoder`]: a stream of [`Segment`]s.
+pub fn framed_sentences<R: AsyncRead>(
+    reader: R,
+    options: SegmentOptions,
+) -> Result<FramedRead<R, SentenceDecoder>> {
+    Ok(FramedRead::new(reader, SentenceDecoder::new(options)?))
+}
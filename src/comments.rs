@@ -0,0 +1,267 @@
+//! Extraction of comments and docstrings from source code, so only the prose in a file is
+//! segmented, e.g. for documentation QA or comment translation.
+//!
+//! A run of line comments on consecutive lines is one comment, and so is every block comment;
+//! their lines are joined with single spaces after the comment markers (and the `*` that
+//! starts the lines of many block comments) are removed. The sentences returned by
+//! [`segment_source`] carry offsets, lines and columns in the source file.
+
+use crate::config::SegmentOptions;
+use crate::error::Result;
+use crate::segmenter::{segment_document, Segment};
+
+/// How comments and string literals are written in a language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommentSyntax {
+    /// Markers that start a comment running to the end of the line, e.g. `//`.
+    pub line: Vec<String>,
+    /// Opening and closing markers of block comments, e.g. `/*` and `*/`. Python docstrings
+    /// are written as `"""` blocks.
+    pub block: Vec<(String, String)>,
+    /// Characters delimiting string literals, which are skipped so a marker inside a string
+    /// does not start a comment.
+    pub string_quotes: Vec<char>,
+}
+
+/// Built-in comment syntaxes, for `--comment-syntax`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum CommentPreset {
+    /// `//` and `/* */`: C, C++, C#, Rust, Go, Java, JavaScript, TypeScript, Swift, Kotlin.
+    C,
+    /// `#` and `"""`/`'''` docstrings.
+    Python,
+    /// `#`: shell, Ruby, Perl, YAML, TOML, R.
+    Shell,
+    /// `<!-- -->`: HTML, XML, Markdown.
+    Html,
+    /// `--` and `/* */`: SQL, Lua, Haskell, Ada.
+    Sql,
+}
+
+impl CommentPreset {
+    /// The preset commonly used for files with extension `extension` (without the dot).
+    pub fn for_extension(extension: &str) -> Option<Self> {
+        Some(match extension.to_ascii_lowercase().as_str() {
+            "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "rs" | "go" | "java" | "js" | "mjs"
+            | "jsx" | "ts" | "tsx" | "swift" | "kt" | "kts" | "scala" | "dart" | "php" => {
+                CommentPreset::C
+            }
+            "py" | "pyi" => CommentPreset::Python,
+            "sh" | "bash" | "zsh" | "rb" | "pl" | "yaml" | "yml" | "toml" | "r" | "ps1" => {
+                CommentPreset::Shell
+            }
+            "html" | "htm" | "xml" | "svg" | "md" | "vue" => CommentPreset::Html,
+            "sql" | "lua" | "hs" | "adb" | "ads" => CommentPreset::Sql,
+            _ => return None,
+        })
+    }
+
+    /// The comment syntax of the preset.
+    pub fn syntax(self) -> CommentSyntax {
+        let strings = |s: &[&str]| s.iter().map(|s| s.to_string()).collect();
+        let blocks = |b: &[(&str, &str)]| {
+            b.iter()
+                .map(|(open, close)| (open.to_string(), close.to_string()))
+                .collect()
+        };
+        match self {
+            CommentPreset::C => CommentSyntax {
+                line: strings(&["//"]),
+                block: blocks(&[("/*", "*/")]),
+                string_quotes: vec!['"'],
+            },
+            CommentPreset::Python => CommentSyntax {
+                line: strings(&["#"]),
+                block: blocks(&[("\"\"\"", "\"\"\""), ("'''", "'''")]),
+                string_quotes: vec!['"', '\''],
+            },
+            CommentPreset::Shell => CommentSyntax {
+                line: strings(&["#"]),
+                block: Vec::new(),
+                string_quotes: vec!['"', '\''],
+            },
+            CommentPreset::Html => CommentSyntax {
+                line: Vec::new(),
+                block: blocks(&[("<!--", "-->")]),
+                string_quotes: Vec::new(),
+            },
+            CommentPreset::Sql => CommentSyntax {
+                line: strings(&["--"]),
+                block: blocks(&[("/*", "*/")]),
+                string_quotes: vec!['\''],
+            },
+        }
+    }
+}
+
+/// The text of one comment and where its pieces came from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comment {
+    /// The comment without its markers, lines joined with single spaces.
+    pub text: String,
+    /// Byte offsets at which each piece of `text` starts, in `text` and in the source.
+    pieces: Vec<(usize, usize)>,
+}
+
+impl Comment {
+    fn push(&mut self, source_offset: usize, piece: &str) {
+        if piece.is_empty() {
+            return;
+        }
+        if !self.text.is_empty() {
+            self.text.push(' ');
+        }
+        self.pieces.push((self.text.len(), source_offset));
+        self.text.push_str(piece);
+    }
+
+    /// Source offset of the position `offset` in `text`; `end` maps a position between two
+    /// pieces to the end of the earlier one.
+    fn source_offset(&self, offset: usize, end: bool) -> usize {
+        let (text_start, source_start) = self
+            .pieces
+            .iter()
+            .rev()
+            .find(|&&(start, _)| if end { start < offset } else { start <= offset })
+            .copied()
+            .unwrap_or_default();
+        source_start + offset - text_start
+    }
+}
+
+/// Cleans up one line of a comment and records it, `offset` being where `line` starts in the
+/// source.
+fn push_line(comment: &mut Comment, offset: usize, line: &str) {
+    let content = line
+        .trim_start()
+        .trim_start_matches(['/', '!', '#', '*', '-'])
+        .trim_start();
+    comment.push(offset + line.len() - content.len(), content.trim_end());
+}
+
+/// Finds the comments in `source`, in order.
+pub fn extract_comments(source: &str, syntax: &CommentSyntax) -> Vec<Comment> {
+    let mut comments = Vec::new();
+    // Line comment being extended by the comments on the following lines
+    let mut open_line: Option<(Comment, usize)> = None;
+    let mut line_number = 0;
+    let mut position = 0;
+
+    while position < source.len() {
+        let rest = &source[position..];
+        if let Some(marker) = syntax.line.iter().find(|m| rest.starts_with(m.as_str())) {
+            let body_start = position + marker.len();
+            let body_end = source[body_start..]
+                .find('\n')
+                .map_or(source.len(), |i| body_start + i);
+            let starts_line = source[..position]
+                .rsplit('\n')
+                .next()
+                .is_some_and(|before| before.trim().is_empty());
+            let mut comment = match open_line.take() {
+                Some((comment, line)) if starts_line && line + 1 == line_number => comment,
+                previous => {
+                    comments.extend(previous.map(|(comment, _)| comment));
+                    Comment::default()
+                }
+            };
+            push_line(&mut comment, body_start, &source[body_start..body_end]);
+            open_line = Some((comment, line_number));
+            position = body_end;
+        } else if let Some((open, close)) = syntax
+            .block
+            .iter()
+            .find(|(open, _)| rest.starts_with(open.as_str()))
+        {
+            if let Some((comment, _)) = open_line.take() {
+                comments.push(comment);
+            }
+            let body_start = position + open.len();
+            let body_end = source[body_start..]
+                .find(close.as_str())
+                .map_or(source.len(), |i| body_start + i);
+            let mut comment = Comment::default();
+            let mut line_start = body_start;
+            for line in source[body_start..body_end].split('\n') {
+                push_line(&mut comment, line_start, line);
+                line_start += line.len() + 1;
+            }
+            line_number += source[body_start..body_end].matches('\n').count();
+            comments.push(comment);
+            position = (body_end + close.len()).min(source.len());
+        } else if let Some(quote) = rest
+            .chars()
+            .next()
+            .filter(|c| syntax.string_quotes.contains(c))
+        {
+            position += skip_string(rest, quote);
+        } else {
+            let c = rest.chars().next().unwrap_or_default();
+            if c == '\n' {
+                line_number += 1;
+            }
+            position += c.len_utf8();
+        }
+    }
+    comments.extend(open_line.map(|(comment, _)| comment));
+    comments.retain(|comment| !comment.text.is_empty());
+    comments
+}
+
+/// Length of the string literal at the start of `text`, up to and including the closing
+/// `quote` or the end of the line.
+fn skip_string(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            '\n' => return i,
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            _ if c == quote => return i + c.len_utf8(),
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// Segments the comments in `source` into sentences whose offsets, lines and columns refer to
+/// `source`; indexes count the sentences of all comments.
+pub fn segment_source(
+    source: &str,
+    syntax: &CommentSyntax,
+    options: &SegmentOptions,
+) -> Result<Vec<Segment>> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let mut sentences = Vec::new();
+    // Character offset of a byte offset, counted on from the previous one since sentences come
+    // in source order
+    let mut counted = (0, 0);
+    let mut char_offset = |byte: usize| {
+        let (from_byte, from_char) = if byte >= counted.0 { counted } else { (0, 0) };
+        counted = (byte, from_char + source[from_byte..byte].chars().count());
+        counted.1
+    };
+    for comment in extract_comments(source, syntax) {
+        for segment in segment_document(&comment.text, options.clone())? {
+            let start = comment.source_offset(segment.start, false);
+            let end = comment.source_offset(segment.end, true);
+            let line = line_starts.partition_point(|&line_start| line_start <= start);
+            let column = source[line_starts[line - 1]..start].chars().count() + 1;
+            sentences.push(Segment {
+                index: sentences.len(),
+                start,
+                end,
+                char_start: char_offset(start),
+                char_end: char_offset(end),
+                line,
+                column,
+                ..segment
+            });
+        }
+    }
+    Ok(sentences)
+}
@@ -0,0 +1,135 @@
+//! Boundary comparison between segmentation backends, to help choose the right one per language.
+
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use crate::segmenter::segment_document;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A sentence segmentation backend that can take part in a comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Backend {
+    /// The tqsm rule set used everywhere else in this crate.
+    Tqsm,
+    /// Unicode default sentence boundaries (UAX #29), without language rules.
+    Uax29,
+    /// ICU4X's sentence-break rules with its language tailorings, as with
+    /// [`Backend::Icu`](crate::backend::Backend::Icu).
+    #[cfg(feature = "icu")]
+    Icu,
+}
+
+impl Backend {
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Tqsm => "tqsm",
+            Backend::Uax29 => "uax29",
+            #[cfg(feature = "icu")]
+            Backend::Icu => "icu",
+        }
+    }
+
+    /// Byte offsets in `text` where this backend ends a sentence, excluding trailing spaces.
+    pub fn boundaries(self, text: &str, options: &SegmentOptions) -> Result<Vec<usize>> {
+        match self {
+            Backend::Tqsm => engine_boundaries(text, options, crate::backend::Backend::Tqsm),
+            #[cfg(feature = "icu")]
+            Backend::Icu => engine_boundaries(text, options, crate::backend::Backend::Icu),
+            Backend::Uax29 => Ok(text
+                .split_sentence_bound_indices()
+                .filter(|(_, sentence)| !sentence.trim().is_empty())
+                .map(|(start, sentence)| start + sentence.trim_end().len())
+                .collect()),
+        }
+    }
+}
+
+/// Boundaries of a whole-document segmentation with `engine` in place of `options.backend`.
+fn engine_boundaries(
+    text: &str,
+    options: &SegmentOptions,
+    engine: crate::backend::Backend,
+) -> Result<Vec<usize>> {
+    let options = SegmentOptions {
+        backend: engine,
+        ..options.clone()
+    };
+    Ok(segment_document(text, options)?
+        .into_iter()
+        .filter(|segment| !segment.text.trim().is_empty())
+        .map(|segment| segment.start + segment.text.trim_end().len())
+        .collect())
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = SegmenterError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tqsm" => Ok(Backend::Tqsm),
+            "uax29" => Ok(Backend::Uax29),
+            #[cfg(feature = "icu")]
+            "icu" => Ok(Backend::Icu),
+            #[cfg(not(feature = "icu"))]
+            "icu" => Err(SegmenterError::InvalidOptions(
+                "the ICU4X backend needs the `icu` feature".to_string(),
+            )),
+            other => Err(SegmenterError::UnsupportedBackend(other.to_string())),
+        }
+    }
+}
+
+/// A boundary offset and the backends that placed a sentence break there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundaryDiff {
+    pub offset: usize,
+    pub backends: Vec<Backend>,
+}
+
+/// Result of segmenting one text with several backends.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// Number of sentences each backend produced, in the order the backends were given.
+    pub counts: Vec<(Backend, usize)>,
+    /// Boundaries every backend agrees on.
+    pub shared: usize,
+    /// Boundaries found by some but not all backends, in text order.
+    pub differences: Vec<BoundaryDiff>,
+}
+
+/// Segments `text` with each backend and collects where their boundaries disagree.
+pub fn compare(text: &str, backends: &[Backend], options: &SegmentOptions) -> Result<Comparison> {
+    let mut counts = Vec::with_capacity(backends.len());
+    let mut by_offset: BTreeMap<usize, Vec<Backend>> = BTreeMap::new();
+    for &backend in backends {
+        let boundaries = backend.boundaries(text, options)?;
+        counts.push((backend, boundaries.len()));
+        for offset in boundaries {
+            by_offset.entry(offset).or_default().push(backend);
+        }
+    }
+
+    let shared = by_offset
+        .values()
+        .filter(|found| found.len() == backends.len())
+        .count();
+    let differences = by_offset
+        .into_iter()
+        .filter(|(_, found)| found.len() < backends.len())
+        .map(|(offset, backends)| BoundaryDiff { offset, backends })
+        .collect();
+
+    Ok(Comparison {
+        counts,
+        shared,
+        differences,
+    })
+}
@@ -1,24 +1,97 @@
+#[cfg(feature = "cli")]
 use clap::Parser;
+#[cfg(feature = "cli")]
 use std::path::PathBuf;
 
 /// Asynchronous, streaming sentence segmenter based on tqsm
+#[cfg(feature = "cli")]
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct CliArgs {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Minimum lookahead (in characters) required before finalizing a sentence.
-    /// Lower values mean lower latency but potentially lower accuracy.
-    #[arg(long, value_name = "CHARS", default_value_t = 10)]
-    pub lookahead: usize,
+    /// Lower values mean lower latency but potentially lower accuracy. [default: 10, 3 for
+    /// Chinese, Japanese and Korean, or the `--profile`'s]
+    #[arg(long, value_name = "CHARS")]
+    pub lookahead: Option<usize>,
 
     /// Maximum internal buffer size in characters. Helps prevent excessive memory use.
-    /// May force splits if exceeded, potentially impacting accuracy.
-    #[arg(long, value_name = "CHARS", default_value_t = 8192)] // Increased default
-    pub max_buffer: usize,
+    /// May force splits if exceeded, potentially impacting accuracy. [default: 8192, or the
+    /// `--profile`'s]
+    #[arg(long, value_name = "CHARS")]
+    pub max_buffer: Option<usize>,
+
+    /// Text without a sentence boundary that fills `--max-buffer`: `error` stops, `whitespace`
+    /// and `grapheme` write it up to its last whitespace or grapheme cluster as a sentence and
+    /// go on. [default: error]
+    #[arg(long, value_enum)]
+    pub overflow_policy: Option<crate::OverflowPolicy>,
+
+    /// Split sentences longer than this many bytes after a clause mark, at whitespace or
+    /// between grapheme clusters; 0 disables the limit. [default: 0, or 500 with `--profile
+    /// tts-low-latency`]
+    #[arg(long, value_name = "BYTES")]
+    pub max_sentence_len: Option<usize>,
+
+    /// Merge sentences shorter than this many bytes with the following sentence; 0 disables
+    /// merging.
+    #[arg(long, value_name = "BYTES", default_value_t = 0)]
+    pub min_sentence_len: usize,
+
+    /// Start from the settings of a use case; explicit options still take precedence.
+    #[arg(long, value_enum)]
+    pub profile: Option<Profile>,
+
+    /// Kind of input: `prose`; `log` (every line is a record, its time stamp and level are
+    /// stripped); or `chat` (every line is a message, emoji end sentences, lower case starts
+    /// are accepted, URLs, @mentions and #hashtags are protected). [default: prose, or chat
+    /// with `--profile chat`]
+    #[arg(long, value_enum)]
+    pub mode: Option<Mode>,
+
+    /// What a segment is: a `sentence`; a `paragraph`, delimited by blank lines; or `nested`,
+    /// sentences that also end at blank lines and carry the index of their paragraph
+    /// (`paragraph` in `jsonl` records).
+    #[arg(long, value_enum, default_value_t = SegmentUnit::Sentence, global = true)]
+    pub unit: SegmentUnit,
+
+    /// Regex for the time stamp, level and other prefix of each line that `--mode log` leaves
+    /// out of the sentences. [default: ISO 8601, syslog or bare times, then levels like `INFO`
+    /// or `[warn]`, then a `[target]` or `module:` tag]
+    #[arg(long, value_name = "REGEX")]
+    pub log_prefix: Option<String>,
 
-    /// Language code for segmentation rules (e.g., "en", "de", "es").
-    #[arg(long, short, value_name = "CODE", default_value = "en")]
+    /// Language code for segmentation rules (e.g., "en", "de", "es"), or `auto` to detect it
+    /// from the start of the input.
+    #[arg(long, short, value_name = "CODE", default_value = "en", global = true)]
     pub language: String,
 
+    /// Bytes of input to detect the language from with `--language auto`.
+    #[arg(long, value_name = "BYTES", default_value_t = 512, global = true)]
+    pub detection_window: usize,
+
+    /// Detect the language of every sentence, for text that mixes languages: `tag` adds it to
+    /// `jsonl` records, `switch` also segments with the detected language's rules.
+    #[arg(long, value_enum, default_value_t = crate::detect::SentenceLanguage::Off, global = true)]
+    pub sentence_language: crate::detect::SentenceLanguage,
+
+    /// Comma-separated languages `--sentence-language` chooses from, e.g. `de,en`. Without them
+    /// a sentence is only tagged if it is long enough to tell among all languages.
+    #[arg(long, value_name = "CODES", value_delimiter = ',', global = true)]
+    pub sentence_languages: Vec<String>,
+
+    /// Use this language's rules, with a warning, when `--language` has none instead of
+    /// failing, e.g. `en` for batch jobs over many languages.
+    #[arg(long, value_name = "CODE", global = true)]
+    pub fallback_language: Option<String>,
+
+    /// Rule engine: `tqsm` (the language's libtqsm rules) or `icu` (Unicode sentence breaks
+    /// from ICU4X, for any language; needs the `icu` feature).
+    #[arg(long, value_enum, default_value_t = crate::backend::Backend::Tqsm, global = true)]
+    pub backend: crate::backend::Backend,
+
     /// Optional input file path. If not provided, reads from stdin.
     #[arg(long, short, value_name = "FILE")]
     pub input_file: Option<PathBuf>,
@@ -26,6 +99,488 @@ pub struct CliArgs {
     /// Optional output file path. If not provided, writes to stdout.
     #[arg(long, short, value_name = "FILE")]
     pub output_file: Option<PathBuf>,
+
+    /// Output format: plain sentences, one per line, or JSON lines with index and byte offsets.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    /// Add an estimated speaking duration (`duration_ms`) to each `jsonl` record.
+    #[arg(long)]
+    pub durations: bool,
+
+    /// Document identifier in `jsonl` records. Defaults to the `--input-file` path, or
+    /// `stdin`; in `--input-dir` mode each file's path relative to the directory is used.
+    #[arg(long, value_name = "ID", conflicts_with = "input_dir")]
+    pub doc_id: Option<String>,
+
+    /// Add a stable hash of each sentence's text (`hash`) to `jsonl` records; optionally
+    /// `--content-hash=xxh3` or `=sha1` instead of the default FNV-1a.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ALGORITHM",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "fnv"
+    )]
+    pub content_hash: Option<crate::ids::HashAlgorithm>,
+
+    /// Keep only the first N hex digits of `--content-hash`.
+    #[arg(long, value_name = "N", requires = "content_hash")]
+    pub hash_prefix: Option<usize>,
+
+    /// Drop sentences whose SimHash differs in at most BITS bits from one of the last
+    /// `--dedup-window` sentences written (0 drops only repeats equal up to case, digits and
+    /// punctuation).
+    #[arg(long, value_name = "BITS")]
+    pub dedup: Option<u32>,
+
+    /// Number of recent sentences `--dedup` compares against (8 bytes each).
+    #[arg(long, value_name = "N", default_value_t = 10_000, requires = "dedup")]
+    pub dedup_window: usize,
+
+    /// Write only a random share of the sentences, e.g. 0.01 for one in a hundred. The
+    /// selection depends on `--seed` and each sentence's `doc_id#index`, so it is reproducible.
+    #[arg(long, value_name = "RATE", value_parser = parse_rate)]
+    pub sample: Option<f64>,
+
+    /// Seed for `--sample`.
+    #[arg(long, default_value_t = 0, requires = "sample")]
+    pub seed: u64,
+
+    /// Write one JSON line per accepted sentence boundary to FILE: its offset, the rule that
+    /// matched, the lookahead available and a confidence score.
+    #[arg(long, value_name = "FILE")]
+    pub audit: Option<PathBuf>,
+
+    /// End `jsonl` output with an `{"end": ...}` record holding the flushed tail and stream
+    /// statistics, written only if the input was read completely.
+    #[arg(long)]
+    pub end_marker: bool,
+
+    /// Speaking rate for `--durations` and `srt` timing, in words per minute (characters per
+    /// minute for Chinese, Japanese, Korean, Thai, Lao and Khmer). Defaults to a per-language
+    /// rate.
+    #[arg(long, value_name = "RATE", value_parser = parse_speaking_rate)]
+    pub wpm: Option<f64>,
+
+    /// Maximum subtitle line width in columns for `--output-format srt`.
+    #[arg(long, value_name = "COLUMNS", default_value_t = 42)]
+    pub subtitle_width: usize,
+
+    /// Maximum lines per subtitle cue for `--output-format srt`; longer sentences are spread
+    /// over several cues.
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    pub subtitle_lines: usize,
+
+    /// Bytes requested per read, or `auto` to adapt between 512 bytes and 256 KiB: small for
+    /// interactive pipes, large for fast files.
+    #[arg(long, value_name = "BYTES|auto", default_value_t = crate::chunking::ReadSize::default())]
+    pub read_size: crate::chunking::ReadSize,
+
+    /// Invalid UTF-8 in the input: `strict` stops with an error, `skip` drops the bad bytes,
+    /// `replace` substitutes U+FFFD. With `skip` and `replace`, offsets refer to the decoded text.
+    #[arg(long, value_enum, default_value_t = crate::decode::Utf8Policy::Strict)]
+    pub utf8_policy: crate::decode::Utf8Policy,
+
+    /// Encoding of the input, as a WHATWG label such as `windows-1252` or `shift_jis`; it is
+    /// transcoded to UTF-8 before segmentation, with `--utf8-policy` deciding about undecodable
+    /// bytes. Needs the `encoding` feature. [default: utf-8]
+    #[arg(long, value_name = "LABEL")]
+    pub encoding: Option<String>,
+
+    /// Let a byte order mark at the start of the input override `--encoding`, and drop it.
+    /// Needs the `encoding` feature.
+    #[arg(long)]
+    pub sniff_bom: bool,
+
+    /// Text left at the end of the input without a sentence boundary: `emit` it as a sentence,
+    /// `drop` it, or `mark` it (`"incomplete": true` in `jsonl` output). [default: emit, or
+    /// the `--profile`'s]
+    #[arg(long, value_enum)]
+    pub tail: Option<crate::TailPolicy>,
+
+    /// Write the buffered text as a sentence once the input has been idle for this many
+    /// milliseconds, instead of waiting for the sentence to end. [default: off, or 500 with
+    /// `--profile tts-low-latency`]
+    #[arg(long, value_name = "MS")]
+    pub flush_after: Option<u64>,
+
+    /// Don't split inside quotes and brackets opened at most this many bytes earlier, even
+    /// across chunks; longer ones are treated as unbalanced. 0 disables the check.
+    #[arg(long, value_name = "BYTES", default_value_t = 1000)]
+    pub max_quote_span: usize,
+
+    /// Maximum nesting of quotes and brackets tracked for `--max-quote-span`.
+    #[arg(long, value_name = "DEPTH", default_value_t = 8)]
+    pub max_quote_depth: usize,
+
+    /// Closing quote or bracket that does not match the innermost open one: `ignore` it (e.g.
+    /// apostrophes), `close-to-match` the enclosing one it belongs to, or `reset` all of them.
+    #[arg(long, value_enum, default_value_t = crate::quotes::QuoteRecovery::Ignore)]
+    pub quote_recovery: crate::quotes::QuoteRecovery,
+
+    /// Let footnote markers after sentence punctuation (`word.[12]`, `word.¹`) start the next
+    /// sentence instead of keeping them with the one they follow.
+    #[arg(long)]
+    pub no_footnotes: bool,
+
+    /// Split inside parenthetical citations (`(Smith et al., 2020)`) and start the next
+    /// sentence with a citation that follows the sentence punctuation.
+    #[arg(long)]
+    pub no_citations: bool,
+
+    /// When a period after an initial (`J. R. R. Tolkien`) does not end a sentence: `off`,
+    /// `conservative` (chains of initials, or one opening the sentence) or `aggressive` (any
+    /// initial before a capitalized word).
+    #[arg(long, value_enum, default_value_t = crate::protect::Strictness::Conservative)]
+    pub initials: crate::protect::Strictness,
+
+    /// When a period after a Roman numeral does not end a sentence: `off`, `conservative`
+    /// (after `Chapter`, `Part`, `Vol.` etc.) or `aggressive` (any numeral except `I`).
+    #[arg(long, value_enum, default_value_t = crate::protect::Strictness::Conservative)]
+    pub roman_numerals: crate::protect::Strictness,
+
+    /// Split inside times and dates like `5 p.m.`, `Jan. 5, 2024` or `No. 7`.
+    #[arg(long)]
+    pub no_dates: bool,
+
+    /// Ordinal numbers written with a period (`Am 3. Mai`): `off`, `conservative` (day numbers
+    /// before a month, numeric dates) or `aggressive` (any number before a word, for legal
+    /// text). Defaults to `conservative` for languages that write ordinals this way.
+    #[arg(long, value_enum)]
+    pub ordinal_dates: Option<crate::protect::Strictness>,
+
+    /// Lexicons of abbreviated units and quantities to protect, comma-separated: `general`,
+    /// `cooking`, `science`, `finance`.
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "general",
+        conflicts_with = "no_measurements"
+    )]
+    pub measurements: Vec<crate::protect::MeasurementDomain>,
+
+    /// Disable all measurement lexicons.
+    #[arg(long)]
+    pub no_measurements: bool,
+
+    /// File of regexes for spans no sentence ends inside, one per line, with `#` comments and
+    /// `[language]` headers for patterns that only apply to that language. May be given several
+    /// times.
+    #[arg(long, value_name = "FILE")]
+    pub rules_file: Vec<PathBuf>,
+
+    /// End a sentence after every match of REGEX, e.g. `' • '`. May be given several times.
+    #[arg(long, value_name = "REGEX")]
+    pub split_after: Vec<String>,
+
+    /// Start a sentence at every match of REGEX, e.g. `'\[\d\d:\d\d\] '` for chat-log
+    /// prefixes. May be given several times.
+    #[arg(long, value_name = "REGEX")]
+    pub split_before: Vec<String>,
+
+    /// Never end a sentence inside or right after a match of REGEX, e.g. `'\bapprox\.'`. May
+    /// be given several times.
+    #[arg(long, value_name = "REGEX")]
+    pub no_split: Vec<String>,
+
+    /// Keep the leading spaces of each sentence (indentation) instead of trimming them.
+    #[arg(long)]
+    pub keep_indentation: bool,
+
+    /// Apply the terminators of other scripts (Thai, Devanagari, Armenian, …) to runs of that
+    /// script inside the text, not just those of `--language`.
+    #[arg(long)]
+    pub mixed_scripts: bool,
+
+    /// Remove Markdown emphasis, backticks, heading markers and bullets from the sentences, for
+    /// speech synthesis; offsets still refer to the input.
+    #[arg(long)]
+    pub strip_markup: bool,
+
+    /// Spell out numbers and expand abbreviations such as "Dr." with the built-in English
+    /// rules, for speech synthesis (after `--strip-markup`).
+    #[arg(long)]
+    pub verbalize: bool,
+
+    /// Log a warning for sentences longer than this many bytes; 0 disables the check.
+    /// [default: 2000, or the `--profile`'s]
+    #[arg(long, value_name = "BYTES")]
+    pub long_sentence_warning: Option<usize>,
+
+    /// Moses-style non-breaking prefix file: one abbreviation per line, `#` comments. May be
+    /// given several times. The files are watched and reloaded when they change, also for
+    /// sessions already running in the server modes.
+    #[arg(long, value_name = "FILE")]
+    pub abbrev_file: Vec<PathBuf>,
+
+    /// Treat WORD as an ordinary word even if the language's rules or `--abbrev-file` list it
+    /// as an abbreviation, so a period after it ends the sentence. May be given several times.
+    #[arg(long, value_name = "WORD")]
+    pub remove_abbrev: Vec<String>,
+
+    /// How often to check `--abbrev-file` for changes, in seconds; 0 disables reloading.
+    #[arg(long, value_name = "SECS", default_value_t = 2)]
+    pub reload_interval: u64,
+
+    /// Log format on stderr: human-readable text or one JSON object per line. Verbosity follows
+    /// `RUST_LOG` (default `async_tqsm=info`).
+    #[arg(long, value_enum, default_value_t = crate::logging::LogFormat::Text, global = true)]
+    pub log_format: crate::logging::LogFormat,
+
+    /// Segment every text file below this directory instead of a single input. Sentences are
+    /// written file by file in sorted path order; `jsonl` records carry a `file` field.
+    #[arg(long, value_name = "DIR", conflicts_with = "input_file")]
+    pub input_dir: Option<PathBuf>,
+
+    /// Number of files of `--input-dir` segmented concurrently. The output does not depend on
+    /// it unless `--unordered` is given.
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "input_dir")]
+    pub jobs: usize,
+
+    /// Write each file of `--input-dir` as soon as it is done rather than in path order, for
+    /// maximum throughput with `--jobs`. The order of the files then varies between runs.
+    #[arg(long, requires = "input_dir")]
+    pub unordered: bool,
+
+    /// Parallel-corpus mode: segment `--input-file` and this translation of it, then write one
+    /// aligned sentence pair per line as `SOURCE_IDS<TAB>TARGET_IDS<TAB>SOURCE<TAB>TARGET`.
+    #[arg(long, value_name = "FILE", requires = "input_file")]
+    pub bitext: Option<PathBuf>,
+
+    /// Language code for the `--bitext` target file. Defaults to `--language`.
+    #[arg(long, value_name = "CODE", requires = "bitext")]
+    pub target_language: Option<String>,
+
+    /// Source-code mode: segment only the comments and docstrings of `--input-file` (or
+    /// stdin). `jsonl` records carry the file and the line and column of each sentence in it.
+    #[arg(long)]
+    pub comments: bool,
+
+    /// Comment syntax for `--comments`. [default: by the extension of `--input-file`, else `c`]
+    #[arg(long, value_enum, value_name = "SYNTAX", requires = "comments")]
+    pub comment_syntax: Option<crate::comments::CommentPreset>,
+
+    /// Additional line comment marker for `--comments`, e.g. `;`. May be given several times.
+    #[arg(long, value_name = "MARKER", requires = "comments")]
+    pub line_comment: Vec<String>,
+
+    /// Additional block comment markers for `--comments` as `OPEN,CLOSE`, e.g. `{-,-}`. May be
+    /// given several times.
+    #[arg(long, value_name = "OPEN,CLOSE", value_parser = parse_block_comment, requires = "comments")]
+    pub block_comment: Vec<(String, String)>,
+
+    /// Speak newline-delimited JSON-RPC 2.0 on stdin/stdout (`segment`, `feed`, `flush`,
+    /// `configure`) for embedding in editors and other tools.
+    #[cfg(feature = "rpc")]
+    #[arg(long)]
+    pub stdio_rpc: bool,
+
+    /// Serve segmentation sessions on a Unix domain socket instead of processing a single input.
+    /// Each connection is independent: write text, read sentences back line by line.
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH")]
+    pub serve_unix: Option<PathBuf>,
+
+    /// Serve the HTTP API on this address (e.g. `127.0.0.1:8080`): `POST /segment` and
+    /// `POST /segment/stream`.
+    #[cfg(feature = "http")]
+    #[arg(long, value_name = "ADDR")]
+    pub serve: Option<String>,
+
+    /// Serve the gRPC `Segmentation` service on this address (e.g. `127.0.0.1:50051`).
+    #[cfg(feature = "grpc")]
+    #[arg(long, value_name = "ADDR")]
+    pub serve_grpc: Option<std::net::SocketAddr>,
+
+    /// Serve segmentation sessions over WebSocket on this address (e.g. `127.0.0.1:9001`).
+    /// Sentences are sent back as JSON messages with index and byte offsets.
+    #[cfg(feature = "ws")]
+    #[arg(long, value_name = "ADDR")]
+    pub serve_ws: Option<String>,
+
+    /// Serve HTTP, WebSocket and gRPC over TLS with this PEM certificate chain.
+    #[cfg(feature = "tls")]
+    #[arg(long, value_name = "FILE", requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key for `--tls-cert`.
+    #[cfg(feature = "tls")]
+    #[arg(long, value_name = "FILE", requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// Require client certificates signed by a CA in this PEM file (mTLS).
+    #[cfg(feature = "tls")]
+    #[arg(long, value_name = "FILE", requires = "tls_cert")]
+    pub tls_client_ca: Option<PathBuf>,
+
+    /// Require this bearer token (`Authorization: Bearer ...` or `X-API-Key`) on HTTP, WebSocket
+    /// and gRPC endpoints. May be given several times.
+    #[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+    #[arg(long, value_name = "TOKEN")]
+    pub auth_token: Vec<String>,
+
+    /// Read accepted tokens from this file, one per line (`#` starts a comment).
+    #[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+    #[arg(long, value_name = "FILE")]
+    pub auth_token_file: Option<PathBuf>,
+
+    /// Language codes HTTP, WebSocket and gRPC clients may request per request or connection
+    /// (comma-separated; default: any). `--language` is always allowed.
+    #[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+    #[arg(long, value_name = "CODES", value_delimiter = ',')]
+    pub allow_language: Vec<String>,
+
+    /// Largest lookahead clients may request.
+    #[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+    #[arg(long, value_name = "CHARS")]
+    pub max_request_lookahead: Option<usize>,
+
+    /// Reject requests that add their own abbreviations.
+    #[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+    #[arg(long)]
+    pub deny_request_abbreviations: bool,
+
+    /// Output formats clients may request (comma-separated; HTTP streams: `ndjson`, `sse`;
+    /// WebSocket: `json`, `text`). Each server's default format is always allowed.
+    #[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+    #[arg(long, value_name = "FORMATS", value_delimiter = ',')]
+    pub allow_format: Vec<String>,
+
+    /// Server modes: maximum number of concurrent sessions; further clients are refused.
+    #[arg(long, value_name = "N")]
+    pub max_sessions: Option<usize>,
+
+    /// Server modes: maximum input rate per session, in bytes per second. Faster clients are
+    /// slowed down rather than disconnected.
+    #[arg(long, value_name = "BYTES")]
+    pub max_client_rate: Option<u64>,
+
+    /// Server modes: upper bound for every session's `--max-buffer`, including per-request
+    /// overrides.
+    #[arg(long, value_name = "BYTES")]
+    pub max_session_buffer: Option<usize>,
+
+    /// Server modes: bytes of text all sessions together may buffer. Sessions stop reading
+    /// while more than 90% is in use; when it is full, `--memory-policy` applies.
+    #[arg(long, value_name = "BYTES")]
+    pub memory_budget: Option<usize>,
+
+    /// What a session does with input the `--memory-budget` has no room for: `flush` what it
+    /// has buffered early, or `reject` the input with an error.
+    #[arg(long, value_enum, default_value_t = crate::memory::MemoryPolicy::Flush, requires = "memory_budget")]
+    pub memory_policy: crate::memory::MemoryPolicy,
+
+    /// Serve `GET /metrics`, `/healthz` and `/readyz` on this address for `--serve-grpc` and
+    /// `--serve-ws`. The HTTP server always serves these itself.
+    #[cfg(any(feature = "grpc", feature = "ws"))]
+    #[arg(long, value_name = "ADDR")]
+    pub admin_addr: Option<String>,
+
+    /// NATS server URL. Runs as a service segmenting `--nats-input` into `--nats-output`.
+    #[cfg(feature = "nats")]
+    #[arg(long, value_name = "URL", requires_all = ["nats_input", "nats_output"])]
+    pub nats_url: Option<String>,
+
+    /// NATS subject to read text chunks from.
+    #[cfg(feature = "nats")]
+    #[arg(long, value_name = "SUBJECT", requires = "nats_url")]
+    pub nats_input: Option<String>,
+
+    /// NATS subject to publish sentences to.
+    #[cfg(feature = "nats")]
+    #[arg(long, value_name = "SUBJECT", requires = "nats_url")]
+    pub nats_output: Option<String>,
+
+    /// Redis URL. Runs as a service segmenting stream `--redis-input` into `--redis-output`.
+    #[cfg(feature = "redis")]
+    #[arg(long, value_name = "URL", requires_all = ["redis_input", "redis_output"])]
+    pub redis_url: Option<String>,
+
+    /// Redis stream key to read text chunks from.
+    #[cfg(feature = "redis")]
+    #[arg(long, value_name = "KEY", requires = "redis_url")]
+    pub redis_input: Option<String>,
+
+    /// Redis stream key to append sentences to.
+    #[cfg(feature = "redis")]
+    #[arg(long, value_name = "KEY", requires = "redis_url")]
+    pub redis_output: Option<String>,
+}
+
+/// Subcommands; without one, the CLI segments `--input-file` or stdin.
+#[cfg(feature = "cli")]
+#[derive(clap::Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Segment one text with several backends and print where their boundaries differ.
+    Compare {
+        /// Text file to segment.
+        #[arg(long, value_name = "FILE")]
+        input: PathBuf,
+
+        /// Comma-separated backends to compare (`tqsm`, `uax29`, and `icu` with the `icu`
+        /// feature).
+        #[arg(long, value_delimiter = ',', default_value = "tqsm,uax29")]
+        backends: Vec<crate::compare::Backend>,
+    },
+
+    /// Print the rules in effect for `--language` as SRX or JSON.
+    ExportRules {
+        #[arg(long, value_enum, default_value_t = RulesFormat::Srx)]
+        format: RulesFormat,
+    },
+}
+
+/// Serialization formats for `export-rules`.
+#[cfg(feature = "cli")]
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RulesFormat {
+    Srx,
+    Json,
+}
+
+/// Parses a `--block-comment` value, `OPEN,CLOSE`.
+#[cfg(feature = "cli")]
+fn parse_block_comment(value: &str) -> Result<(String, String), String> {
+    match value.split_once(',') {
+        Some((open, close)) if !open.is_empty() && !close.is_empty() => {
+            Ok((open.to_string(), close.to_string()))
+        }
+        _ => Err(format!("expected OPEN,CLOSE, got `{}`", value)),
+    }
+}
+
+/// Parses a `--sample` rate between 0 and 1.
+#[cfg(feature = "cli")]
+fn parse_rate(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err(format!("expected a rate between 0 and 1, got `{}`", value)),
+    }
+}
+
+/// Parses a `--wpm` speaking rate, a positive number.
+#[cfg(feature = "cli")]
+fn parse_speaking_rate(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(rate),
+        _ => Err(format!("expected a positive rate, got `{}`", value)),
+    }
+}
+
+/// How the CLI writes sentences.
+#[cfg(feature = "cli")]
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One sentence per line.
+    Text,
+    /// One JSON object per line: `index`, `start`, `end`, `text` (and `duration_ms`).
+    Jsonl,
+    /// SubRip subtitles: one or more cues per sentence, wrapped into balanced lines and timed at
+    /// the estimated speaking rate.
+    Srt,
 }
 
 #[derive(Debug, Clone)]
@@ -34,8 +589,97 @@ pub struct SegmentOptions {
     pub lookahead: usize,
     /// Maximum buffer length before potentially forcing a split or erroring.
     pub max_buffer: usize,
-    /// Language code for segmentation rules.
+    /// What happens when text without a sentence boundary fills `max_buffer`.
+    pub overflow_policy: crate::OverflowPolicy,
+    /// Split sentences longer than this many bytes (not counting surrounding spaces), e.g. for
+    /// speech synthesizers and embedding models with a hard input limit: after a clause mark
+    /// such as a comma, else at whitespace, else between grapheme clusters. 0 disables it.
+    pub max_sentence_len: usize,
+    /// Merge a sentence shorter than this many bytes (not counting surrounding spaces), such as
+    /// a stray "Dr." or "Etc.", with the sentence after it. 0 disables it.
+    pub min_sentence_len: usize,
+    /// Language code for segmentation rules, or `"auto"` to detect it (`detect` feature).
     pub language: String,
+    /// Bytes buffered to detect the language from under `language: "auto"`; the first sentences
+    /// wait for them, or for the end of the input or an idle flush.
+    pub detection_window: usize,
+    /// Whether each sentence's language is detected and reported in
+    /// [`Segment::language`](crate::Segment::language), and whether the rules follow it
+    /// (`detect` feature).
+    pub sentence_language: crate::detect::SentenceLanguage,
+    /// Languages the sentences are expected in, e.g. `["de", "en"]`, which single sentences are
+    /// detected among; empty considers all, which takes longer sentences.
+    pub sentence_languages: Vec<String>,
+    /// Language whose rules are used, with a warning, if `language` has none; `None` falls
+    /// back to ICU4X with the `icu` feature, and fails with `UnsupportedLanguage` without it.
+    pub fallback_language: Option<String>,
+    /// Rule engine: the `libtqsm` rules, or ICU4X's (`icu` feature).
+    pub backend: crate::backend::Backend,
+    /// Words that never end a sentence when followed by a period, in addition to the
+    /// language's own list (e.g. `"approx."`; the trailing period is optional).
+    pub extra_abbreviations: Vec<String>,
+    /// More abbreviations, loaded from files and possibly reloaded while segmenters use them.
+    pub shared_abbreviations: Option<crate::abbreviations::SharedAbbreviations>,
+    /// Words that are not abbreviations after all, although the language's list, the
+    /// `extra_abbreviations` or the `shared_abbreviations` have them: a period after one of
+    /// them followed by whitespace ends the sentence (trailing period optional).
+    pub removed_abbreviations: Vec<String>,
+    /// Regex rules that add or veto sentence boundaries on top of the language's rules, e.g.
+    /// to split at bullets or chat-log prefixes.
+    pub custom_boundaries: Vec<crate::rules::CustomBoundary>,
+    /// How byte streams handle input that is not valid UTF-8.
+    pub utf8_policy: crate::decode::Utf8Policy,
+    /// WHATWG label of the encoding byte input is in (e.g. `"windows-1252"`, `"shift_jis"`);
+    /// `None` is UTF-8. Anything else needs the `encoding` feature. Undecodable bytes follow
+    /// `utf8_policy`.
+    pub encoding: Option<String>,
+    /// Let a byte order mark at the start of byte input override `encoding` (UTF-8, UTF-16LE
+    /// or UTF-16BE), and drop it; needs the `encoding` feature.
+    pub sniff_bom: bool,
+    /// Receives non-fatal warnings (lossy decoding, long sentences); `None` drops them.
+    pub warnings: Option<crate::warnings::Warnings>,
+    /// Warn about sentences longer than this many bytes; 0 disables the check.
+    pub long_sentence_warning: usize,
+    /// Which transient read errors the sentence streams retry.
+    pub retry: crate::retry::RetryPolicy,
+    /// How much the sentence streams read at a time.
+    pub read_size: crate::chunking::ReadSize,
+    /// What to do with a trailing fragment without a sentence boundary at the end of the input.
+    pub tail_policy: crate::TailPolicy,
+    /// Emit the buffered text once the input has been idle this long, e.g. while a language
+    /// model pauses mid-sentence in front of a speech synthesizer; `None` waits for more input.
+    /// Applies to the tokio-based streams.
+    pub flush_after: Option<std::time::Duration>,
+    /// Ignore sentence boundaries inside quotes and brackets, also when the quotation spans
+    /// several chunks, as long as it was opened at most this many bytes earlier; a longer one
+    /// is treated as never closed. 0 leaves quotes to the language rules alone.
+    pub max_quote_span: usize,
+    /// Quotes and brackets nested deeper than this are not tracked.
+    pub max_quote_depth: usize,
+    /// How a closing quote or bracket that does not match the innermost open one is handled.
+    pub quote_recovery: crate::quotes::QuoteRecovery,
+    /// Extra rules for footnote markers and similar constructs.
+    pub protections: crate::protect::Protections,
+    /// Keep the spaces a sentence starts with (indentation in code comments, poetry or quoted
+    /// email) instead of trimming them; trailing spaces are always trimmed.
+    pub keep_indentation: bool,
+    /// Also apply the script rules of other scripts to runs of them inside the text, e.g. a
+    /// Hindi quotation in English text ends at its danda.
+    pub mixed_scripts: bool,
+    /// Kind of input the rules are tuned for.
+    pub mode: Mode,
+    /// Whether sentences or paragraphs are emitted.
+    pub unit: SegmentUnit,
+    /// Regex for the part of a log line in [`Mode::Log`] that is not part of the message;
+    /// `None` uses [`logs::DEFAULT_PREFIX`](crate::logs::DEFAULT_PREFIX).
+    pub log_prefix: Option<String>,
+    /// Receives a record of every accepted sentence boundary; `None` disables auditing.
+    pub audit: Option<crate::audit::Audit>,
+    /// Byte budget shared with other segmenters; `None` leaves only `max_buffer`.
+    pub memory_pool: Option<crate::memory::MemoryPool>,
+    /// Applied to the text of every sentence before it is emitted; offsets still refer to the
+    /// input.
+    pub post_process: Option<crate::postprocess::PostProcess>,
     // Potentially store the loaded language object directly if desired
     // pub(crate) language_impl: &'static (dyn Language + Send + Sync),
 }
@@ -46,18 +690,366 @@ impl Default for SegmentOptions {
         Self {
             lookahead: 10,
             max_buffer: 8192,
+            overflow_policy: crate::OverflowPolicy::default(),
+            max_sentence_len: 0,
+            min_sentence_len: 0,
             language: "en".to_string(),
+            detection_window: 512,
+            sentence_language: crate::detect::SentenceLanguage::Off,
+            sentence_languages: Vec::new(),
+            fallback_language: None,
+            backend: crate::backend::Backend::default(),
+            extra_abbreviations: Vec::new(),
+            shared_abbreviations: None,
+            removed_abbreviations: Vec::new(),
+            custom_boundaries: Vec::new(),
+            utf8_policy: crate::decode::Utf8Policy::default(),
+            encoding: None,
+            sniff_bom: false,
+            warnings: None,
+            long_sentence_warning: 2000,
+            retry: crate::retry::RetryPolicy::default(),
+            read_size: crate::chunking::ReadSize::default(),
+            tail_policy: crate::TailPolicy::default(),
+            flush_after: None,
+            max_quote_span: 1000,
+            max_quote_depth: 8,
+            quote_recovery: crate::quotes::QuoteRecovery::default(),
+            protections: crate::protect::Protections::default(),
+            keep_indentation: false,
+            mixed_scripts: false,
+            mode: Mode::Prose,
+            unit: SegmentUnit::Sentence,
+            log_prefix: None,
+            audit: None,
+            memory_pool: None,
+            post_process: None,
             // language_impl: libtqsm::get_language("en").unwrap(), // Or load dynamically
         }
     }
 }
 
+/// A coherent set of error, overflow and tail settings for [`SegmentOptions::preset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Surface every problem: invalid UTF-8 and read errors end the stream, the default
+    /// buffer limit applies, and an incomplete tail is emitted but marked.
+    Strict,
+    /// Keep going whatever the input looks like: invalid UTF-8 is replaced, `WouldBlock` and
+    /// `TimedOut` reads are retried, the buffer may grow eight times larger, after which text
+    /// is split at whitespace, and the tail is emitted like any other sentence.
+    Lenient,
+}
+
+/// Kind of input, for [`SegmentOptions::mode`] and `--mode`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Mode {
+    /// Running text: sentences end at punctuation, as the language rules say.
+    #[default]
+    Prose,
+    /// Application logs: every line is a record, ended by its line break like a chat
+    /// message, and the time stamp and level in front of each line (see
+    /// [`SegmentOptions::log_prefix`]) are left out of the sentences.
+    Log,
+    /// Chat and instant messages: every line break ends a message, and its sentences are
+    /// emitted as soon as the line break arrives, without waiting for the lookahead; a run of emoji before a capitalized word ends a sentence, a terminator
+    /// followed by a lower-case word does too, and no sentence ends inside a URL, @mention or
+    /// #hashtag.
+    Chat,
+}
+
+/// Latency and accuracy settings tuned for a use case, for [`SegmentOptions::profile`] and
+/// `--profile`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Profile {
+    /// Speech synthesis: a 2-character lookahead hands each sentence over as soon as its
+    /// terminator is followed by the next word, and sentences above 300 bytes are reported
+    /// because they stall playback. Occasionally splits after an abbreviation the rules can
+    /// only recognise from what follows.
+    TtsLowLatency,
+    /// Offline corpora: a 40-character lookahead and a 64 KiB buffer give every rule full
+    /// context, at the price of sentences arriving late and more memory per stream.
+    BatchAccurate,
+    /// Chat messages: [`Mode::Chat`] rules, a 5-character lookahead, and unterminated last
+    /// lines, which are the norm in chat, are emitted like any other sentence; sentences above
+    /// 500 bytes are reported.
+    Chat,
+}
+
+impl Mode {
+    /// Whether every line is a record of its own, ended by its line break.
+    pub fn is_line_based(self) -> bool {
+        matches!(self, Mode::Chat | Mode::Log)
+    }
+}
+
+/// What a segment is, for [`SegmentOptions::unit`] and `--unit`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SegmentUnit {
+    /// A sentence, as the language rules and the mode find them.
+    #[default]
+    Sentence,
+    /// A paragraph: the text up to the next blank line, whatever punctuation it contains,
+    /// without the line breaks around it. Length limits and buffer policies apply to whole
+    /// paragraphs.
+    Paragraph,
+    /// Sentences, which also end at a blank line, each with the index of its paragraph in
+    /// [`Segment::paragraph`](crate::Segment::paragraph).
+    Nested,
+}
+
+impl SegmentOptions {
+    /// Default options for `language`, with a lookahead suited to its script (see
+    /// [`scripts::default_lookahead`](crate::scripts::default_lookahead)).
+    pub fn for_language(language: &str) -> Self {
+        Self {
+            lookahead: crate::scripts::default_lookahead(language),
+            language: language.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Default options with the lookahead, buffer, tail, idle flush and sentence length settings
+    /// of `profile`.
+    pub fn profile(profile: Profile) -> Self {
+        let defaults = Self::default();
+        match profile {
+            Profile::TtsLowLatency => Self {
+                lookahead: 2,
+                long_sentence_warning: 300,
+                max_sentence_len: 500,
+                tail_policy: crate::TailPolicy::Emit,
+                flush_after: Some(std::time::Duration::from_millis(500)),
+                ..defaults
+            },
+            Profile::BatchAccurate => Self {
+                lookahead: 40,
+                max_buffer: 64 * 1024,
+                ..defaults
+            },
+            Profile::Chat => Self {
+                mode: Mode::Chat,
+                lookahead: 5,
+                long_sentence_warning: 500,
+                tail_policy: crate::TailPolicy::Emit,
+                ..defaults
+            },
+        }
+    }
+
+    /// Default options with the error, overflow and tail settings of `preset`; the remaining
+    /// fields can still be changed afterwards.
+    pub fn preset(preset: Preset) -> Self {
+        let defaults = Self::default();
+        match preset {
+            Preset::Strict => Self {
+                utf8_policy: crate::decode::Utf8Policy::Strict,
+                retry: crate::retry::RetryPolicy::none(),
+                tail_policy: crate::TailPolicy::EmitMarkedIncomplete,
+                ..defaults
+            },
+            Preset::Lenient => Self {
+                max_buffer: defaults.max_buffer * 8,
+                overflow_policy: crate::OverflowPolicy::ForceSplitAtWhitespace,
+                utf8_policy: crate::decode::Utf8Policy::Replace,
+                retry: crate::retry::RetryPolicy {
+                    retry_would_block: true,
+                    retry_timed_out: true,
+                    ..Default::default()
+                },
+                tail_policy: crate::TailPolicy::Emit,
+                ..defaults
+            },
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl CliArgs {
+    /// TLS settings from `--tls-cert`, `--tls-key` and `--tls-client-ca`, if TLS was requested.
+    #[cfg(feature = "tls")]
+    pub fn tls(&self) -> Option<crate::server::tls::TlsConfig> {
+        let (cert, key) = (self.tls_cert.as_ref()?, self.tls_key.as_ref()?);
+        let config = crate::server::tls::TlsConfig::new(cert, key);
+        Some(match &self.tls_client_ca {
+            Some(ca) => config.client_ca(ca),
+            None => config,
+        })
+    }
+
+    /// Accepted tokens from `--auth-token` and `--auth-token-file`; disabled if neither is given.
+    #[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+    pub fn auth(&self) -> crate::Result<crate::server::auth::Auth> {
+        use crate::server::auth::Auth;
+
+        let mut auth = if self.auth_token.is_empty() {
+            Auth::disabled()
+        } else {
+            Auth::tokens(self.auth_token.iter().cloned())
+        };
+        if let Some(path) = &self.auth_token_file {
+            auth = auth.merge(Auth::from_file(path)?);
+        }
+        Ok(auth)
+    }
+
+    /// Per-request option allow-lists from `--allow-language`, `--max-request-lookahead`,
+    /// `--deny-request-abbreviations` and `--allow-format`.
+    #[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+    pub fn policy(&self) -> crate::server::tenancy::Policy {
+        let mut policy = crate::server::tenancy::Policy::new()
+            .allow_abbreviations(!self.deny_request_abbreviations);
+        if !self.allow_language.is_empty() {
+            policy = policy.allow_languages(self.allow_language.iter().cloned());
+        }
+        if let Some(max) = self.max_request_lookahead {
+            policy = policy.max_lookahead(max);
+        }
+        if !self.allow_format.is_empty() {
+            policy = policy.allow_formats(self.allow_format.iter().cloned());
+        }
+        policy
+    }
+
+    /// Server resource limits from `--max-sessions`, `--max-client-rate`,
+    /// `--max-session-buffer` and `--memory-budget`.
+    pub fn limits(&self) -> crate::server::limits::Limits {
+        let mut limits = crate::server::limits::Limits::new();
+        if let Some(max) = self.max_sessions {
+            limits = limits.max_sessions(max);
+        }
+        if let Some(rate) = self.max_client_rate {
+            limits = limits.bytes_per_sec(rate);
+        }
+        if let Some(max) = self.max_session_buffer {
+            limits = limits.max_session_buffer(max);
+        }
+        if let Some(capacity) = self.memory_budget {
+            let pool = crate::memory::MemoryPool::with_policy(capacity, self.memory_policy);
+            limits = limits.memory_pool(pool);
+        }
+        limits
+    }
+}
+
+/// The `--split-after`, `--split-before` and `--no-split` rules.
+#[cfg(feature = "cli")]
+fn custom_boundaries(
+    split_after: &[String],
+    split_before: &[String],
+    no_split: &[String],
+) -> Vec<crate::rules::CustomBoundary> {
+    use crate::rules::{BoundaryAction, CustomBoundary};
+    [
+        (split_after, BoundaryAction::Split),
+        (split_before, BoundaryAction::SplitBefore),
+        (no_split, BoundaryAction::NoSplit),
+    ]
+    .into_iter()
+    .flat_map(|(patterns, action)| {
+        patterns
+            .iter()
+            .map(move |pattern| CustomBoundary::new(pattern.as_str(), action))
+    })
+    .collect()
+}
+
+/// The post-processing steps selected by `--strip-markup` and `--verbalize`, in that order.
+#[cfg(feature = "cli")]
+fn post_process(strip_markup: bool, verbalize: bool) -> Option<crate::postprocess::PostProcess> {
+    use crate::postprocess::PostProcess;
+    use crate::verbalize::{English, Verbalizer};
+
+    match (strip_markup, verbalize) {
+        (false, false) => None,
+        (true, false) => Some(PostProcess::strip_markup()),
+        (false, true) => Some(PostProcess::verbalize(English::new())),
+        (true, true) => {
+            let english = English::new();
+            Some(PostProcess::strip_markup().then(move |s| english.verbalize(&s)))
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
 impl From<CliArgs> for SegmentOptions {
     fn from(args: CliArgs) -> Self {
+        let base = args
+            .profile
+            .map(SegmentOptions::profile)
+            .unwrap_or_else(|| SegmentOptions::for_language(&args.language));
         Self {
-            lookahead: args.lookahead,
-            max_buffer: args.max_buffer,
+            lookahead: args.lookahead.unwrap_or(base.lookahead),
+            max_buffer: args.max_buffer.unwrap_or(base.max_buffer),
+            overflow_policy: args.overflow_policy.unwrap_or(base.overflow_policy),
+            max_sentence_len: args.max_sentence_len.unwrap_or(base.max_sentence_len),
+            min_sentence_len: args.min_sentence_len,
             language: args.language,
+            detection_window: args.detection_window,
+            sentence_language: args.sentence_language,
+            sentence_languages: args.sentence_languages,
+            fallback_language: args.fallback_language,
+            backend: args.backend,
+            extra_abbreviations: Vec::new(),
+            shared_abbreviations: None,
+            removed_abbreviations: args.remove_abbrev,
+            custom_boundaries: custom_boundaries(
+                &args.split_after,
+                &args.split_before,
+                &args.no_split,
+            ),
+            utf8_policy: args.utf8_policy,
+            encoding: args.encoding,
+            sniff_bom: args.sniff_bom,
+            warnings: None,
+            long_sentence_warning: args
+                .long_sentence_warning
+                .unwrap_or(base.long_sentence_warning),
+            retry: crate::retry::RetryPolicy::default(),
+            read_size: args.read_size,
+            tail_policy: args.tail.unwrap_or(base.tail_policy),
+            flush_after: args
+                .flush_after
+                .map(std::time::Duration::from_millis)
+                .or(base.flush_after),
+            max_quote_span: args.max_quote_span,
+            max_quote_depth: args.max_quote_depth,
+            quote_recovery: args.quote_recovery,
+            protections: crate::protect::Protections {
+                footnotes: !args.no_footnotes,
+                citations: !args.no_citations,
+                initials: args.initials,
+                roman_numerals: args.roman_numerals,
+                dates_and_times: !args.no_dates,
+                ordinal_dates: args.ordinal_dates,
+                measurements: if args.no_measurements {
+                    Vec::new()
+                } else {
+                    args.measurements
+                },
+                patterns: Vec::new(),
+            },
+            keep_indentation: args.keep_indentation,
+            mixed_scripts: args.mixed_scripts,
+            mode: args.mode.unwrap_or(base.mode),
+            unit: args.unit,
+            log_prefix: args.log_prefix,
+            audit: None,
+            memory_pool: None,
+            post_process: post_process(args.strip_markup, args.verbalize),
             // language_impl: libtqsm::get_language(&args.language).unwrap_or_else(|_| { /* handle error or default */}),
         }
     }
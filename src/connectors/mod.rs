@@ -0,0 +1,53 @@
+//! Lightweight connectors that run the segmenter as a small service on top of a message bus.
+//!
+//! Each connector treats the incoming messages as one continuous text stream, feeds them into a
+//! [`Segmenter`](crate::Segmenter) and publishes every completed sentence as its own message.
+//! Enable them with the `nats` and `redis` features.
+
+use crate::config::SegmentOptions;
+use crate::error::Result;
+use crate::segmenter::Segmenter;
+
+#[cfg(feature = "nats")]
+pub mod nats;
+#[cfg(feature = "redis")]
+pub mod redis;
+
+/// The part of a connector that does not depend on the bus: turns each incoming payload into
+/// the sentences to publish.
+///
+/// A payload that cannot be segmented, e.g. because it is not valid UTF-8, does not stop the
+/// service: the error is logged and the text stream starts over with the next message.
+pub(crate) struct Relay {
+    segmenter: Segmenter,
+}
+
+impl Relay {
+    pub(crate) fn new(options: SegmentOptions) -> Result<Self> {
+        Ok(Self {
+            segmenter: Segmenter::new(options)?,
+        })
+    }
+
+    /// The sentences completed by `payload`, followed by the remaining ones if it ends the
+    /// input.
+    pub(crate) fn message(&mut self, payload: &[u8], end: bool) -> Vec<String> {
+        let segmenter = &mut self.segmenter;
+        let sentences = segmenter.feed_bytes(payload).and_then(|mut sentences| {
+            if end {
+                sentences.extend(segmenter.finish()?);
+            }
+            Ok(sentences)
+        });
+        sentences.unwrap_or_else(|e| {
+            crate::server::report_session_error("message skipped", &e);
+            self.segmenter.reset();
+            Vec::new()
+        })
+    }
+
+    /// The remaining sentences once the input has ended.
+    pub(crate) fn finish(&mut self) -> Vec<String> {
+        self.message(&[], true)
+    }
+}
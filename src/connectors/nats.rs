@@ -0,0 +1,57 @@
+use super::Relay;
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use futures::StreamExt;
+
+/// Subscribes to `input_subject` and publishes each sentence found in the message payloads to
+/// `output_subject`.
+///
+/// Payloads are concatenated in arrival order, so a sentence may span several messages. Runs until
+/// the subscription is closed, then flushes and publishes any remaining text. A payload that
+/// cannot be segmented is logged and skipped, dropping the text buffered before it.
+pub async fn segment_subject(
+    client: &async_nats::Client,
+    input_subject: &str,
+    output_subject: &str,
+    options: SegmentOptions,
+) -> Result<()> {
+    let mut relay = Relay::new(options)?;
+    let mut subscriber = client
+        .subscribe(input_subject.to_string())
+        .await
+        .map_err(|e| SegmenterError::StreamError(e.to_string()))?;
+
+    while let Some(message) = subscriber.next().await {
+        for sentence in relay.message(&message.payload, false) {
+            publish(client, output_subject, sentence).await?;
+        }
+    }
+    for sentence in relay.finish() {
+        publish(client, output_subject, sentence).await?;
+    }
+
+    client
+        .flush()
+        .await
+        .map_err(|e| SegmenterError::StreamError(e.to_string()))
+}
+
+/// Connects to the NATS server at `url` and runs [`segment_subject`] on the new connection.
+pub async fn connect_and_segment(
+    url: &str,
+    input_subject: &str,
+    output_subject: &str,
+    options: SegmentOptions,
+) -> Result<()> {
+    let client = async_nats::connect(url)
+        .await
+        .map_err(|e| SegmenterError::StreamError(e.to_string()))?;
+    segment_subject(&client, input_subject, output_subject, options).await
+}
+
+async fn publish(client: &async_nats::Client, subject: &str, sentence: String) -> Result<()> {
+    client
+        .publish(subject.to_string(), sentence.into())
+        .await
+        .map_err(|e| SegmenterError::StreamError(e.to_string()))
+}
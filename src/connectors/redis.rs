@@ -0,0 +1,85 @@
+use super::Relay;
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use redis::streams::{StreamRangeReply, StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+
+/// Field holding the text chunk in input entries and the sentence in output entries.
+pub const TEXT_FIELD: &str = "text";
+
+/// An input entry carrying this field (with any value) flushes the buffered tail.
+pub const END_FIELD: &str = "end";
+
+/// How long a single `XREAD` blocks before polling again, in milliseconds.
+const BLOCK_MS: usize = 1000;
+
+/// Reads new entries from the `input_key` stream and appends each sentence found in their
+/// [`TEXT_FIELD`] values to the `output_key` stream.
+///
+/// Only entries added after the call starts are consumed. Entries are concatenated in stream
+/// order; an entry with an [`END_FIELD`] flushes whatever text is still buffered. An entry that
+/// cannot be segmented, e.g. because its text is not valid UTF-8, is logged and skipped, dropping
+/// the text buffered before it. Runs until a Redis command fails.
+pub async fn segment_stream(
+    client: &redis::Client,
+    input_key: &str,
+    output_key: &str,
+    options: SegmentOptions,
+) -> Result<()> {
+    let mut con = client
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(redis_error)?;
+    let mut relay = Relay::new(options)?;
+    let read_options = StreamReadOptions::default().block(BLOCK_MS).count(100);
+    // Resolved once: `$` would skip entries added between two reads that returned nothing
+    let latest: StreamRangeReply = con
+        .xrevrange_count(input_key, "+", "-", 1)
+        .await
+        .map_err(redis_error)?;
+    let mut last_id = match latest.ids.into_iter().next() {
+        Some(entry) => entry.id,
+        None => "0-0".to_string(),
+    };
+
+    loop {
+        let reply: Option<StreamReadReply> = con
+            .xread_options(&[input_key], &[&last_id], &read_options)
+            .await
+            .map_err(redis_error)?;
+        let Some(reply) = reply else {
+            continue; // Nothing new within the block window
+        };
+
+        for stream_key in reply.keys {
+            for entry in stream_key.ids {
+                last_id = entry.id.clone();
+
+                // Bytes rather than a `String`, so invalid UTF-8 is reported instead of ignored
+                let chunk = entry.get::<Vec<u8>>(TEXT_FIELD).unwrap_or_default();
+                let sentences = relay.message(&chunk, entry.map.contains_key(END_FIELD));
+                for sentence in sentences {
+                    let _: Option<String> = con
+                        .xadd(output_key, "*", &[(TEXT_FIELD, sentence)])
+                        .await
+                        .map_err(redis_error)?;
+                }
+            }
+        }
+    }
+}
+
+/// Opens a client for `url` and runs [`segment_stream`] on it.
+pub async fn connect_and_segment(
+    url: &str,
+    input_key: &str,
+    output_key: &str,
+    options: SegmentOptions,
+) -> Result<()> {
+    let client = redis::Client::open(url).map_err(redis_error)?;
+    segment_stream(&client, input_key, output_key, options).await
+}
+
+fn redis_error(e: redis::RedisError) -> SegmenterError {
+    SegmenterError::StreamError(e.to_string())
+}
@@ -0,0 +1,325 @@
+//! Incremental UTF-8 decoding of byte chunks, so characters split across reads are reassembled
+//! instead of being reported as invalid, and genuinely invalid input is handled according to a
+//! [`Utf8Policy`].
+//!
+//! With the `encoding` feature, input in a legacy encoding such as Windows-1252 or Shift_JIS
+//! (see [`SegmentOptions::encoding`]) is transcoded by an [`encoding_rs`] streaming decoder
+//! instead, under the same policy.
+
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use crate::warnings::{WarningKind, Warnings};
+
+/// What to do with bytes that are not valid UTF-8 (or not valid in the configured
+/// [`encoding`](SegmentOptions::encoding)).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Utf8Policy {
+    /// Report a `Utf8Error` and end the stream.
+    #[default]
+    Strict,
+    /// Drop invalid bytes and carry on.
+    #[cfg_attr(feature = "cli", value(name = "skip"))]
+    #[cfg_attr(feature = "serde", serde(rename = "skip"))]
+    SkipInvalid,
+    /// Replace each invalid sequence with U+FFFD and carry on.
+    #[cfg_attr(feature = "cli", value(name = "replace"))]
+    #[cfg_attr(feature = "serde", serde(rename = "replace"))]
+    Replace,
+}
+
+/// Turns a sequence of byte chunks into text, holding back incomplete trailing sequences until
+/// the next chunk arrives.
+#[derive(Debug, Default)]
+pub struct Utf8Decoder {
+    policy: Utf8Policy,
+    /// Start of a multi-byte character whose remaining bytes have not arrived yet.
+    pending: Vec<u8>,
+    /// Input bytes before `pending`, for error offsets.
+    consumed: usize,
+    warnings: Option<Warnings>,
+    /// Transcoder for a non-UTF-8 [`encoding`](SegmentOptions::encoding); it holds back split
+    /// characters itself, so `pending` stays empty.
+    #[cfg(feature = "encoding")]
+    legacy: Option<Legacy>,
+    /// Why the options' encoding cannot be used; returned by the first `decode`.
+    unusable: Option<String>,
+}
+
+impl Utf8Decoder {
+    pub fn new(policy: Utf8Policy) -> Self {
+        Self {
+            policy,
+            ..Self::default()
+        }
+    }
+
+    /// A decoder with the `utf8_policy` and `encoding` of `options`, reporting lossy decoding
+    /// to its `warnings`. An unknown encoding fails the first [`decode`](Self::decode); check
+    /// options up front with [`check_encoding`].
+    pub fn for_options(options: &SegmentOptions) -> Self {
+        let mut decoder = Self {
+            warnings: options.warnings.clone(),
+            ..Self::new(options.utf8_policy)
+        };
+        match encoding_for(options) {
+            #[cfg(feature = "encoding")]
+            Ok(Some(encoding)) => decoder.legacy = Some(Legacy::new(encoding, options.sniff_bom)),
+            Ok(_) => {}
+            Err(e) => decoder.unusable = Some(e),
+        }
+        decoder
+    }
+
+    /// Decodes `bytes`, prefixed by whatever was held back from the previous chunk.
+    pub fn decode(&mut self, bytes: &[u8]) -> Result<String> {
+        if let Some(reason) = self.unusable.take() {
+            return Err(SegmenterError::InvalidOptions(reason));
+        }
+        #[cfg(feature = "encoding")]
+        if self.legacy.is_some() {
+            return self.transcode(bytes, false);
+        }
+        self.pending.extend_from_slice(bytes);
+        let input = std::mem::take(&mut self.pending);
+        let mut text = String::with_capacity(input.len());
+        let mut rest = &input[..];
+
+        while !rest.is_empty() {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, invalid) = rest.split_at(e.valid_up_to());
+                    // Already validated by `from_utf8`
+                    text.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    let Some(len) = e.error_len() else {
+                        // Truncated character at the end: wait for the next chunk
+                        self.pending = invalid.to_vec();
+                        break;
+                    };
+                    match self.policy {
+                        Utf8Policy::Strict => {
+                            let position = input.len() - invalid.len();
+                            return Err(self.error(e, &input, position));
+                        }
+                        Utf8Policy::SkipInvalid => {}
+                        Utf8Policy::Replace => text.push(char::REPLACEMENT_CHARACTER),
+                    }
+                    let position = input.len() - invalid.len();
+                    self.warn_lossy(position, len);
+                    rest = &invalid[len..];
+                }
+            }
+        }
+        self.consumed += input.len() - self.pending.len();
+        Ok(text)
+    }
+
+    /// Removes the start of an incomplete character held back from the last chunk.
+    pub fn take_pending(&mut self) -> Vec<u8> {
+        let pending = std::mem::take(&mut self.pending);
+        self.consumed += pending.len();
+        pending
+    }
+
+    /// Deals with a character still incomplete at the end of the input.
+    pub fn finish(&mut self) -> Result<Option<String>> {
+        #[cfg(feature = "encoding")]
+        if self.legacy.is_some() {
+            let rest = self.transcode(&[], true)?;
+            return Ok(Some(rest).filter(|rest| !rest.is_empty()));
+        }
+        if self.pending.is_empty() {
+            return Ok(None);
+        }
+        let pending = std::mem::take(&mut self.pending);
+        match self.policy {
+            Utf8Policy::Strict => match std::str::from_utf8(&pending) {
+                Err(e) => Err(self.error(e, &pending, 0)),
+                Ok(text) => Ok(Some(text.to_string())),
+            },
+            Utf8Policy::SkipInvalid => {
+                self.warn_lossy(0, pending.len());
+                Ok(None)
+            }
+            Utf8Policy::Replace => {
+                self.warn_lossy(0, pending.len());
+                Ok(Some(char::REPLACEMENT_CHARACTER.to_string()))
+            }
+        }
+    }
+
+    fn warn_lossy(&self, position: usize, len: usize) {
+        self.report_lossy(self.consumed + position, len);
+    }
+
+    /// Warns that `len` bytes at stream offset `offset` were skipped or replaced.
+    fn report_lossy(&self, offset: usize, len: usize) {
+        if let Some(warnings) = &self.warnings {
+            let action = match self.policy {
+                Utf8Policy::Replace => "replaced",
+                _ => "skipped",
+            };
+            warnings.report(
+                WarningKind::LossyDecode,
+                offset,
+                format!(
+                    "{} {} invalid {} byte(s)",
+                    action,
+                    len,
+                    self.encoding_name()
+                ),
+            );
+        }
+    }
+
+    /// Name of the encoding being decoded, for messages.
+    fn encoding_name(&self) -> &'static str {
+        #[cfg(feature = "encoding")]
+        if let Some(legacy) = &self.legacy {
+            return legacy.decoder.encoding().name();
+        }
+        "UTF-8"
+    }
+
+    /// Transcodes `bytes` with the legacy decoder; `last` ends the input.
+    #[cfg(feature = "encoding")]
+    fn transcode(&mut self, bytes: &[u8], last: bool) -> Result<String> {
+        use encoding_rs::DecoderResult;
+
+        let capacity = self
+            .legacy
+            .as_ref()
+            .expect("legacy decoder in use")
+            .decoder
+            .max_utf8_buffer_length_without_replacement(bytes.len())
+            .unwrap_or(bytes.len() * 3);
+        let mut text = String::with_capacity(capacity);
+        let mut rest = bytes;
+        loop {
+            let legacy = self.legacy.as_mut().expect("legacy decoder in use");
+            let (result, read) = legacy
+                .decoder
+                .decode_to_string_without_replacement(rest, &mut text, last);
+            rest = &rest[read..];
+            match result {
+                DecoderResult::InputEmpty => break,
+                DecoderResult::OutputFull => {
+                    let more = legacy
+                        .decoder
+                        .max_utf8_buffer_length_without_replacement(rest.len())
+                        .unwrap_or(rest.len() * 3);
+                    text.reserve(more.max(4));
+                }
+                DecoderResult::Malformed(len, after) => {
+                    // The malformed sequence may have started in an earlier chunk
+                    let end = bytes.len() - rest.len();
+                    let position = end as isize - isize::from(len) - isize::from(after);
+                    let offset = self.consumed.saturating_add_signed(position);
+                    match self.policy {
+                        Utf8Policy::Strict => {
+                            return Err(SegmenterError::DecodeError {
+                                encoding: legacy.decoder.encoding().name(),
+                                offset,
+                            });
+                        }
+                        Utf8Policy::SkipInvalid => {}
+                        Utf8Policy::Replace => text.push(char::REPLACEMENT_CHARACTER),
+                    }
+                    self.report_lossy(offset, usize::from(len));
+                }
+            }
+        }
+        self.consumed += bytes.len();
+        Ok(text)
+    }
+
+    /// A `Utf8Error` for the invalid sequence at `input[position..]`, with its stream offset and
+    /// the surrounding text (invalid bytes shown as U+FFFD).
+    fn error(&self, source: std::str::Utf8Error, input: &[u8], position: usize) -> SegmenterError {
+        let before = String::from_utf8_lossy(&input[..position]);
+        let after = String::from_utf8_lossy(&input[position..]);
+        SegmenterError::Utf8Error {
+            source,
+            offset: Some(self.consumed + position),
+            snippet: crate::error::snippet(&before, &after),
+        }
+    }
+}
+
+/// An [`encoding_rs`] streaming decoder.
+#[cfg(feature = "encoding")]
+struct Legacy {
+    decoder: encoding_rs::Decoder,
+}
+
+#[cfg(feature = "encoding")]
+impl Legacy {
+    fn new(encoding: &'static encoding_rs::Encoding, sniff_bom: bool) -> Self {
+        let decoder = if sniff_bom {
+            encoding.new_decoder()
+        } else {
+            encoding.new_decoder_without_bom_handling()
+        };
+        Self { decoder }
+    }
+}
+
+#[cfg(feature = "encoding")]
+impl std::fmt::Debug for Legacy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Legacy")
+            .field("encoding", &self.decoder.encoding().name())
+            .finish()
+    }
+}
+
+/// Fails with `InvalidOptions` if the [`encoding`](SegmentOptions::encoding) of `options` is
+/// not a known label, or needs the `encoding` feature.
+pub fn check_encoding(options: &SegmentOptions) -> Result<()> {
+    encoding_for(options)
+        .map(|_| ())
+        .map_err(SegmenterError::InvalidOptions)
+}
+
+/// The encoding to transcode from, or `None` if the input is UTF-8 and needs no transcoder.
+#[cfg(feature = "encoding")]
+fn encoding_for(
+    options: &SegmentOptions,
+) -> std::result::Result<Option<&'static encoding_rs::Encoding>, String> {
+    let encoding = match &options.encoding {
+        Some(label) => encoding_rs::Encoding::for_label(label.trim().as_bytes())
+            .ok_or_else(|| format!("unknown encoding {:?}", label))?,
+        None => encoding_rs::UTF_8,
+    };
+    // Plain UTF-8 keeps the split-character handling and error offsets of `Utf8Decoder`
+    let transcode = encoding != encoding_rs::UTF_8 || options.sniff_bom;
+    Ok(transcode.then_some(encoding))
+}
+
+#[cfg(not(feature = "encoding"))]
+fn encoding_for(options: &SegmentOptions) -> std::result::Result<Option<()>, String> {
+    match &options.encoding {
+        Some(label) if !is_utf8_label(label) => {
+            Err(format!("encoding {:?} needs the `encoding` feature", label))
+        }
+        _ if options.sniff_bom => Err("BOM sniffing needs the `encoding` feature".to_string()),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(not(feature = "encoding"))]
+fn is_utf8_label(label: &str) -> bool {
+    matches!(
+        label.trim().to_ascii_lowercase().as_str(),
+        "utf-8" | "utf8" | "unicode-1-1-utf-8"
+    )
+}
@@ -0,0 +1,102 @@
+//! Dropping sentences that nearly repeat one seen shortly before, e.g. boilerplate such as
+//! "Copyright 2023 Example Inc." or "Click here to subscribe to our newsletter!" that recurs with
+//! small variations across a crawled corpus.
+//!
+//! Each sentence gets a 64-bit [`simhash`] of its character trigrams; two sentences are near
+//! duplicates if their hashes differ in at most a few bits. [`NearDuplicates`] remembers the
+//! hashes of a bounded window of recent sentences, so memory stays constant however long the
+//! stream is.
+
+use crate::ids::content_hash;
+use std::collections::VecDeque;
+
+/// Characters per shingle; short enough that sentences of a few words still have many.
+const SHINGLE: usize = 3;
+
+/// The text a sentence is compared by: lower-case, digits replaced by `0` and runs of
+/// whitespace and punctuation collapsed into one space, so dates, counters and punctuation
+/// variants do not tell sentences apart.
+fn normalize(text: &str) -> Vec<char> {
+    let mut normalized = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            let c = if c.is_numeric() { '0' } else { c };
+            normalized.extend(c.to_lowercase());
+        } else if normalized.last().is_some_and(|&last| last != ' ') {
+            normalized.push(' ');
+        }
+    }
+    if normalized.last() == Some(&' ') {
+        normalized.pop();
+    }
+    normalized
+}
+
+/// 64-bit SimHash of `text`'s character trigrams (after normalization): similar texts get
+/// hashes that differ in few bits.
+pub fn simhash(text: &str) -> u64 {
+    let chars = normalize(text);
+    let mut weights = [0i32; 64];
+    let mut add = |shingle: &[char]| {
+        let hash = content_hash(&shingle.iter().collect::<String>());
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if (hash >> bit) & 1 == 1 { 1 } else { -1 };
+        }
+    };
+    if chars.len() < SHINGLE {
+        add(&chars);
+    } else {
+        chars.windows(SHINGLE).for_each(&mut add);
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|&(_, &weight)| weight > 0)
+        .fold(0, |hash, (bit, _)| hash | (1 << bit))
+}
+
+/// Number of bits in which two [`simhash`]es differ.
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Filters near-duplicate sentences out of a stream.
+#[derive(Debug, Clone)]
+pub struct NearDuplicates {
+    max_distance: u32,
+    window: usize,
+    recent: VecDeque<u64>,
+}
+
+impl NearDuplicates {
+    /// Treats a sentence as a duplicate if its hash is within `max_distance` bits of one of the
+    /// last `window` sentences kept. 0 only catches sentences equal after normalization; 3 to 6
+    /// also catches small edits. The window costs 8 bytes per sentence.
+    pub fn new(max_distance: u32, window: usize) -> Self {
+        Self {
+            max_distance,
+            window,
+            recent: VecDeque::with_capacity(window.min(4096)),
+        }
+    }
+
+    /// Whether `text` nearly repeats a recent sentence. Sentences that do not are remembered;
+    /// duplicates are not, so a run of variants cannot drift away from the first one.
+    pub fn is_duplicate(&mut self, text: &str) -> bool {
+        let hash = simhash(text);
+        if self
+            .recent
+            .iter()
+            .any(|&seen| distance(seen, hash) <= self.max_distance)
+        {
+            return true;
+        }
+        if self.window > 0 {
+            if self.recent.len() == self.window {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(hash);
+        }
+        false
+    }
+}
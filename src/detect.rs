@@ -0,0 +1,165 @@
+//! Language detection for `language: "auto"`, with [whatlang](https://docs.rs/whatlang) (`detect`
+//! feature).
+//!
+//! A [`Segmenter`](crate::Segmenter) created with [`AUTO_LANGUAGE`] holds back the first
+//! [`detection_window`](crate::SegmentOptions::detection_window) bytes, detects their language
+//! and segments the whole stream with that language's rules.
+//! [`Segmenter::language`](crate::Segmenter::language) and the per-language
+//! [`StreamStats`](crate::StreamStats) report the detected code.
+//!
+//! For streams that switch language from one sentence to the next, such as chat transcripts,
+//! [`SentenceLanguage`] detects the language of every sentence instead. Single sentences are
+//! too short to tell among all languages, so list the ones to expect in
+//! [`sentence_languages`](crate::SegmentOptions::sentence_languages).
+
+#[cfg(feature = "detect")]
+use crate::error::{Result, SegmenterError};
+
+/// The language code that asks for detection.
+pub const AUTO_LANGUAGE: &str = "auto";
+
+/// Whether each sentence is given a language of its own
+/// ([`SegmentOptions::sentence_language`](crate::SegmentOptions::sentence_language)).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SentenceLanguage {
+    /// Sentences carry no language.
+    #[default]
+    Off,
+    /// Detect the language of every sentence and report it in
+    /// [`Segment::language`](crate::Segment::language).
+    Tag,
+    /// Tag, and when a sentence turns out to be in another language than the rules in use,
+    /// segment it again with that language's rules, which then stay until the language changes
+    /// again.
+    Switch,
+}
+
+/// The language of `text` as an ISO 639-1 code (ISO 639-3 for the few without one), if
+/// whatlang can tell it reliably.
+#[cfg(feature = "detect")]
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let info = whatlang::detect(text).filter(whatlang::Info::is_reliable)?;
+    Some(iso_639_1(info.lang()))
+}
+
+/// Lowest whatlang confidence at which the best of the candidate languages is taken as a
+/// sentence's language.
+#[cfg(feature = "detect")]
+const MIN_CANDIDATE_CONFIDENCE: f64 = 0.1;
+
+/// The language of the sentence `text` among `candidates` (language codes), or among all
+/// languages if there are none, if whatlang is confident enough.
+#[cfg(feature = "detect")]
+pub fn detect_sentence_language(text: &str, candidates: &[String]) -> Option<&'static str> {
+    if candidates.is_empty() {
+        return detect_language(text);
+    }
+    let allowlist = whatlang::Lang::all()
+        .iter()
+        .copied()
+        .filter(|&lang| candidates.iter().any(|code| code == iso_639_1(lang)))
+        .collect();
+    let info = whatlang::Detector::with_allowlist(allowlist).detect(text)?;
+    (info.confidence() >= MIN_CANDIDATE_CONFIDENCE).then(|| iso_639_1(info.lang()))
+}
+
+/// Checks that whatlang knows every code in `candidates`.
+#[cfg(feature = "detect")]
+pub(crate) fn check_candidates(candidates: &[String]) -> Result<()> {
+    match candidates.iter().find(|&code| {
+        !whatlang::Lang::all()
+            .iter()
+            .any(|&lang| iso_639_1(lang) == code)
+    }) {
+        Some(code) => Err(SegmenterError::InvalidOptions(format!(
+            "language detection does not know '{}'",
+            code
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The two-letter code of a whatlang language, as the language rules are keyed by.
+#[cfg(feature = "detect")]
+fn iso_639_1(lang: whatlang::Lang) -> &'static str {
+    use whatlang::Lang::*;
+    match lang {
+        Afr => "af",
+        Aka => "ak",
+        Amh => "am",
+        Ara => "ar",
+        Aze => "az",
+        Bel => "be",
+        Ben => "bn",
+        Bul => "bg",
+        Cat => "ca",
+        Ces => "cs",
+        Cmn => "zh",
+        Dan => "da",
+        Deu => "de",
+        Ell => "el",
+        Eng => "en",
+        Epo => "eo",
+        Est => "et",
+        Fin => "fi",
+        Fra => "fr",
+        Guj => "gu",
+        Heb => "he",
+        Hin => "hi",
+        Hrv => "hr",
+        Hun => "hu",
+        Hye => "hy",
+        Ind => "id",
+        Ita => "it",
+        Jav => "jv",
+        Jpn => "ja",
+        Kan => "kn",
+        Kat => "ka",
+        Khm => "km",
+        Kor => "ko",
+        Lat => "la",
+        Lav => "lv",
+        Lit => "lt",
+        Mal => "ml",
+        Mar => "mr",
+        Mkd => "mk",
+        Mya => "my",
+        Nep => "ne",
+        Nld => "nl",
+        Nob => "nb",
+        Ori => "or",
+        Pan => "pa",
+        Pes => "fa",
+        Pol => "pl",
+        Por => "pt",
+        Ron => "ro",
+        Rus => "ru",
+        Sin => "si",
+        Slk => "sk",
+        Slv => "sl",
+        Sna => "sn",
+        Spa => "es",
+        Srp => "sr",
+        Swe => "sv",
+        Tam => "ta",
+        Tel => "te",
+        Tgl => "tl",
+        Tha => "th",
+        Tuk => "tk",
+        Tur => "tr",
+        Ukr => "uk",
+        Urd => "ur",
+        Uzb => "uz",
+        Vie => "vi",
+        Yid => "yi",
+        Zul => "zu",
+        #[allow(unreachable_patterns)]
+        other => other.code(),
+    }
+}
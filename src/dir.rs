@@ -0,0 +1,119 @@
+//! Directory segmentation: walks a directory tree and segments every text file in it, tagging
+//! each sentence with the file it came from.
+
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use crate::segmenter::{segment_document, Segment};
+use async_stream::stream;
+use futures::stream::{self, Stream, StreamExt};
+use std::path::{Path, PathBuf};
+
+/// Segments every text file below `path`, one file at a time.
+///
+/// Files are visited in sorted path order, so the output is reproducible. Files that are not
+/// valid UTF-8 are treated as binary and skipped.
+pub fn segment_dir<'a>(
+    path: impl AsRef<Path>,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<(PathBuf, Segment)>> + 'a {
+    segment_dir_parallel(path, options, 1)
+}
+
+/// Like [`segment_dir`], but segments up to `parallelism` files concurrently on the blocking
+/// thread pool. Sentences are still yielded file by file, in sorted path order, so the output
+/// is identical for every `parallelism`.
+pub fn segment_dir_parallel<'a>(
+    path: impl AsRef<Path>,
+    options: SegmentOptions,
+    parallelism: usize,
+) -> impl Stream<Item = Result<(PathBuf, Segment)>> + 'a {
+    segment_files(path.as_ref().to_path_buf(), options, parallelism, true)
+}
+
+/// Like [`segment_dir_parallel`], but yields each file as soon as it is done instead of
+/// waiting for the files before it. The sentences of one file stay together and in order;
+/// the order of the files may differ from run to run.
+pub fn segment_dir_unordered<'a>(
+    path: impl AsRef<Path>,
+    options: SegmentOptions,
+    parallelism: usize,
+) -> impl Stream<Item = Result<(PathBuf, Segment)>> + 'a {
+    segment_files(path.as_ref().to_path_buf(), options, parallelism, false)
+}
+
+/// Segments the files below `root`, `parallelism` at a time, yielding them in path order if
+/// `ordered`, in completion order otherwise.
+fn segment_files<'a>(
+    root: PathBuf,
+    options: SegmentOptions,
+    parallelism: usize,
+    ordered: bool,
+) -> impl Stream<Item = Result<(PathBuf, Segment)>> + 'a {
+    stream! {
+        let files = match list_files(&root).await {
+            Ok(files) => files,
+            Err(e) => {
+                yield Err(SegmenterError::IoError(e));
+                return;
+            }
+        };
+
+        let jobs = stream::iter(files).map(|file| {
+            let options = options.clone();
+            async move {
+                let result = segment_file(&file, options).await;
+                (file, result)
+            }
+        });
+        let results = if ordered {
+            jobs.buffered(parallelism.max(1)).left_stream()
+        } else {
+            jobs.buffer_unordered(parallelism.max(1)).right_stream()
+        };
+        futures::pin_mut!(results);
+
+        while let Some((file, result)) = results.next().await {
+            match result {
+                Ok(segments) => {
+                    for segment in segments {
+                        yield Ok((file.clone(), segment));
+                    }
+                }
+                Err(e) => yield Err(e),
+            }
+        }
+    }
+}
+
+/// Reads and segments a single file; non-UTF-8 files produce no segments.
+async fn segment_file(path: &Path, options: SegmentOptions) -> Result<Vec<Segment>> {
+    let bytes = tokio::fs::read(path).await?;
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(_) => return Ok(Vec::new()),
+    };
+    tokio::task::spawn_blocking(move || segment_document(&text, options))
+        .await
+        .map_err(|e| SegmenterError::StreamError(e.to_string()))?
+}
+
+/// Collects all regular files below `root`, sorted by path.
+async fn list_files(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
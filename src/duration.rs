@@ -0,0 +1,54 @@
+//! Rough speaking-time estimates for sentences, so TTS schedulers and subtitle generators can plan
+//! timing before any audio exists.
+
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Languages written without spaces between words; their rate is counted in characters per
+/// minute instead of words per minute.
+const CHARACTER_LANGUAGES: [&str; 6] = ["zh", "ja", "ko", "th", "lo", "km"];
+
+/// Extra time for the pause after a sentence-final punctuation mark.
+const SENTENCE_PAUSE: Duration = Duration::from_millis(250);
+
+/// Typical reading-aloud rate for `language`, in words per minute (characters per minute for
+/// languages listed in [`CHARACTER_LANGUAGES`]).
+pub fn default_rate(language: &str) -> f64 {
+    match base_language(language) {
+        "zh" => 250.0,
+        "ja" => 350.0,
+        "ko" => 300.0,
+        "th" | "lo" | "km" => 300.0,
+        "de" | "nl" | "fi" | "ru" => 140.0,
+        "es" | "it" | "fr" | "pt" => 170.0,
+        _ => 155.0,
+    }
+}
+
+/// Estimates how long `text` takes to speak in `language`.
+///
+/// `rate` overrides the language default; it is words per minute, or characters per minute for
+/// languages written without spaces.
+pub fn estimate_duration(text: &str, language: &str, rate: Option<f64>) -> Duration {
+    let rate = rate.unwrap_or_else(|| default_rate(language));
+    let units = if CHARACTER_LANGUAGES.contains(&base_language(language)) {
+        text.chars().filter(|c| c.is_alphanumeric()).count()
+    } else {
+        text.unicode_words().count()
+    };
+    if units == 0 || !(rate.is_finite() && rate > 0.0) {
+        return Duration::ZERO;
+    }
+
+    let speech = Duration::from_secs_f64(units as f64 * 60.0 / rate);
+    if text.trim_end().ends_with(['.', '!', '?', '。', '！', '？']) {
+        speech + SENTENCE_PAUSE
+    } else {
+        speech
+    }
+}
+
+/// Strips a region suffix such as `-US` or `_BR` from a language code.
+fn base_language(language: &str) -> &str {
+    language.split(['-', '_']).next().unwrap_or(language)
+}
@@ -5,23 +5,204 @@ pub enum SegmenterError {
     #[error("Language '{0}' not supported by underlying libtqsm")]
     UnsupportedLanguage(String),
 
+    #[error("Unknown segmentation backend '{0}'")]
+    UnsupportedBackend(String),
+
     #[error("Failed to load language data for '{0}': {1}")]
-    LanguageLoadError(String, anyhow::Error), // Or more specific error type
+    LanguageLoadError(String, Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("Invalid options: {0}")]
+    InvalidOptions(String),
+
+    /// `offset` is where the rejected chunk would have started in the stream; `snippet` shows
+    /// the text on either side of it. `pending` hands back everything the segmenter held (the
+    /// buffered text followed by the rejected chunk), which leaves it empty and ready for more
+    /// input.
+    #[error(
+        "Buffer overflow: Maximum buffer size of {max} characters exceeded at byte {offset} near {snippet:?}"
+    )]
+    BufferOverflow {
+        max: usize,
+        offset: usize,
+        snippet: String,
+        pending: String,
+    },
 
-    #[error("Buffer overflow: Maximum buffer size of {0} characters exceeded")]
-    BufferOverflow(usize),
+    /// The shared [`MemoryPool`](crate::memory::MemoryPool) had no room for a chunk under
+    /// [`MemoryPolicy::Reject`](crate::memory::MemoryPolicy::Reject). The segmenter is unchanged;
+    /// `pending` hands back the rejected chunk, which starts at `offset`.
+    #[error("Memory budget of {capacity} bytes exhausted at byte {offset}")]
+    MemoryBudgetExceeded {
+        capacity: usize,
+        offset: usize,
+        pending: String,
+    },
 
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
 
-    #[error("UTF-8 decoding error: {0}")]
-    Utf8Error(#[from] std::str::Utf8Error),
+    /// `offset` is the absolute position of the invalid byte in the input, if known.
+    #[error("UTF-8 decoding error: {source}{}", location(.offset, .snippet))]
+    Utf8Error {
+        source: std::str::Utf8Error,
+        offset: Option<usize>,
+        snippet: String,
+    },
+
+    /// Bytes that are not valid in the configured
+    /// [`encoding`](crate::SegmentOptions::encoding), at stream offset `offset`.
+    #[error("{encoding} decoding error at byte {offset}")]
+    DecodeError {
+        encoding: &'static str,
+        offset: usize,
+    },
 
     #[error("Stream processing error: {0}")]
     StreamError(String), // Generic stream error
 
-    #[error("Underlying segmentation error: {0}")]
-    SegmentationError(#[from] anyhow::Error),
+    #[error("Underlying segmentation error: {source}{}", location(.offset, .snippet))]
+    SegmentationError {
+        source: Box<dyn std::error::Error + Send + Sync>,
+        offset: Option<usize>,
+        snippet: String,
+    },
+}
+
+impl From<std::str::Utf8Error> for SegmenterError {
+    fn from(source: std::str::Utf8Error) -> Self {
+        SegmenterError::Utf8Error {
+            source,
+            offset: None,
+            snippet: String::new(),
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for SegmenterError {
+    fn from(source: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        SegmenterError::SegmentationError {
+            source,
+            offset: None,
+            snippet: String::new(),
+        }
+    }
+}
+
+impl SegmenterError {
+    /// Short name of the error variant, e.g. for metric labels. Like [`code`](Self::code), a
+    /// name is never changed or reused once released.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SegmenterError::UnsupportedLanguage(_) => "unsupported_language",
+            SegmenterError::UnsupportedBackend(_) => "unsupported_backend",
+            SegmenterError::LanguageLoadError(..) => "language_load",
+            SegmenterError::InvalidOptions(_) => "invalid_options",
+            SegmenterError::BufferOverflow { .. } => "buffer_overflow",
+            SegmenterError::MemoryBudgetExceeded { .. } => "memory_budget",
+            SegmenterError::IoError(_) => "io",
+            SegmenterError::Utf8Error { .. } => "utf8",
+            SegmenterError::StreamError(_) => "stream",
+            SegmenterError::SegmentationError { .. } => "segmentation",
+            SegmenterError::DecodeError { .. } => "decode",
+        }
+    }
+
+    /// Numeric code of the error variant, for protocols that carry numbers rather than names
+    /// (the C API, gRPC metadata, JSON-RPC error data). Codes are never changed or reused; new
+    /// variants get the next free number.
+    ///
+    /// | code | kind |
+    /// |-----:|------|
+    /// | 1 | `unsupported_language` |
+    /// | 2 | `unsupported_backend` |
+    /// | 3 | `language_load` |
+    /// | 4 | `invalid_options` |
+    /// | 5 | `buffer_overflow` |
+    /// | 6 | `memory_budget` |
+    /// | 7 | `io` |
+    /// | 8 | `utf8` |
+    /// | 9 | `stream` |
+    /// | 10 | `segmentation` |
+    /// | 11 | `decode` |
+    pub fn code(&self) -> u32 {
+        match self {
+            SegmenterError::UnsupportedLanguage(_) => 1,
+            SegmenterError::UnsupportedBackend(_) => 2,
+            SegmenterError::LanguageLoadError(..) => 3,
+            SegmenterError::InvalidOptions(_) => 4,
+            SegmenterError::BufferOverflow { .. } => 5,
+            SegmenterError::MemoryBudgetExceeded { .. } => 6,
+            SegmenterError::IoError(_) => 7,
+            SegmenterError::Utf8Error { .. } => 8,
+            SegmenterError::StreamError(_) => 9,
+            SegmenterError::SegmentationError { .. } => 10,
+            SegmenterError::DecodeError { .. } => 11,
+        }
+    }
+
+    /// Absolute byte offset in the input stream the error refers to, if known.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            SegmenterError::BufferOverflow { offset, .. }
+            | SegmenterError::MemoryBudgetExceeded { offset, .. }
+            | SegmenterError::DecodeError { offset, .. } => Some(*offset),
+            SegmenterError::Utf8Error { offset, .. }
+            | SegmenterError::SegmentationError { offset, .. } => *offset,
+            _ => None,
+        }
+    }
+}
+
+/// Lets segmentation errors flow through `std::io` / `tokio::io` plumbing (`io::copy`, codecs,
+/// `StreamReader`). I/O errors are unwrapped; everything else is wrapped with a matching
+/// [`ErrorKind`](std::io::ErrorKind) and can be recovered with `get_ref()` or `into_inner()` and
+/// a downcast.
+impl From<SegmenterError> for std::io::Error {
+    fn from(error: SegmenterError) -> Self {
+        use std::io::ErrorKind;
+
+        let kind = match error {
+            SegmenterError::IoError(e) => return e,
+            SegmenterError::UnsupportedLanguage(_)
+            | SegmenterError::UnsupportedBackend(_)
+            | SegmenterError::InvalidOptions(_) => ErrorKind::InvalidInput,
+            SegmenterError::Utf8Error { .. } | SegmenterError::DecodeError { .. } => {
+                ErrorKind::InvalidData
+            }
+            SegmenterError::BufferOverflow { .. } | SegmenterError::MemoryBudgetExceeded { .. } => {
+                ErrorKind::OutOfMemory
+            }
+            SegmenterError::LanguageLoadError(..)
+            | SegmenterError::StreamError(_)
+            | SegmenterError::SegmentationError { .. } => ErrorKind::Other,
+        };
+        std::io::Error::new(kind, error)
+    }
+}
+
+/// Bytes of context shown on each side of an error position.
+const SNIPPET_CONTEXT: usize = 24;
+
+/// Up to [`SNIPPET_CONTEXT`] bytes from the end of `before` and the start of `after`, cut at
+/// character boundaries.
+pub(crate) fn snippet(before: &str, after: &str) -> String {
+    let mut start = before.len().saturating_sub(SNIPPET_CONTEXT);
+    while !before.is_char_boundary(start) {
+        start += 1;
+    }
+    let mut end = after.len().min(SNIPPET_CONTEXT);
+    while !after.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &before[start..], &after[..end])
+}
+
+fn location(offset: &Option<usize>, snippet: &str) -> String {
+    match offset {
+        Some(offset) if snippet.is_empty() => format!(" at byte {}", offset),
+        Some(offset) => format!(" at byte {} near {:?}", offset, snippet),
+        None => String::new(),
+    }
 }
 
 pub type Result<T> = std::result::Result<T, SegmenterError>;
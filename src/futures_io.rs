@@ -0,0 +1,144 @@
+//! Runtime-agnostic entry point for readers implementing [`futures::io::AsyncRead`], so
+//! async-std and smol users can segment their streams without a tokio adapter.
+//!
+//! The functions mirror the tokio ones at the crate root and share their decoding and
+//! segmentation; only reading differs. Build with `default-features = false, features =
+//! ["futures-io"]` to leave tokio out of the dependency tree.
+
+use crate::chunking::ChunkSizer;
+use crate::config::SegmentOptions;
+use crate::decode::Utf8Decoder;
+use crate::error::Result;
+use crate::item::StreamItem;
+use crate::retry::RetryPolicy;
+use crate::segmenter::{Segment, SentenceSegmenter};
+use async_stream::stream;
+use futures::io::{AsyncRead, AsyncReadExt, BufReader};
+use futures::stream::{Stream, StreamExt};
+
+/// Same as [`crate::sentences_stream`], for a `futures::io::AsyncRead` reader (e.g.
+/// `async_std::fs::File` or `smol::Unblock<std::io::Stdin>`).
+pub fn sentences_stream<'a, R>(
+    reader: R,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<String>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+{
+    crate::sentences_from_chunks(
+        read_chunks(
+            reader,
+            options.retry.clone(),
+            ChunkSizer::for_options(&options),
+        ),
+        options,
+    )
+}
+
+/// Same as [`crate::segments_stream`], for a `futures::io::AsyncRead` reader.
+pub fn segments_stream<'a, R>(
+    reader: R,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<Segment>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+{
+    crate::segments_from_chunks(
+        read_chunks(
+            reader,
+            options.retry.clone(),
+            ChunkSizer::for_options(&options),
+        ),
+        options,
+    )
+}
+
+/// Same as [`crate::segments_stream_with`], for a `futures::io::AsyncRead` reader.
+pub fn segments_stream_with<'a, R, G>(
+    reader: R,
+    segmenter: G,
+    options: &SegmentOptions,
+) -> impl Stream<Item = Result<Segment>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+    G: SentenceSegmenter + Send + 'a,
+{
+    crate::only_segments(crate::items_with(
+        read_chunks(
+            reader,
+            options.retry.clone(),
+            ChunkSizer::for_options(options),
+        ),
+        Utf8Decoder::for_options(options),
+        Ok(segmenter),
+    ))
+}
+
+/// Same as [`crate::sentences_stream_with`], for a `futures::io::AsyncRead` reader.
+pub fn sentences_stream_with<'a, R, G>(
+    reader: R,
+    segmenter: G,
+    options: &SegmentOptions,
+) -> impl Stream<Item = Result<String>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+    G: SentenceSegmenter + Send + 'a,
+{
+    segments_stream_with(reader, segmenter, options)
+        .map(|segment| segment.map(|segment| segment.text))
+}
+
+/// Same as [`crate::items_stream`], for a `futures::io::AsyncRead` reader.
+pub fn items_stream<'a, R>(
+    reader: R,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<StreamItem>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+{
+    crate::items_from_chunks(
+        read_chunks(
+            reader,
+            options.retry.clone(),
+            ChunkSizer::for_options(&options),
+        ),
+        options,
+    )
+}
+
+/// Reads `reader` in chunks sized by `sizer` until EOF or the first I/O error that `retry` gives
+/// up on.
+fn read_chunks<'a, R>(
+    reader: R,
+    retry: RetryPolicy,
+    mut sizer: ChunkSizer,
+) -> impl Stream<Item = std::io::Result<Vec<u8>>> + Send + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+{
+    stream! {
+        let mut buf_reader = BufReader::new(reader);
+        let mut buffer = vec![0; sizer.max_size()];
+        let mut failures = 0;
+
+        loop {
+            let started = std::time::Instant::now();
+            match buf_reader.read(&mut buffer[..sizer.size()]).await {
+                Ok(0) => break, // EOF reached
+                Ok(n) => {
+                    failures = 0;
+                    sizer.record(n, started.elapsed());
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(bytes = n, "read chunk");
+                    yield Ok(buffer[..n].to_vec());
+                }
+                // No timer without a runtime, so retries happen immediately
+                Err(e) if retry.should_retry(e.kind(), failures + 1) => failures += 1,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+    }
+}
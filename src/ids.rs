@@ -0,0 +1,59 @@
+//! Identifiers that let later pipeline stages refer to a sentence unambiguously: the document
+//! it came from, its index within that document, and a hash of its text.
+
+/// A stable 64-bit hash of `text` (FNV-1a), identical across platforms, runs and crate
+/// versions, so it can be stored and compared later.
+pub fn content_hash(text: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    text.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// [`content_hash`] as 16 lowercase hex digits.
+pub fn content_hash_hex(text: &str) -> String {
+    format!("{:016x}", content_hash(text))
+}
+
+/// How [`fingerprint`] hashes a sentence. Every algorithm gives the same result across
+/// platforms, runs and crate versions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum HashAlgorithm {
+    /// 64-bit FNV-1a, see [`content_hash`]; 16 hex digits.
+    #[default]
+    Fnv,
+    /// 64-bit XXH3, much faster on long sentences; 16 hex digits.
+    #[cfg(feature = "fingerprint")]
+    Xxh3,
+    /// SHA-1, for pipelines that already key documents by it; 40 hex digits.
+    #[cfg(feature = "fingerprint")]
+    Sha1,
+}
+
+/// `text` hashed with `algorithm`, as lowercase hex digits, cut to the first `prefix` digits if
+/// given; a short prefix of a long hash saves space in large corpora.
+pub fn fingerprint(text: &str, algorithm: HashAlgorithm, prefix: Option<usize>) -> String {
+    let mut hex = match algorithm {
+        HashAlgorithm::Fnv => content_hash_hex(text),
+        #[cfg(feature = "fingerprint")]
+        HashAlgorithm::Xxh3 => format!("{:016x}", xxhash_rust::xxh3::xxh3_64(text.as_bytes())),
+        #[cfg(feature = "fingerprint")]
+        HashAlgorithm::Sha1 => sha1_smol::Sha1::from(text).digest().to_string(),
+    };
+    if let Some(prefix) = prefix {
+        hex.truncate(prefix);
+    }
+    hex
+}
+
+/// The identifier of sentence `index` in document `doc_id`, e.g. `report.txt#12`.
+pub fn sentence_id(doc_id: &str, index: usize) -> String {
+    format!("{}#{}", doc_id, index)
+}
@@ -0,0 +1,78 @@
+//! Stream items that include an explicit end marker, so consumers can tell a stream that ran
+//! to completion from one that was dropped or ended by an error, and collect final statistics.
+
+use crate::segmenter::Segment;
+use std::collections::BTreeMap;
+
+/// One item of [`items_stream`](crate::items_stream).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum StreamItem {
+    /// A sentence.
+    Segment(Segment),
+    /// The input ended normally; always the last item. Not sent after a fatal error.
+    End {
+        /// The trailing fragment without a sentence boundary, if there was one. Under
+        /// [`TailPolicy::Drop`](crate::TailPolicy::Drop) this is the only place it shows up;
+        /// otherwise it was also yielded as the last segment.
+        flushed_tail: Option<String>,
+        stats: StreamStats,
+    },
+}
+
+/// One item of [`segment_events_stream`](crate::segment_events_stream), for interfaces that
+/// render a sentence while it is being written, e.g. live captions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SegmentEvent {
+    /// The text received but not yet final, sent whenever it changes; it replaces the
+    /// previous preview.
+    Partial(String),
+    /// A sentence whose boundary is confirmed; it ends the current preview.
+    Final(String),
+}
+
+/// Totals for one stream.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamStats {
+    /// Sentences yielded, including an emitted tail.
+    pub sentences: usize,
+    /// Input bytes read.
+    pub bytes: usize,
+    /// Segmentation errors that were reported without ending the stream.
+    pub errors: usize,
+    /// Sentences and sentence bytes per language code, for streams whose language changes
+    /// along the way; a single entry otherwise.
+    pub languages: BTreeMap<String, LanguageStats>,
+}
+
+/// Totals for the sentences of one language.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LanguageStats {
+    pub sentences: usize,
+    /// Bytes of sentence text, excluding the whitespace between sentences.
+    pub bytes: usize,
+}
+
+impl StreamStats {
+    /// Counts `segment`, which was segmented with the rules for `language`, under its own
+    /// [`language`](Segment::language) if it has one.
+    #[cfg(feature = "_stream")]
+    pub(crate) fn record(&mut self, segment: &Segment, language: &str) {
+        self.sentences += 1;
+        let language = segment.language.as_deref().unwrap_or(language);
+        let entry = self.languages.entry(language.to_string()).or_default();
+        entry.sentences += 1;
+        entry.bytes += segment.text.len();
+    }
+}
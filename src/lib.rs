@@ -1,13 +1,83 @@
+#![cfg_attr(feature = "nightly", feature(async_iterator))]
+
+#[cfg(feature = "_stream")]
 use async_stream::stream;
-use futures::stream::Stream;
+#[cfg(feature = "_stream")]
+use decode::Utf8Decoder;
+#[cfg(feature = "_stream")]
+use futures::stream::{Stream, StreamExt};
+#[cfg(feature = "tokio")]
 use tokio::io::{AsyncRead, AsyncReadExt, BufReader};
+pub mod abbreviations;
+#[cfg(feature = "align")]
+pub mod align;
+#[cfg(feature = "nightly")]
+pub mod async_iter;
+pub mod audit;
+pub mod backend;
+pub mod blocking;
+#[cfg(any(feature = "http-body", feature = "reqwest"))]
+pub mod body;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod chat;
+pub mod chunking;
+#[cfg(feature = "codec")]
+pub mod codec;
+#[cfg(feature = "comments")]
+pub mod comments;
+#[cfg(feature = "compare")]
+pub mod compare;
 pub mod config;
+#[cfg(any(feature = "nats", feature = "redis"))]
+pub mod connectors;
+pub mod decode;
+#[cfg(feature = "dedup")]
+pub mod dedup;
+pub mod detect;
+#[cfg(feature = "tokio")]
+pub mod dir;
+#[cfg(feature = "duration")]
+pub mod duration;
 mod error;
+#[cfg(feature = "futures-io")]
+pub mod futures_io;
+pub mod ids;
+pub mod item;
+#[cfg(feature = "logging")]
+pub mod logging;
+pub mod logs;
+pub mod memory;
+#[cfg(feature = "node")]
+mod node;
+pub mod postprocess;
+pub mod protect;
+#[cfg(feature = "python")]
+mod python;
+pub mod quotes;
+pub mod retry;
+pub mod rules;
+#[cfg(feature = "sample")]
+pub mod sample;
+pub mod scripts;
 mod segmenter;
+#[cfg(feature = "tokio")]
+pub mod server;
+#[cfg(feature = "tokio")]
+pub mod source;
+#[cfg(feature = "subtitles")]
+pub mod subtitles;
+#[cfg(feature = "verbalize")]
+pub mod verbalize;
+pub mod warnings;
+#[cfg(feature = "wasm")]
+mod wasm;
 
+pub use backend::SegmentationBackend;
 pub use config::SegmentOptions;
 pub use error::{Result, SegmenterError};
-pub use segmenter::Segmenter;
+pub use item::{LanguageStats, SegmentEvent, StreamItem, StreamStats};
+pub use segmenter::{OverflowPolicy, Segment, Segmenter, SentenceSegmenter, TailPolicy};
 
 /// Creates an asynchronous stream of sentences from a reader.
 ///
@@ -23,6 +93,7 @@ pub use segmenter::Segmenter;
 ///
 /// An implementation of `Stream` that yields `Result<String, SegmenterError>`.
 /// Errors during I/O or segmentation will be returned as `Err` variants in the stream.
+#[cfg(feature = "tokio")]
 pub fn sentences_stream<'a, R>(
     reader: R,
     options: SegmentOptions,
@@ -30,39 +101,431 @@ pub fn sentences_stream<'a, R>(
 where
     R: AsyncRead + Unpin + Send + 'a,
 {
+    sentences_from_chunks(
+        read_chunks(
+            reader,
+            options.retry.clone(),
+            chunking::ChunkSizer::for_options(&options),
+        ),
+        options,
+    )
+}
+
+/// Like [`sentences_stream`], but yields each sentence as a [`Segment`] with its index and
+/// stream offsets.
+#[cfg(feature = "tokio")]
+pub fn segments_stream<'a, R>(
+    reader: R,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<Segment>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+{
+    segments_from_chunks(
+        read_chunks(
+            reader,
+            options.retry.clone(),
+            chunking::ChunkSizer::for_options(&options),
+        ),
+        options,
+    )
+}
+
+/// Like [`segments_stream`], but ends with a [`StreamItem::End`] carrying the flushed tail and
+/// the stream's statistics once the input has been read completely.
+#[cfg(feature = "tokio")]
+pub fn items_stream<'a, R>(
+    reader: R,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<StreamItem>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+{
+    items_from_chunks(
+        read_chunks(
+            reader,
+            options.retry.clone(),
+            chunking::ChunkSizer::for_options(&options),
+        ),
+        options,
+    )
+}
+
+/// Like [`segments_stream`], but segments with `segmenter`, any [`SentenceSegmenter`]
+/// implementation. `options` still control reading and decoding: `read_size`, `retry` and
+/// `utf8_policy`.
+#[cfg(feature = "tokio")]
+pub fn segments_stream_with<'a, R, G>(
+    reader: R,
+    segmenter: G,
+    options: &SegmentOptions,
+) -> impl Stream<Item = Result<Segment>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+    G: SentenceSegmenter + Send + 'a,
+{
+    let chunks = read_chunks(
+        reader,
+        options.retry.clone(),
+        chunking::ChunkSizer::for_options(options),
+    );
+    only_segments(items_with(
+        idle_marks(chunks, options.flush_after),
+        Utf8Decoder::for_options(options),
+        Ok(segmenter),
+    ))
+}
+
+/// Like [`sentences_stream`], but segments with `segmenter`; see [`segments_stream_with`].
+#[cfg(feature = "tokio")]
+pub fn sentences_stream_with<'a, R, G>(
+    reader: R,
+    segmenter: G,
+    options: &SegmentOptions,
+) -> impl Stream<Item = Result<String>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+    G: SentenceSegmenter + Send + 'a,
+{
+    segments_stream_with(reader, segmenter, options)
+        .map(|segment| segment.map(|segment| segment.text))
+}
+
+/// Like [`sentences_stream`], but pairs each sentence with the zero-based index of its
+/// paragraph, for document chunking that needs both granularities. Sentences also end at the
+/// blank lines between paragraphs ([`SegmentUnit::Nested`](config::SegmentUnit::Nested)).
+#[cfg(feature = "tokio")]
+pub fn paragraph_sentences_stream<'a, R>(
+    reader: R,
+    mut options: SegmentOptions,
+) -> impl Stream<Item = Result<(usize, String)>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+{
+    options.unit = config::SegmentUnit::Nested;
+    segments_stream(reader, options)
+        .map(|segment| segment.map(|segment| (segment.paragraph.unwrap_or_default(), segment.text)))
+}
+
+/// Like [`sentences_stream`], but also previews the sentence being written: after each read
+/// that changes the text not yet final, a [`SegmentEvent::Partial`] carries that text, and
+/// every confirmed sentence arrives as a [`SegmentEvent::Final`]. Captions can render the
+/// partial text progressively and commit it on the final one.
+#[cfg(feature = "tokio")]
+pub fn segment_events_stream<'a, R>(
+    reader: R,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<SegmentEvent>> + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+{
+    let chunks = read_chunks(
+        reader,
+        options.retry.clone(),
+        chunking::ChunkSizer::for_options(&options),
+    );
+    events_from_chunks(idle_marks(chunks, options.flush_after), options)
+}
+
+/// Event variant of [`items_from_chunks`], with the same error handling.
+#[cfg(feature = "tokio")]
+fn events_from_chunks<'a, S>(
+    chunks: S,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<SegmentEvent>> + 'a
+where
+    S: Stream<Item = std::io::Result<Vec<u8>>> + Send + 'a,
+{
+    let mut decoder = Utf8Decoder::for_options(&options);
+    let post_process = options.post_process.clone();
+    let segmenter = Segmenter::new(options);
     stream! {
-        let mut segmenter = match Segmenter::new(options) {
+        let mut segmenter = match segmenter {
             Ok(s) => s,
             Err(e) => {
                 yield Err(e);
                 return;
             }
         };
+        let mut preview = String::new();
+
+        futures::pin_mut!(chunks);
+        while let Some(chunk) = chunks.next().await {
+            let fed = match chunk {
+                Ok(bytes) if bytes.is_empty() => segmenter.flush_idle(),
+                Ok(bytes) => match decoder.decode(&bytes) {
+                    Ok(text) => segmenter.feed_segments(&text),
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                },
+                Err(e) => {
+                    yield Err(SegmenterError::IoError(e));
+                    return;
+                }
+            };
+            match fed {
+                Ok(segments) => {
+                    for segment in segments {
+                        yield Ok(SegmentEvent::Final(segment.text));
+                    }
+                }
+                Err(e) => yield Err(e),
+            }
+            let pending = segmenter.buffered_text().trim();
+            let pending = match &post_process {
+                Some(post_process) if !pending.is_empty() => post_process.apply(pending.to_string()),
+                _ => pending.to_string(),
+            };
+            if pending != preview {
+                preview = pending;
+                if !preview.is_empty() {
+                    yield Ok(SegmentEvent::Partial(preview.clone()));
+                }
+            }
+        }
+
+        let rest = match decoder.finish() {
+            Ok(rest) => rest,
+            Err(e) => {
+                yield Err(e);
+                return;
+            }
+        };
+        let mut segments = match rest.map(|rest| segmenter.feed_segments(&rest)) {
+            Some(Err(e)) => {
+                yield Err(e);
+                Vec::new()
+            }
+            Some(Ok(segments)) => segments,
+            None => Vec::new(),
+        };
+        match segmenter.finish_segments() {
+            Ok(tail) => segments.extend(tail),
+            Err(e) => yield Err(e),
+        }
+        for segment in segments {
+            yield Ok(SegmentEvent::Final(segment.text));
+        }
+    }
+}
+
+/// Segments a stream of text chunks, e.g. the tokens of a language model API client, without
+/// wrapping it in an `AsyncRead`. Sentences still waiting for lookahead and the tail are emitted
+/// once the stream ends.
+#[cfg(feature = "_stream")]
+pub fn sentences_from_stream<'a, S>(
+    chunks: S,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<String>> + 'a
+where
+    S: Stream<Item = String> + Send + 'a,
+{
+    sentences_from_chunks(chunks.map(|chunk| Ok(chunk.into_bytes())), options)
+}
+
+/// Like [`sentences_from_stream`], for a stream of fallible chunks; an error ends the stream like
+/// an I/O error, as [`SegmenterError::IoError`] wrapping `E`.
+#[cfg(feature = "_stream")]
+pub fn try_sentences_from_stream<'a, S, E>(
+    chunks: S,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<String>> + 'a
+where
+    S: Stream<Item = std::result::Result<String, E>> + Send + 'a,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let chunks = chunks.map(|chunk| chunk.map(String::into_bytes).map_err(std::io::Error::other));
+    sentences_from_chunks(chunks, options)
+}
 
+/// Reads `reader` in chunks sized by `sizer` until EOF or the first I/O error that `retry` gives
+/// up on.
+#[cfg(feature = "tokio")]
+fn read_chunks<'a, R>(
+    reader: R,
+    retry: retry::RetryPolicy,
+    mut sizer: chunking::ChunkSizer,
+) -> impl Stream<Item = std::io::Result<Vec<u8>>> + Send + 'a
+where
+    R: AsyncRead + Unpin + Send + 'a,
+{
+    stream! {
         let mut buf_reader = BufReader::new(reader);
-        let mut buffer = [0; 4096]; // Read in 4KB chunks
+        let mut buffer = vec![0; sizer.max_size()];
+        let mut failures = 0;
 
         loop {
-            match buf_reader.read(&mut buffer).await {
-                Ok(0) => {
-                    // EOF reached
-                    break;
-                }
+            let started = std::time::Instant::now();
+            match buf_reader.read(&mut buffer[..sizer.size()]).await {
+                Ok(0) => break, // EOF reached
                 Ok(n) => {
-                    // Process the chunk
-                    // Need to handle potential UTF-8 errors if a character is split across chunks
-                    // BufReader should mitigate this for read_line, but read might still split.
-                    // A safer approach involves a dedicated UTF-8 aware buffer/decoder if read() is used directly.
-                    // For simplicity with `read`, we'll attempt direct conversion and handle errors.
-                    match std::str::from_utf8(&buffer[..n]) {
+                    failures = 0;
+                    sizer.record(n, started.elapsed());
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(bytes = n, "read chunk");
+                    yield Ok(buffer[..n].to_vec());
+                }
+                Err(e) => {
+                    failures += 1;
+                    if retry.should_retry(e.kind(), failures) {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(error = %e, attempt = failures, "retrying read");
+                        tokio::time::sleep(retry.delay(failures)).await;
+                        continue;
+                    }
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Segments a stream of raw byte chunks; shared by the tokio and `futures-io` readers.
+///
+/// An I/O error, or invalid UTF-8 under [`Utf8Policy::Strict`](decode::Utf8Policy::Strict), ends
+/// the stream without flushing; segmentation errors are reported and skipped.
+#[cfg(feature = "_stream")]
+fn sentences_from_chunks<'a, S>(
+    chunks: S,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<String>> + 'a
+where
+    S: Stream<Item = std::io::Result<Vec<u8>>> + Send + 'a,
+{
+    segments_from_chunks(chunks, options).map(|segment| segment.map(|segment| segment.text))
+}
+
+/// Positioned variant of [`sentences_from_chunks`].
+#[cfg(feature = "_stream")]
+fn segments_from_chunks<'a, S>(
+    chunks: S,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<Segment>> + 'a
+where
+    S: Stream<Item = std::io::Result<Vec<u8>>> + Send + 'a,
+{
+    only_segments(items_from_chunks(chunks, options))
+}
+
+/// The segments of an item stream, without its [`StreamItem::End`].
+#[cfg(feature = "_stream")]
+fn only_segments<'a>(
+    items: impl Stream<Item = Result<StreamItem>> + 'a,
+) -> impl Stream<Item = Result<Segment>> + 'a {
+    items.filter_map(|item| {
+        futures::future::ready(match item {
+            Ok(StreamItem::Segment(segment)) => Some(Ok(segment)),
+            Ok(StreamItem::End { .. }) => None,
+            Err(e) => Some(Err(e)),
+        })
+    })
+}
+
+/// Variant of [`segments_from_chunks`] ending with [`StreamItem::End`].
+#[cfg(feature = "_stream")]
+fn items_from_chunks<'a, S>(
+    chunks: S,
+    options: SegmentOptions,
+) -> impl Stream<Item = Result<StreamItem>> + 'a
+where
+    S: Stream<Item = std::io::Result<Vec<u8>>> + Send + 'a,
+{
+    let decoder = Utf8Decoder::for_options(&options);
+    #[cfg(feature = "tokio")]
+    let chunks = idle_marks(chunks, options.flush_after);
+    items_with(chunks, decoder, Segmenter::new(options))
+}
+
+/// Passes `chunks` through, inserting an empty chunk once no chunk has arrived for
+/// `flush_after`; [`items_with`] flushes the segmenter on it. Real reads are never empty.
+#[cfg(feature = "tokio")]
+fn idle_marks<'a, S>(
+    chunks: S,
+    flush_after: Option<std::time::Duration>,
+) -> impl Stream<Item = std::io::Result<Vec<u8>>> + Send + 'a
+where
+    S: Stream<Item = std::io::Result<Vec<u8>>> + Send + 'a,
+{
+    stream! {
+        futures::pin_mut!(chunks);
+        // One flush per pause: after it, wait for input however long it takes
+        let mut idle = false;
+        loop {
+            let next = match flush_after {
+                Some(after) if !idle => match tokio::time::timeout(after, chunks.next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        idle = true;
+                        yield Ok(Vec::new());
+                        continue;
+                    }
+                },
+                _ => chunks.next().await,
+            };
+            let Some(chunk) = next else { break };
+            idle = false;
+            yield chunk;
+        }
+    }
+}
+
+/// [`items_from_chunks`] with any [`SentenceSegmenter`]; a failure to create it is the stream's
+/// only item.
+#[cfg(feature = "_stream")]
+fn items_with<'a, S, G>(
+    chunks: S,
+    mut decoder: Utf8Decoder,
+    segmenter: Result<G>,
+) -> impl Stream<Item = Result<StreamItem>> + 'a
+where
+    S: Stream<Item = std::io::Result<Vec<u8>>> + Send + 'a,
+    G: SentenceSegmenter + Send + 'a,
+{
+    stream! {
+        let mut stats = StreamStats::default();
+        let mut segmenter = match segmenter {
+            Ok(s) => s,
+            Err(e) => {
+                yield Err(e);
+                return;
+            }
+        };
+
+        futures::pin_mut!(chunks);
+        while let Some(chunk) = chunks.next().await {
+            match chunk {
+                // The input paused (see `idle_marks`)
+                Ok(bytes) if bytes.is_empty() => match segmenter.flush_idle() {
+                    Ok(segments) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(sentences = segments.len(), "input idle, flushed");
+                        for segment in segments {
+                            stats.record(&segment, segmenter.language());
+                            yield Ok(StreamItem::Segment(segment));
+                        }
+                    }
+                    Err(e) => {
+                        stats.errors += 1;
+                        yield Err(e);
+                    }
+                },
+                Ok(bytes) => {
+                    stats.bytes += bytes.len();
+                    // Characters split across chunks are held back by the decoder
+                    match decoder.decode(&bytes) {
                          Ok(chunk_str) => {
-                              match segmenter.feed(chunk_str) {
-                                   Ok(sentences) => {
-                                       for sentence in sentences {
-                                           yield Ok(sentence);
+                              match segmenter.feed_segments(&chunk_str) {
+                                   Ok(segments) => {
+                                       for segment in segments {
+                                           stats.record(&segment, segmenter.language());
+                                           yield Ok(StreamItem::Segment(segment));
                                        }
                                    }
                                    Err(e) => {
+                                       stats.errors += 1;
                                        yield Err(e);
                                        // Decide whether to stop streaming on error
                                        // return;
@@ -70,13 +533,16 @@ where
                               }
                          }
                          Err(e) => {
-                             yield Err(SegmenterError::Utf8Error(e));
-                             // Decide whether to stop streaming on UTF-8 error
-                             // return;
+                             #[cfg(feature = "tracing")]
+                             tracing::error!(error = %e, "undecodable input, ending stream");
+                             yield Err(e);
+                             return;
                          }
                     }
                 }
                 Err(e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!(error = %e, "read failed, ending stream");
                     yield Err(SegmenterError::IoError(e));
                     // Stop streaming on I/O error
                     return;
@@ -84,14 +550,36 @@ where
             }
         }
 
-        // Flush any remaining text after EOF
-        match segmenter.flush() {
-            Ok(Some(last_sentence)) => {
-                if !last_sentence.is_empty() {
-                    yield Ok(last_sentence);
+        // An incomplete character at EOF is subject to the UTF-8 policy as well
+        match decoder.finish() {
+            Ok(Some(tail)) => match segmenter.feed_segments(&tail) {
+                Ok(segments) => {
+                    for segment in segments {
+                        stats.record(&segment, segmenter.language());
+                        yield Ok(StreamItem::Segment(segment));
+                    }
+                }
+                Err(e) => {
+                    stats.errors += 1;
+                    yield Err(e);
+                }
+            },
+            Ok(None) => {}
+            Err(e) => {
+                yield Err(e);
+                return;
+            }
+        }
+
+        // Emit what is still waiting for lookahead, then the tail per the tail policy
+        match segmenter.finish_with_tail() {
+            Ok((segments, flushed_tail)) => {
+                for segment in segments {
+                    stats.record(&segment, segmenter.language());
+                    yield Ok(StreamItem::Segment(segment));
                 }
+                yield Ok(StreamItem::End { flushed_tail, stats });
             }
-            Ok(None) => { /* No remaining text, do nothing */ }
             Err(e) => {
                 yield Err(e);
             }
@@ -102,16 +590,21 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "tokio")]
     use futures::pin_mut;
+    #[cfg(any(feature = "tokio", feature = "futures-io"))]
     use futures::StreamExt;
+    #[cfg(feature = "tokio")]
     use tokio::io::Result as TokioResult; // Alias to avoid conflict
 
     // A simple mock reader
+    #[cfg(feature = "tokio")]
     struct MockReader {
         data: Vec<&'static str>,
         pos: usize,
     }
 
+    #[cfg(feature = "tokio")]
     impl AsyncRead for MockReader {
         fn poll_read(
             mut self: std::pin::Pin<&mut Self>,
@@ -129,6 +622,64 @@ mod tests {
         }
     }
 
+    // Fails every other read with `ErrorKind::Interrupted`
+    #[cfg(feature = "tokio")]
+    struct InterruptingReader {
+        inner: MockReader,
+        interrupt: bool,
+    }
+
+    #[cfg(feature = "tokio")]
+    impl AsyncRead for InterruptingReader {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<TokioResult<()>> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                let error = std::io::Error::from(std::io::ErrorKind::Interrupted);
+                return std::task::Poll::Ready(Err(error));
+            }
+            std::pin::Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_stream_retries_interrupted_reads() -> anyhow::Result<()> {
+        let reader = InterruptingReader {
+            inner: MockReader {
+                data: vec!["First one. ", "Second one."],
+                pos: 0,
+            },
+            interrupt: false,
+        };
+        let texts: Vec<String> = sentences_stream(reader, SegmentOptions::default())
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_>>()?;
+        assert_eq!(texts.concat().replace(' ', ""), "Firstone.Secondone.");
+
+        // Without retries the first interruption ends the stream
+        let reader = InterruptingReader {
+            inner: MockReader {
+                data: vec!["First one. "],
+                pos: 0,
+            },
+            interrupt: false,
+        };
+        let options = SegmentOptions {
+            retry: retry::RetryPolicy::none(),
+            ..Default::default()
+        };
+        let results: Vec<_> = sentences_stream(reader, options).collect().await;
+        assert!(matches!(results[..], [Err(SegmenterError::IoError(_))]));
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_stream_basic() -> anyhow::Result<()> {
         let options = SegmentOptions::default();
@@ -154,6 +705,7 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_stream_lookahead() -> anyhow::Result<()> {
         let options = SegmentOptions {
@@ -181,6 +733,2450 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_segment_offsets() -> anyhow::Result<()> {
+        let input = "  First one. Second one.  Tail";
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            lookahead: 0,
+            ..Default::default()
+        })?;
+
+        let mut segments = segmenter.feed_segments(input)?;
+        segments.extend(segmenter.flush_segment()?);
+
+        assert!(segments.len() >= 2);
+        for (i, segment) in segments.iter().enumerate() {
+            assert_eq!(segment.index, i);
+            assert_eq!(&input[segment.start..segment.end], segment.text);
+        }
+        assert_eq!(segments.last().unwrap().text, "Tail");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_segment_dir() -> anyhow::Result<()> {
+        let root = std::env::temp_dir().join(format!("async-tqsm-dir-{}", std::process::id()));
+        tokio::fs::create_dir_all(root.join("nested")).await?;
+        tokio::fs::write(root.join("b.txt"), "Second file.").await?;
+        tokio::fs::write(root.join("nested/a.txt"), "Nested file.").await?;
+        tokio::fs::write(root.join("a.bin"), [0xff, 0xfe, 0x00]).await?;
+
+        let results: Vec<_> = dir::segment_dir_parallel(&root, SegmentOptions::default(), 2)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_>>()?;
+        tokio::fs::remove_dir_all(&root).await?;
+
+        let tagged: Vec<_> = results
+            .iter()
+            .map(|(path, segment)| (path.strip_prefix(&root).unwrap(), segment.text.as_str()))
+            .collect();
+        assert_eq!(
+            tagged,
+            [
+                (std::path::Path::new("b.txt"), "Second file."),
+                (std::path::Path::new("nested/a.txt"), "Nested file."),
+            ]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_segment_dir_order_independent_of_jobs() -> anyhow::Result<()> {
+        let root = std::env::temp_dir().join(format!("async-tqsm-jobs-{}", std::process::id()));
+        tokio::fs::create_dir_all(&root).await?;
+        for i in 0..12 {
+            // Larger files first, so they tend to finish last
+            let text = "Some sentence. ".repeat((12 - i) * 4);
+            tokio::fs::write(root.join(format!("{:02}.txt", i)), text).await?;
+        }
+
+        let mut runs = Vec::new();
+        for jobs in [1, 4, 12] {
+            let run: Vec<_> = dir::segment_dir_parallel(&root, SegmentOptions::default(), jobs)
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<Result<_>>()?;
+            runs.push(run);
+        }
+        let unordered: Vec<_> = dir::segment_dir_unordered(&root, SegmentOptions::default(), 4)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_>>()?;
+        tokio::fs::remove_dir_all(&root).await?;
+
+        assert_eq!(runs[0], runs[1]);
+        assert_eq!(runs[0], runs[2]);
+        // Same sentences, possibly in a different file order
+        let mut sorted = unordered.clone();
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(sorted, runs[0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_abbreviations() -> anyhow::Result<()> {
+        let input = "It weighs approx. two tons. Really.";
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            lookahead: 0,
+            extra_abbreviations: vec!["approx.".to_string()],
+            ..Default::default()
+        })?;
+
+        let mut sentences = segmenter.feed(input)?;
+        sentences.extend(segmenter.flush()?);
+
+        assert_eq!(sentences[0], "It weighs approx. two tons.");
+
+        // Removals override the language's list as well as the extra abbreviations
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            lookahead: 0,
+            extra_abbreviations: vec!["approx.".to_string()],
+            removed_abbreviations: vec!["Dr".to_string(), "approx.".to_string()],
+            ..Default::default()
+        })?;
+        let mut sentences = segmenter.feed("Ask the Dr. She knows approx. Fine.")?;
+        sentences.extend(segmenter.flush()?);
+        assert_eq!(sentences, ["Ask the Dr.", "She knows approx.", "Fine."]);
+
+        // The exported rules list the effective abbreviations
+        let rules = rules::RuleSet::for_options(&SegmentOptions {
+            extra_abbreviations: vec!["approx.".to_string(), "ca".to_string()],
+            removed_abbreviations: vec!["ca.".to_string()],
+            ..Default::default()
+        })?;
+        assert!(rules.abbreviations.contains(&"approx".to_string()));
+        assert!(!rules.abbreviations.contains(&"ca".to_string()));
+        assert!(rules
+            .to_srx()
+            .contains(r"<beforebreak>\bapprox\.</beforebreak>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tail_policy() -> anyhow::Result<()> {
+        let finish = |tail_policy| -> anyhow::Result<Vec<Segment>> {
+            let mut segmenter = Segmenter::new(SegmentOptions {
+                tail_policy,
+                ..Default::default()
+            })?;
+            let mut segments = segmenter.feed_segments("It rained. And then")?;
+            segments.extend(segmenter.finish_segments()?);
+            Ok(segments)
+        };
+
+        let emitted = finish(TailPolicy::Emit)?;
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(emitted[0].text, "It rained.");
+        assert_eq!(emitted[1].text, "And then");
+        assert!(!emitted[1].incomplete);
+
+        let dropped = finish(TailPolicy::Drop)?;
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].text, "It rained.");
+
+        let marked = finish(TailPolicy::EmitMarkedIncomplete)?;
+        assert!(marked[1].incomplete);
+        assert!(!marked[0].incomplete);
+        Ok(())
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_profile_with_explicit_options() {
+        use clap::Parser;
+        use config::{CliArgs, Profile};
+
+        let args = CliArgs::parse_from([
+            "async-tqsm",
+            "--profile",
+            "tts-low-latency",
+            "--lookahead",
+            "4",
+        ]);
+        let options = SegmentOptions::from(args);
+        let profile = SegmentOptions::profile(Profile::TtsLowLatency);
+
+        assert_eq!(options.lookahead, 4);
+        assert_eq!(options.long_sentence_warning, profile.long_sentence_warning);
+    }
+
+    #[test]
+    fn test_audit_records_boundaries() -> anyhow::Result<()> {
+        use audit::{Audit, BoundaryRule};
+        use std::sync::{Arc, Mutex};
+
+        let boundaries = Arc::new(Mutex::new(Vec::new()));
+        let sink = boundaries.clone();
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            audit: Some(Audit::new(move |b| sink.lock().unwrap().push(b))),
+            ..Default::default()
+        })?;
+        segmenter.feed("It rained. It poured all day. Then")?;
+        segmenter.finish()?;
+
+        let boundaries = boundaries.lock().unwrap();
+        let rules: Vec<_> = boundaries.iter().map(|b| b.rule).collect();
+        assert_eq!(
+            rules,
+            [
+                BoundaryRule::Terminator,
+                BoundaryRule::Terminator,
+                BoundaryRule::EndOfInput
+            ]
+        );
+        assert_eq!(boundaries[0].offset, 11);
+        assert_eq!(boundaries[0].confidence, 1.0);
+        // Accepted at the end of the input with only " Then" after it
+        assert!(boundaries[1].confidence < 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_feed_iter_matches_feed() -> anyhow::Result<()> {
+        let input = ["It rained. It ", "poured. Then it ", "stopped. The end."];
+        let mut eager = Segmenter::new(SegmentOptions::default())?;
+        let mut lazy = Segmenter::new(SegmentOptions::default())?;
+
+        for chunk in input {
+            let expected = eager.feed_segments(chunk)?;
+            assert_eq!(lazy.feed_iter(chunk)?.collect::<Vec<_>>(), expected);
+        }
+
+        // Sentences left unread stay buffered
+        let mut segmenter = Segmenter::new(SegmentOptions::default())?;
+        let first = segmenter
+            .feed_iter("One. Two. Three. Four and more")?
+            .next();
+        assert_eq!(first.map(|s| s.text).as_deref(), Some("One."));
+        assert_eq!(segmenter.feed(" text.")?, ["Two.", "Three."]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_feed_limited() -> anyhow::Result<()> {
+        let mut segmenter = Segmenter::new(SegmentOptions::default())?;
+        let first = segmenter.feed_limited("One. Two. Three. Four. Five and more", 2)?;
+        assert_eq!(first.len(), 2);
+        let next = segmenter.feed_limited("", 1)?;
+        assert_eq!(next[0].text, "Three.");
+        let rest: Vec<_> = segmenter
+            .drain_pending()
+            .into_iter()
+            .map(|s| s.text)
+            .collect();
+        assert_eq!(rest, ["Four."]);
+        assert!(segmenter.drain_pending().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_feed_limited_keeps_forced_segments() -> anyhow::Result<()> {
+        let pool = memory::MemoryPool::new(30);
+        let options = SegmentOptions {
+            memory_pool: Some(pool.clone()),
+            ..SegmentOptions::default()
+        };
+        let mut first = Segmenter::new(options.clone())?;
+        let mut second = Segmenter::new(options)?;
+        first.feed("It rained all day. And")?;
+        second.feed("Hello there. Bye")?;
+        // Both segments are cut to make room, but only one is asked for
+        let limited = first.feed_limited(" then", 1)?;
+        assert_eq!(limited[0].text, "It rained all day.");
+        let rest: Vec<_> = first.drain_pending().into_iter().map(|s| s.text).collect();
+        assert_eq!(rest, ["And"]);
+        assert_eq!(first.finish()?, ["then"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_and_column() -> anyhow::Result<()> {
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            lookahead: 0,
+            ..Default::default()
+        })?;
+        let mut segments = segmenter.feed_segments("Überall Regen. Nass.\n  Dann Sonne. ")?;
+        segments.extend(segmenter.finish_segments()?);
+
+        let positions: Vec<_> = segments.iter().map(|s| (s.line, s.column)).collect();
+        assert_eq!(positions, [(1, 1), (1, 16), (2, 3)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ids() {
+        // Pinned, since stored hashes must stay comparable across versions
+        assert_eq!(ids::content_hash_hex(""), "cbf29ce484222325");
+        assert_eq!(
+            ids::content_hash_hex("It rained."),
+            ids::content_hash_hex("It rained.")
+        );
+        assert_ne!(
+            ids::content_hash("It rained."),
+            ids::content_hash("It rained!")
+        );
+        assert_eq!(ids::sentence_id("a.txt", 3), "a.txt#3");
+
+        use ids::{fingerprint, HashAlgorithm};
+        assert_eq!(fingerprint("", HashAlgorithm::Fnv, Some(8)), "cbf29ce4");
+        #[cfg(feature = "fingerprint")]
+        {
+            assert_eq!(
+                fingerprint("", HashAlgorithm::Xxh3, None),
+                "2d06800538d394c2"
+            );
+            assert_eq!(
+                fingerprint("", HashAlgorithm::Sha1, None),
+                "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+            );
+        }
+    }
+
+    #[test]
+    fn test_keep_indentation() -> anyhow::Result<()> {
+        let segment = |keep_indentation| -> anyhow::Result<Vec<Segment>> {
+            let mut segmenter = Segmenter::new(SegmentOptions {
+                keep_indentation,
+                ..Default::default()
+            })?;
+            segmenter.feed("    Indented line.  ")?;
+            Ok(segmenter.finish_segments()?)
+        };
+
+        let trimmed = segment(false)?;
+        assert_eq!(
+            (trimmed[0].text.as_str(), trimmed[0].start),
+            ("Indented line.", 4)
+        );
+        let kept = segment(true)?;
+        assert_eq!(
+            (kept[0].text.as_str(), kept[0].start),
+            ("    Indented line.", 0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_quotes_across_chunks() -> anyhow::Result<()> {
+        let segment = |max_quote_span, chunks: &[&str]| -> anyhow::Result<Vec<String>> {
+            let mut segmenter = Segmenter::new(SegmentOptions {
+                lookahead: 0,
+                max_quote_span,
+                ..Default::default()
+            })?;
+            let mut sentences = Vec::new();
+            for chunk in chunks {
+                sentences.extend(segmenter.feed(chunk)?);
+            }
+            sentences.extend(segmenter.finish()?);
+            Ok(sentences)
+        };
+        let chunks = ["He said \"Stop. Wait", ".\" Then he left. Bye."];
+
+        // Without tracking, the closing quote is not in the buffer when "Stop." is checked
+        assert_eq!(segment(0, &chunks)?[0], "He said \"Stop.");
+        assert_eq!(
+            segment(1000, &chunks)?[0],
+            "He said \"Stop. Wait.\" Then he left."
+        );
+        // An unbalanced quote only holds boundaries back for `max_quote_span` bytes
+        let stray = segment(5, &["A 12\" screen. It works. Fine."])?;
+        assert_eq!(stray, ["A 12\" screen.", "It works.", "Fine."]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_quotes() -> anyhow::Result<()> {
+        use quotes::QuoteRecovery;
+
+        let segment = |input, max_quote_depth, quote_recovery| -> anyhow::Result<Vec<String>> {
+            let mut segmenter = Segmenter::new(SegmentOptions {
+                lookahead: 0,
+                max_quote_depth,
+                quote_recovery,
+                ..Default::default()
+            })?;
+            let mut sentences = segmenter.feed(input)?;
+            sentences.extend(segmenter.finish()?);
+            Ok(sentences)
+        };
+
+        // The ")" leaves the inner quote open unless recovery closes it
+        let unbalanced = "(\"Quote. Still) Next. Done.";
+        assert_eq!(segment(unbalanced, 8, QuoteRecovery::Ignore)?.len(), 1);
+        assert_eq!(
+            segment(unbalanced, 8, QuoteRecovery::CloseToMatch)?,
+            ["(\"Quote. Still) Next.", "Done."]
+        );
+        assert_eq!(segment(unbalanced, 8, QuoteRecovery::Reset)?.len(), 2);
+
+        // Beyond the depth limit the "(" is not tracked, so the quote closes normally
+        let nested = "\"(Stop. Go\" more. Done.";
+        assert_eq!(segment(nested, 8, QuoteRecovery::Ignore)?.len(), 1);
+        assert_eq!(segment(nested, 1, QuoteRecovery::Ignore)?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_footnote_markers() -> anyhow::Result<()> {
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            lookahead: 0,
+            ..Default::default()
+        })?;
+        let mut sentences =
+            segmenter.feed("It rained.[12] It poured.¹ Then it stopped.[a][3] The end. ")?;
+        sentences.extend(segmenter.finish()?);
+
+        assert_eq!(
+            sentences,
+            [
+                "It rained.[12]",
+                "It poured.¹",
+                "Then it stopped.[a][3]",
+                "The end."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_citations() -> anyhow::Result<()> {
+        let mut segmenter = Segmenter::new(SegmentOptions::default())?;
+        let mut sentences = segmenter.feed("As shown (Smith et al. 2020). It holds. ")?;
+        sentences.extend(segmenter.feed("Less so later. (Jones, 20")?);
+        sentences.extend(segmenter.feed("19a) Next one. The end.")?);
+        sentences.extend(segmenter.finish()?);
+
+        assert_eq!(
+            sentences,
+            [
+                "As shown (Smith et al. 2020).",
+                "It holds.",
+                "Less so later. (Jones, 2019a)",
+                "Next one.",
+                "The end."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_initials_and_roman_numerals() -> anyhow::Result<()> {
+        use protect::Strictness;
+
+        let text = "J. R. R. Tolkien wrote it. Chapter IV. The Journey begins. \
+                    John F. Kennedy spoke. Ate vitamin C. The end.";
+        let split = |initials, roman_numerals| -> anyhow::Result<Vec<String>> {
+            let mut options = SegmentOptions::default();
+            options.protections.initials = initials;
+            options.protections.roman_numerals = roman_numerals;
+            let mut segmenter = Segmenter::new(options)?;
+            let mut sentences = segmenter.feed(text)?;
+            sentences.extend(segmenter.finish()?);
+            Ok(sentences)
+        };
+
+        assert_eq!(
+            split(Strictness::Conservative, Strictness::Conservative)?,
+            [
+                "J. R. R. Tolkien wrote it.",
+                "Chapter IV. The Journey begins.",
+                "John F.",
+                "Kennedy spoke.",
+                "Ate vitamin C.",
+                "The end."
+            ]
+        );
+        assert_eq!(
+            split(Strictness::Aggressive, Strictness::Off)?,
+            [
+                "J. R. R. Tolkien wrote it.",
+                "Chapter IV.",
+                "The Journey begins.",
+                "John F. Kennedy spoke.",
+                "Ate vitamin C. The end."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dates_and_times() -> anyhow::Result<()> {
+        let mut options = SegmentOptions::default();
+        options
+            .protections
+            .patterns
+            .push(r"\bMt\.\s+\p{Lu}".to_string());
+        let mut segmenter = Segmenter::new(options)?;
+        let mut sentences =
+            segmenter.feed("We met on Jan. 5, 2024 at 5 p.m. Then we left. See No. 7. ")?;
+        sentences.extend(segmenter.feed("They climbed Mt. Everest. Done.")?);
+        sentences.extend(segmenter.finish()?);
+
+        assert_eq!(
+            sentences,
+            [
+                "We met on Jan. 5, 2024 at 5 p.m.",
+                "Then we left.",
+                "See No. 7.",
+                "They climbed Mt. Everest.",
+                "Done."
+            ]
+        );
+
+        let mut options = SegmentOptions::default();
+        options.protections.patterns.push("(".to_string());
+        assert!(matches!(
+            Segmenter::new(options),
+            Err(SegmenterError::InvalidOptions(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_measurements() -> anyhow::Result<()> {
+        use protect::MeasurementDomain;
+
+        let text = "Add approx. 3 kg. of flour and 2 tbsp. sugar. It costs $5.00. Done.";
+        let split = |measurements| -> anyhow::Result<Vec<String>> {
+            let mut options = SegmentOptions::default();
+            options.protections.measurements = measurements;
+            let mut segmenter = Segmenter::new(options)?;
+            let mut sentences = segmenter.feed(text)?;
+            sentences.extend(segmenter.finish()?);
+            Ok(sentences)
+        };
+
+        assert_eq!(
+            split(vec![MeasurementDomain::General, MeasurementDomain::Cooking])?,
+            [
+                "Add approx. 3 kg. of flour and 2 tbsp. sugar.",
+                "It costs $5.00.",
+                "Done."
+            ]
+        );
+        assert_eq!(
+            split(vec![MeasurementDomain::General])?,
+            [
+                "Add approx. 3 kg. of flour and 2 tbsp.",
+                "sugar.",
+                "It costs $5.00.",
+                "Done."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ordinal_dates() -> anyhow::Result<()> {
+        use protect::Strictness;
+
+        let text = "Am 3. Mai regnete es. Er wurde 2. Sieger. Dann kam der 4. Juli.";
+        let split = |language: &str, ordinal_dates| -> anyhow::Result<Vec<String>> {
+            let mut options = SegmentOptions {
+                language: language.to_string(),
+                ..SegmentOptions::default()
+            };
+            options.protections.ordinal_dates = ordinal_dates;
+            let mut segmenter = Segmenter::new(options)?;
+            let mut sentences = segmenter.feed(text)?;
+            sentences.extend(segmenter.finish()?);
+            Ok(sentences)
+        };
+
+        assert_eq!(
+            split("de", None)?,
+            [
+                "Am 3. Mai regnete es.",
+                "Er wurde 2.",
+                "Sieger.",
+                "Dann kam der 4. Juli."
+            ]
+        );
+        assert_eq!(
+            split("de", Some(Strictness::Aggressive))?,
+            [
+                "Am 3. Mai regnete es.",
+                "Er wurde 2. Sieger.",
+                "Dann kam der 4. Juli."
+            ]
+        );
+        assert_eq!(split("en", None)?.len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cjk() -> anyhow::Result<()> {
+        let options = SegmentOptions::for_language("zh");
+        assert_eq!(options.lookahead, 3);
+        let mut segmenter = Segmenter::new(options)?;
+        let mut segments = segmenter.feed_segments("你好。他说：「今天很好。」我们走吧！")?;
+        segments.extend(segmenter.feed_segments("\u{3000}真的吗？好的，")?);
+        segments.extend(segmenter.feed_segments("再见。")?);
+        segments.extend(segmenter.finish_segments()?);
+
+        let texts: Vec<_> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [
+                "你好。",
+                "他说：「今天很好。」",
+                "我们走吧！",
+                "真的吗？",
+                "好的，再见。"
+            ]
+        );
+        assert_eq!(segments[3].column, 20);
+        Ok(())
+    }
+
+    #[test]
+    fn test_southeast_asian_scripts() -> anyhow::Result<()> {
+        let split = |language: &str, text: &str| -> anyhow::Result<Vec<String>> {
+            let mut segmenter = Segmenter::new(SegmentOptions::for_language(language))?;
+            let mut sentences = segmenter.feed(text)?;
+            sentences.extend(segmenter.finish()?);
+            Ok(sentences)
+        };
+
+        assert_eq!(
+            split("th", "วันนี้อากาศดีมาก เราไปเที่ยวกันเถอะ และกินข้าวด้วย ขอบคุณครับ")?,
+            ["วันนี้อากาศดีมาก", "เราไปเที่ยวกันเถอะ และกินข้าวด้วย", "ขอบคุณครับ"]
+        );
+        assert_eq!(split("km", "ខ្ញុំទៅផ្សារ។ គាត់នៅផ្ទះ។")?, ["ខ្ញុំទៅផ្សារ។", "គាត់នៅផ្ទះ។"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rtl_scripts() -> anyhow::Result<()> {
+        let split = |language: &str, text: &str| -> anyhow::Result<Vec<Segment>> {
+            let mut segmenter = Segmenter::new(SegmentOptions::for_language(language))?;
+            let mut segments = segmenter.feed_segments(text)?;
+            segments.extend(segmenter.finish_segments()?);
+            Ok(segments)
+        };
+        let texts = |segments: &[Segment]| -> Vec<String> {
+            segments.iter().map(|s| s.text.clone()).collect()
+        };
+
+        let urdu = split("ur", "یہ کتاب ہے۔ وہ کہاں ہے؟ قیمت 3.5 روپے ہے۔")?;
+        assert_eq!(
+            texts(&urdu),
+            ["یہ کتاب ہے۔", "وہ کہاں ہے؟", "قیمت 3.5 روپے ہے۔"]
+        );
+
+        let text = "مرحبا، كيف حالك؟\u{200F} أنا بخير.";
+        let arabic = split("ar", text)?;
+        assert_eq!(texts(&arabic), ["مرحبا، كيف حالك؟\u{200F}", "أنا بخير."]);
+        assert_eq!(&text[arabic[1].start..arabic[1].end], "أنا بخير.");
+        assert_eq!(arabic[1].column, 19);
+        Ok(())
+    }
+
+    #[test]
+    fn test_danda() -> anyhow::Result<()> {
+        for language in ["hi", "mr"] {
+            let mut segmenter = Segmenter::new(SegmentOptions::for_language(language))?;
+            let mut sentences = segmenter.feed("डॉ. शर्मा आज आए।वे बहुत खुश थे। धन्यवाद॥ ")?;
+            sentences.extend(segmenter.finish()?);
+            assert_eq!(sentences, ["डॉ. शर्मा आज आए।", "वे बहुत खुश थे।", "धन्यवाद॥"]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_more_script_terminators() -> anyhow::Result<()> {
+        let split = |language: &str, text: &str| -> anyhow::Result<Vec<String>> {
+            let mut segmenter = Segmenter::new(SegmentOptions::for_language(language))?;
+            let mut sentences = segmenter.feed(text)?;
+            sentences.extend(segmenter.finish()?);
+            Ok(sentences)
+        };
+
+        assert_eq!(
+            split("am", "ሰላም ነው። እንዴት ነህ፧ ደህና ነኝ፡፡ አመሰግናለሁ")?,
+            ["ሰላም ነው።", "እንዴት ነህ፧", "ደህና ነኝ፡፡", "አመሰግናለሁ"]
+        );
+        assert_eq!(split("hy", "Բարև։ Ինչպե՞ս ես։")?, ["Բարև։", "Ինչպե՞ս ես։"]);
+        assert_eq!(
+            split("my", "မင်္ဂလာပါ။ နေကောင်းလား၊ ဟုတ်ကဲ့။")?,
+            ["မင်္ဂလာပါ။", "နေကောင်းလား၊ ဟုတ်ကဲ့။"]
+        );
+        assert_eq!(
+            split("el", "Τι κάνεις; Καλά είμαι.")?,
+            ["Τι κάνεις;", "Καλά είμαι."]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_mixed_scripts() -> anyhow::Result<()> {
+        let text = "He wrote: नमस्ते दोस्त। कैसे हो। Then he left. Բարև։ Bye.";
+        let split = |mixed_scripts| -> anyhow::Result<Vec<String>> {
+            let mut segmenter = Segmenter::new(SegmentOptions {
+                mixed_scripts,
+                ..SegmentOptions::default()
+            })?;
+            let mut sentences = segmenter.feed(text)?;
+            sentences.extend(segmenter.finish()?);
+            Ok(sentences)
+        };
+
+        assert_eq!(
+            split(true)?,
+            [
+                "He wrote: नमस्ते दोस्त।",
+                "कैसे हो।",
+                "Then he left.",
+                "Բարև։",
+                "Bye."
+            ]
+        );
+        assert_eq!(split(false)?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chat_mode() -> anyhow::Result<()> {
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            mode: config::Mode::Chat,
+            ..SegmentOptions::default()
+        })?;
+        let mut sentences = segmenter.feed("hey @sam.jones see www.example.com/a. ok? cool\n")?;
+        assert_eq!(
+            sentences,
+            ["hey @sam.jones see www.example.com/a.", "ok?", "cool\n"]
+        );
+        sentences = segmenter.feed("Loved it 😍😍 See you at #rust.conf\nbye. ")?;
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(
+            sentences,
+            ["Loved it 😍😍", "See you at #rust.conf\n", "bye."]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_chat_standalone_messages() -> anyhow::Result<()> {
+        let mut segmenter = Segmenter::new(SegmentOptions::profile(config::Profile::Chat))?;
+        assert_eq!(segmenter.feed("👍👍")?, ["👍👍"]);
+        assert_eq!(segmenter.feed("lol")?, ["lol"]);
+        assert_eq!(segmenter.feed("👍 thanks, see")?, ["👍"]);
+        let mut sentences = segmenter.feed(" you\nhahaha Nice one")?;
+        assert_eq!(sentences, ["thanks, see you\n", "hahaha"]);
+        sentences = segmenter.feed("")?;
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(sentences, ["Nice one"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_mode() -> anyhow::Result<()> {
+        let log = "2024-01-05T12:00:00.123Z INFO [server] Listening on port 80. Ready\n\
+                   2024-01-05T12:00:01Z ERROR db: Connection lost. Retrying in 5 s\n";
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            mode: config::Mode::Log,
+            ..SegmentOptions::default()
+        })?;
+        let mut segments = segmenter.feed_segments(log)?;
+        segments.extend(segmenter.finish_segments()?);
+
+        let texts: Vec<_> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [
+                "Listening on port 80.",
+                "Ready\n",
+                "Connection lost.",
+                "Retrying in 5 s\n"
+            ]
+        );
+        assert_eq!(&log[segments[2].start..segments[2].end], "Connection lost.");
+        assert_eq!((segments[2].line, segments[2].column), (2, 32));
+
+        let invalid = SegmentOptions {
+            mode: config::Mode::Log,
+            log_prefix: Some("(".to_string()),
+            ..SegmentOptions::default()
+        };
+        assert!(Segmenter::new(invalid).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "comments")]
+    #[test]
+    fn test_comments() -> anyhow::Result<()> {
+        use comments::CommentPreset;
+
+        let source = "let url = \"http://example.com\"; // Fetch it. Then\n\
+                      // parse it.\n\
+                      /*\n * Done here. */\n";
+        let sentences = comments::segment_source(
+            source,
+            &CommentPreset::C.syntax(),
+            &SegmentOptions::default(),
+        )?;
+        let texts: Vec<_> = sentences.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["Fetch it.", "Then parse it.", "Done here."]);
+        let positions: Vec<_> = sentences.iter().map(|s| (s.line, s.column)).collect();
+        assert_eq!(positions, [(1, 36), (1, 46), (4, 4)]);
+        assert_eq!(&source[sentences[2].start..sentences[2].end], "Done here.");
+        assert_eq!(sentences[2].index, 2);
+
+        let python = "def f():\n    \"\"\"Return one. Always.\"\"\"\n    return 1  # Not two.\n";
+        let sentences = comments::segment_source(
+            python,
+            &CommentPreset::Python.syntax(),
+            &SegmentOptions::default(),
+        )?;
+        let texts: Vec<_> = sentences.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["Return one.", "Always.", "Not two."]);
+        Ok(())
+    }
+
+    #[cfg(feature = "subtitles")]
+    #[test]
+    fn test_balanced_lines() {
+        use subtitles::{balance_lines, wrap_balanced, SubtitleOptions, Subtitles};
+
+        let sentence = "When the storm finally passed, the villagers walked down to the harbor";
+        assert_eq!(
+            balance_lines(sentence, 2),
+            [
+                "When the storm finally passed,",
+                "the villagers walked down to the harbor"
+            ]
+        );
+        assert_eq!(
+            balance_lines("I saw a cat on the big red mat", 2),
+            ["I saw a cat", "on the big red mat"]
+        );
+        assert_eq!(balance_lines("Hello", 3), ["Hello"]);
+        for line in wrap_balanced(sentence, 30) {
+            assert!(subtitles::display_width(&line) <= 30);
+        }
+        assert_eq!(wrap_balanced("今日は良い天気ですね。", 10).len(), 3);
+
+        let mut srt = Subtitles::new(SubtitleOptions {
+            width: 20,
+            max_lines: 2,
+            ..SubtitleOptions::default()
+        });
+        let cues = srt.cues(sentence);
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[1].number, 2);
+        assert_eq!(cues[0].end, cues[1].start);
+        assert!(cues.iter().all(|cue| cue.lines.len() <= 2));
+        assert!(cues[0]
+            .to_string()
+            .starts_with("1\n00:00:00,000 --> 00:00:0"));
+    }
+
+    #[test]
+    fn test_memory_pool() -> anyhow::Result<()> {
+        use memory::{MemoryPolicy, MemoryPool};
+
+        let pool = MemoryPool::new(30);
+        let options = SegmentOptions {
+            memory_pool: Some(pool.clone()),
+            ..SegmentOptions::default()
+        };
+        let mut first = Segmenter::new(options.clone())?;
+        let mut second = Segmenter::new(options)?;
+        assert!(first.feed("It rained all day. And")?.is_empty());
+        assert!(second.feed("Hello there. Bye")?.is_empty());
+        assert_eq!(pool.used(), 38);
+        // Over budget: the session feeding more input flushes what it holds first
+        assert_eq!(first.feed(" then")?, ["It rained all day.", "And"]);
+        assert_eq!(pool.used(), 16 + 5);
+        drop(second);
+        assert_eq!(pool.used(), 5);
+
+        let pool = MemoryPool::with_policy(10, MemoryPolicy::Reject);
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            memory_pool: Some(pool.clone()),
+            ..SegmentOptions::default()
+        })?;
+        segmenter.feed("Short.")?;
+        match segmenter.feed(" Too long") {
+            Err(SegmenterError::MemoryBudgetExceeded { pending, .. }) => {
+                assert_eq!(pending, " Too long")
+            }
+            other => panic!("expected MemoryBudgetExceeded, got {:?}", other),
+        }
+        assert_eq!(segmenter.buffered(), 6);
+        assert_eq!(pool.used(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fallback_language() -> anyhow::Result<()> {
+        use std::sync::{Arc, Mutex};
+        use warnings::{WarningKind, Warnings};
+
+        let unsupported = SegmentOptions {
+            language: "tlh".to_string(),
+            ..SegmentOptions::default()
+        };
+        // With the `icu` feature, ICU4X is the fallback
+        #[cfg(not(feature = "icu"))]
+        assert!(matches!(
+            Segmenter::new(unsupported.clone()),
+            Err(SegmenterError::UnsupportedLanguage(_))
+        ));
+
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let sink = reported.clone();
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            fallback_language: Some("en".to_string()),
+            warnings: Some(Warnings::new(move |w| sink.lock().unwrap().push(w.kind))),
+            ..unsupported
+        })?;
+        assert_eq!(segmenter.language(), "en");
+        assert_eq!(*reported.lock().unwrap(), [WarningKind::LanguageFallback]);
+        let mut sentences = segmenter.feed("Qapla'. batlh Daqawlu'taH.")?;
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(sentences, ["Qapla'.", "batlh Daqawlu'taH."]);
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_adaptive_read_size() -> anyhow::Result<()> {
+        use chunking::{ChunkSizer, ReadSize};
+        use std::time::Duration;
+
+        let mut sizer = ChunkSizer::new(ReadSize::adaptive());
+        assert_eq!(sizer.size(), 4096);
+        sizer.record(4096, Duration::ZERO);
+        assert_eq!(sizer.size(), 8192);
+        sizer.record(20, Duration::from_millis(200));
+        assert_eq!(sizer.size(), 4096);
+        assert_eq!("1024".parse(), Ok(ReadSize::Fixed(1024)));
+        let mut fixed = ChunkSizer::new(ReadSize::Fixed(1024));
+        fixed.record(1024, Duration::ZERO);
+        assert_eq!(fixed.size(), 1024);
+        assert!("0".parse::<ReadSize>().is_err());
+
+        let text = "It rained. ".repeat(500);
+        let stream = sentences_stream(
+            text.as_bytes(),
+            SegmentOptions {
+                read_size: ReadSize::adaptive(),
+                ..SegmentOptions::default()
+            },
+        );
+        let sentences: Vec<_> = stream.collect().await;
+        assert_eq!(sentences.len(), 500);
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_custom_segmenter() -> anyhow::Result<()> {
+        /// Ends a sentence after every `|`.
+        #[derive(Default)]
+        struct Pipes {
+            buffer: String,
+            offset: usize,
+            index: usize,
+        }
+
+        impl Pipes {
+            fn take(&mut self, len: usize) -> Segment {
+                let text: String = self.buffer.drain(..len).collect();
+                let segment = Segment {
+                    index: self.index,
+                    start: self.offset,
+                    end: self.offset + text.len(),
+                    char_start: self.offset,
+                    char_end: self.offset + text.len(),
+                    line: 1,
+                    column: self.offset + 1,
+                    text,
+                    incomplete: false,
+                    language: None,
+                    paragraph: None,
+                };
+                self.offset = segment.end;
+                self.index += 1;
+                segment
+            }
+        }
+
+        impl SentenceSegmenter for Pipes {
+            fn feed_segments(&mut self, chunk: &str) -> Result<Vec<Segment>> {
+                self.buffer.push_str(chunk);
+                let mut segments = Vec::new();
+                while let Some(end) = self.buffer.find('|') {
+                    segments.push(self.take(end + 1));
+                }
+                Ok(segments)
+            }
+
+            fn finish_segments(&mut self) -> Result<Vec<Segment>> {
+                let len = self.buffer.len();
+                Ok((len > 0).then(|| self.take(len)).into_iter().collect())
+            }
+
+            fn reset(&mut self) {
+                *self = Self::default();
+            }
+        }
+
+        let reader = MockReader {
+            data: vec!["a|b", "c|", "d"],
+            pos: 0,
+        };
+        let options = SegmentOptions::default();
+        let sentences: Vec<String> = sentences_stream_with(reader, Pipes::default(), &options)
+            .map(|s| s.unwrap())
+            .collect()
+            .await;
+        assert_eq!(sentences, ["a|", "bc|", "d"]);
+
+        // The libtqsm segmenter starts over after a reset
+        let mut segmenter = Segmenter::new(options)?;
+        segmenter.feed("Some text that is abandoned")?;
+        segmenter.reset();
+        let segments =
+            SentenceSegmenter::feed_segments(&mut segmenter, "One. Two. Three and four")?;
+        assert_eq!((segments[0].index, segments[0].start), (0, 0));
+        let tail = SentenceSegmenter::finish_segments(&mut segmenter)?;
+        assert_eq!((tail[0].index, tail[0].start), (2, 10));
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_sentences_from_stream() {
+        let tokens = ["Hel", "lo there. Ho", "w are", " you? Fine"].map(String::from);
+        let sentences: Vec<String> =
+            sentences_from_stream(futures::stream::iter(tokens), SegmentOptions::default())
+                .map(|s| s.unwrap())
+                .collect()
+                .await;
+        assert_eq!(sentences, ["Hello there.", "How are you?", "Fine"]);
+
+        // An error from the source ends the stream without flushing
+        let chunks = vec![Ok("One. Two and more".to_string()), Err("connection reset")];
+        let results: Vec<Result<String>> =
+            try_sentences_from_stream(futures::stream::iter(chunks), SegmentOptions::default())
+                .collect()
+                .await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), "One.");
+        match &results[1] {
+            Err(SegmenterError::IoError(e)) => assert_eq!(e.to_string(), "connection reset"),
+            other => panic!("expected an I/O error, got {:?}", other),
+        }
+    }
+
+    #[cfg(all(feature = "tokio", feature = "http-body"))]
+    #[tokio::test]
+    async fn test_sentences_from_bytes_stream() {
+        // "ü" and "€" are split across chunks
+        let chunks: Vec<std::result::Result<bytes::Bytes, std::io::Error>> = [
+            &b"Gr\xc3"[..],
+            b"\xbc\xc3\x9fe. Es kostet 5 \xe2\x82",
+            b"\xac. Ende",
+        ]
+        .into_iter()
+        .map(|chunk| Ok(bytes::Bytes::from_static(chunk)))
+        .collect();
+        let sentences: Vec<String> =
+            body::sentences_from_bytes_stream(futures::stream::iter(chunks), Default::default())
+                .map(|s| s.unwrap())
+                .collect()
+                .await;
+        assert_eq!(sentences, ["Grüße.", "Es kostet 5 €.", "Ende"]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_flush_after() {
+        let tokens = stream! {
+            yield "Let me think about".to_string();
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            yield " it. Done".to_string();
+        };
+        let options = SegmentOptions {
+            flush_after: Some(std::time::Duration::from_millis(20)),
+            ..Default::default()
+        };
+        let sentences: Vec<String> = sentences_from_stream(tokens, options)
+            .map(|s| s.unwrap())
+            .collect()
+            .await;
+        assert_eq!(sentences, ["Let me think about", "it.", "Done"]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_segment_events() {
+        let reader = MockReader {
+            data: vec!["Hello the", "re. How are", " you doing today? Fine"],
+            pos: 0,
+        };
+        let events: Vec<SegmentEvent> = segment_events_stream(reader, SegmentOptions::default())
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+        use SegmentEvent::{Final, Partial};
+        assert_eq!(
+            events,
+            [
+                Partial("Hello the".into()),
+                Partial("Hello there. How are".into()),
+                Final("Hello there.".into()),
+                Partial("How are you doing today? Fine".into()),
+                Final("How are you doing today?".into()),
+                Final("Fine".into()),
+            ]
+        );
+    }
+
+    #[cfg(all(feature = "tokio", feature = "detect"))]
+    #[tokio::test]
+    async fn test_stats_per_sentence_language() -> anyhow::Result<()> {
+        let reader = MockReader {
+            data: vec![
+                "I think we should meet tomorrow morning at the office. ",
+                "Das Treffen findet im großen Saal statt. Ok.",
+            ],
+            pos: 0,
+        };
+        let options = SegmentOptions {
+            sentence_language: detect::SentenceLanguage::Tag,
+            sentence_languages: vec!["de".to_string(), "en".to_string()],
+            ..Default::default()
+        };
+        let items: Vec<StreamItem> = items_stream(reader, options)
+            .map(|i| i.unwrap())
+            .collect()
+            .await;
+        let Some(StreamItem::End { stats, .. }) = items.last() else {
+            panic!("missing end marker");
+        };
+        // "Ok." is too short to tell and counts under the rules' language
+        assert_eq!(stats.languages["en"].sentences, 2);
+        assert_eq!(stats.languages["de"].sentences, 1);
+        Ok(())
+    }
+
+    #[cfg(feature = "ws")]
+    #[tokio::test]
+    async fn test_ws_empty_message_flushes_tail() -> anyhow::Result<()> {
+        use futures::SinkExt;
+        use tokio_tungstenite::tungstenite::Message;
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        tokio::spawn(server::ws::serve(addr, SegmentOptions::default()));
+        let stream = loop {
+            match tokio::net::TcpStream::connect(addr).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+            }
+        };
+        let url = format!("ws://{}/?format=text", addr);
+        let (mut websocket, _) = tokio_tungstenite::client_async(url, stream).await?;
+        let mut closed = false;
+        websocket.send(Message::text("Hello. World")).await?;
+        websocket.send(Message::text("")).await?;
+        let mut received = Vec::new();
+        while let Some(Ok(message)) = websocket.next().await {
+            match message {
+                Message::Text(text) => received.push(text.to_string()),
+                Message::Close(_) => closed = true,
+                _ => {}
+            }
+        }
+        // "World" has no terminator and is only sent once the input ends, before the server
+        // closes the connection
+        assert_eq!(received, ["Hello.", "World"]);
+        assert!(closed);
+        Ok(())
+    }
+
+    #[cfg(feature = "ws")]
+    #[tokio::test]
+    async fn test_ws_query_token() -> anyhow::Result<()> {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        tokio::spawn(server::ws::serve_with(
+            addr,
+            SegmentOptions::default(),
+            server::metrics::Metrics::new(),
+            server::limits::Limits::default(),
+            server::auth::Auth::tokens(["a+b/c="]),
+            server::tenancy::Policy::default(),
+        ));
+        let connect = |token: &'static str| async move {
+            let stream = loop {
+                match tokio::net::TcpStream::connect(addr).await {
+                    Ok(stream) => break stream,
+                    Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+                }
+            };
+            let url = format!("ws://{}/?access_token={}", addr, token);
+            tokio_tungstenite::client_async(url, stream).await.is_ok()
+        };
+        assert!(connect("a%2Bb%2Fc%3D").await);
+        // An unescaped `+` stands for a space
+        assert!(!connect("a+b/c=").await);
+        Ok(())
+    }
+
+    #[cfg(feature = "ws")]
+    #[tokio::test]
+    async fn test_admin_requests() -> anyhow::Result<()> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        tokio::spawn(server::admin::serve(
+            addr,
+            server::metrics::Metrics::new(),
+            server::health::Health::new(SegmentOptions::default()),
+            server::auth::Auth::tokens(["secret"]),
+        ));
+        let request = |parts: &'static [&'static str]| async move {
+            let mut stream = loop {
+                match tokio::net::TcpStream::connect(addr).await {
+                    Ok(stream) => break stream,
+                    Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+                }
+            };
+            for part in parts {
+                stream.write_all(part.as_bytes()).await?;
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await?;
+            anyhow::Ok(response)
+        };
+
+        // The head may arrive in pieces, and the query is not part of the path
+        let response = request(&["GET /healthz?verbose=1 HT", "TP/1.1\r\nHost: x\r\n\r\n"]).await?;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        let response = request(&["GET /metrics HTTP/1.1\r\nX-API-Key: secret\r\n\r\n"]).await?;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        let response = request(&["GET /metrics HTTP/1.1\r\n\r\n"]).await?;
+        assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+        let response = request(&["GET/readyz\r\n\r\n"]).await?;
+        assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+        Ok(())
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_http_limits() -> anyhow::Result<()> {
+        use server::limits::Limits;
+        use server::tenancy::Policy;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let router = server::http::router_with(
+            SegmentOptions::default(),
+            server::metrics::Metrics::new(),
+            Limits::new().max_sessions(1).max_session_buffer(32),
+            server::auth::Auth::tokens(["secret"]),
+            Policy::new().allow_languages(["de"]),
+        );
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        let post = |target: &str, token: &str, body: &str| {
+            format!(
+                "POST {target} HTTP/1.1\r\nHost: x\r\nAuthorization: Bearer {token}\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        };
+        let status = |request: String| async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await?;
+            stream.write_all(request.as_bytes()).await?;
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await?;
+            anyhow::Ok(response[9..12].to_string())
+        };
+
+        assert_eq!(
+            status(post("/segment", "secret", "One. Two.")).await?,
+            "200"
+        );
+        assert_eq!(status(post("/segment", "wrong", "One. Two.")).await?, "401");
+        assert_eq!(
+            status(post("/segment?language=fr", "secret", "Un.")).await?,
+            "400"
+        );
+        // The session's buffer cap also bounds a larger requested max_buffer
+        let unterminated = "word ".repeat(10);
+        let request = post("/segment?max_buffer=4096", "secret", &unterminated);
+        assert_eq!(status(request).await?, "413");
+
+        // An open streaming request holds the only session slot
+        let mut open = tokio::net::TcpStream::connect(addr).await?;
+        open.write_all(
+            b"POST /segment/stream HTTP/1.1\r\nHost: x\r\nAuthorization: Bearer secret\r\n\
+              Transfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n",
+        )
+        .await?;
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(open.read_u8().await?);
+        }
+        assert!(head.starts_with(b"HTTP/1.1 200"));
+        assert_eq!(status(post("/segment", "secret", "One.")).await?, "503");
+        drop(open);
+        Ok(())
+    }
+
+    #[cfg(feature = "codec")]
+    #[tokio::test]
+    async fn test_sentence_decoder() -> anyhow::Result<()> {
+        // "é" is split across two reads
+        let reader = byte_reader(&[b"Caf\xc3", b"\xa9 first. Then more text. An", b"d a tail"]);
+        let segments: Vec<Segment> = codec::framed_sentences(reader, SegmentOptions::default())?
+            .map(|segment| segment.unwrap())
+            .collect()
+            .await;
+        let sentences: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(sentences, ["Café first.", "Then more text.", "And a tail"]);
+        assert_eq!(segments[2].index, 2);
+        Ok(())
+    }
+
+    /// A reader returning `chunks` one per read.
+    #[cfg(feature = "codec")]
+    fn byte_reader(chunks: &[&'static [u8]]) -> impl tokio::io::AsyncRead + Unpin {
+        let chunks: Vec<std::io::Result<bytes::Bytes>> = chunks
+            .iter()
+            .map(|chunk| Ok(bytes::Bytes::from_static(chunk)))
+            .collect();
+        tokio_util::io::StreamReader::new(futures::stream::iter(chunks))
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_sentence_source() -> anyhow::Result<()> {
+        let reader = MockReader {
+            data: vec!["Hello Mr. Smith", ". How are", " you today? Good!"],
+            pos: 0,
+        };
+        let mut source = source::SentenceSource::new(reader, SegmentOptions::default())?;
+        let mut sentences = Vec::new();
+        while let Some(sentence) = source.next_sentence().await {
+            sentences.push(sentence?);
+        }
+        assert_eq!(
+            sentences,
+            ["Hello Mr. Smith.", "How are you today?", "Good!"]
+        );
+        assert!(source.next_sentence().await.is_none());
+
+        let reader = MockReader {
+            data: vec!["One. Two. Three and four", " and five."],
+            pos: 0,
+        };
+        let mut source = source::SentenceSource::new(reader, SegmentOptions::default())?;
+        assert_eq!(
+            source.next().await.transpose()?.map(|s| s.text),
+            Some("One.".into())
+        );
+        let (remaining, _reader) = source.into_remaining();
+        let texts: Vec<_> = remaining
+            .sentences
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect();
+        assert_eq!(texts, ["Two."]);
+        assert_eq!(remaining.text, "Three and four");
+        assert!(remaining.bytes.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let errors = [
+            SegmenterError::UnsupportedLanguage("xx".into()),
+            SegmenterError::UnsupportedBackend("xx".into()),
+            SegmenterError::LanguageLoadError("xx".into(), "missing".into()),
+            SegmenterError::InvalidOptions("bad".into()),
+            SegmenterError::BufferOverflow {
+                max: 1,
+                offset: 0,
+                snippet: String::new(),
+                pending: String::new(),
+            },
+            SegmenterError::MemoryBudgetExceeded {
+                capacity: 1,
+                offset: 0,
+                pending: String::new(),
+            },
+            SegmenterError::IoError(std::io::ErrorKind::Other.into()),
+            String::from_utf8(vec![0xff])
+                .unwrap_err()
+                .utf8_error()
+                .into(),
+            SegmenterError::StreamError("closed".into()),
+            Box::<dyn std::error::Error + Send + Sync>::from("failed").into(),
+            SegmenterError::DecodeError {
+                encoding: "Shift_JIS",
+                offset: 0,
+            },
+        ];
+        // The codes are part of the public protocol and must never change
+        let codes: Vec<(u32, &str)> = errors.iter().map(|e| (e.code(), e.kind())).collect();
+        assert_eq!(
+            codes,
+            [
+                (1, "unsupported_language"),
+                (2, "unsupported_backend"),
+                (3, "language_load"),
+                (4, "invalid_options"),
+                (5, "buffer_overflow"),
+                (6, "memory_budget"),
+                (7, "io"),
+                (8, "utf8"),
+                (9, "stream"),
+                (10, "segmentation"),
+                (11, "decode"),
+            ]
+        );
+
+        // The C header lists every code under the name of its kind
+        let header = include_str!("../include/async_tqsm.h");
+        for (code, kind) in &codes {
+            let define = format!("#define TQSM_CODE_{} {}\n", kind.to_uppercase(), code);
+            assert!(
+                header.contains(&define),
+                "{} missing from the header",
+                define
+            );
+        }
+        assert_eq!(header.matches("#define TQSM_CODE_").count(), codes.len());
+    }
+
+    #[test]
+    fn test_post_process() -> anyhow::Result<()> {
+        use postprocess::PostProcess;
+
+        let text = "A **bold**  claim. Another\tone. ";
+        let segments = Segmenter::new(SegmentOptions {
+            post_process: Some(
+                PostProcess::new(|s| s.replace("**", ""))
+                    .then(|s| s.split_whitespace().collect::<Vec<_>>().join(" ")),
+            ),
+            ..SegmentOptions::default()
+        })?
+        .segment_all_segments(text)?;
+        let sentences: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(sentences, ["A bold claim.", "Another one."]);
+        // Offsets still cover the original text
+        assert_eq!(
+            &text[segments[0].start..segments[0].end],
+            "A **bold**  claim."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_markup() {
+        use postprocess::{strip_markup, strip_markup_mapped};
+
+        assert_eq!(strip_markup("## Setup"), "Setup");
+        assert_eq!(
+            strip_markup("- Run `cargo build` with **care**."),
+            "Run cargo build with care."
+        );
+        assert_eq!(
+            strip_markup("• An _emphatic_ ~~struck~~ word."),
+            "An emphatic struck word."
+        );
+        assert_eq!(
+            strip_markup("Keep snake_case, 2 * 3 and #tags."),
+            "Keep snake_case, 2 * 3 and #tags."
+        );
+        assert_eq!(
+            strip_markup("First.\n  * (*Nested*) item"),
+            "First.\n(Nested) item"
+        );
+
+        let stripped = strip_markup_mapped("A *big* dog.");
+        assert_eq!(stripped.text, "A big dog.");
+        assert_eq!(stripped.offsets[2], 3); // "big" starts after the asterisk
+        assert_eq!(stripped.offsets[stripped.text.len()], 12);
+    }
+
+    #[cfg(feature = "verbalize")]
+    #[test]
+    fn test_verbalize() {
+        use verbalize::{English, Verbalizer};
+
+        let english = English::new().with_abbreviation("Rd.", "Road");
+        assert_eq!(
+            english.verbalize("Dr. Smith lives on the 21st floor at 3.5 Elm Rd., e.g. near the park."),
+            "Doctor Smith lives on the twenty-first floor at three point five Elm Road, for example near the park."
+        );
+        assert_eq!(
+            english.verbalize("Prices fell -1,250 points, or 12% of 1,000,000."),
+            "Prices fell minus one thousand two hundred fifty points, or twelve percent of one million."
+        );
+        assert_eq!(
+            english.verbalize("Ask Mrs. Jones etc."),
+            "Ask Missus Jones et cetera."
+        );
+        // Numbers that are part of a word stay as they are
+        assert_eq!(
+            english.verbalize("Play the mp3 in 4K."),
+            "Play the mp3 in 4K."
+        );
+
+        let custom = |s: &str| s.replace("&", "and");
+        assert_eq!(custom.verbalize("Salt & pepper."), "Salt and pepper.");
+    }
+
+    #[cfg(feature = "dedup")]
+    #[test]
+    fn test_near_duplicates() {
+        use dedup::{distance, simhash, NearDuplicates};
+
+        let pairs = [
+            ("Copyright 2021 Example Inc.", "COPYRIGHT 2024 Example Inc!"),
+            (
+                "Click here to subscribe to our newsletter!",
+                "Click here to subscribe to the newsletter.",
+            ),
+            (
+                "The quick brown fox jumps over the lazy dog.",
+                "A completely different sentence about the weather.",
+            ),
+        ];
+        let distances: Vec<u32> = pairs
+            .iter()
+            .map(|(a, b)| distance(simhash(a), simhash(b)))
+            .collect();
+        assert_eq!(distances[0], 0);
+        assert!(distances[1] <= 6, "{:?}", distances);
+        assert!(distances[2] > 12, "{:?}", distances);
+
+        let mut filter = NearDuplicates::new(6, 2);
+        let kept: Vec<&str> = [
+            "Page 1 of 3.",
+            "It rained all day.",
+            "Page 2 of 3.",
+            "The river rose.",
+            "Birds sang anyway.",
+            "Page 3 of 3.",
+        ]
+        .into_iter()
+        .filter(|s| !filter.is_duplicate(s))
+        .collect();
+        // The window of two forgets the first page line before the third one arrives
+        assert_eq!(
+            kept,
+            [
+                "Page 1 of 3.",
+                "It rained all day.",
+                "The river rose.",
+                "Birds sang anyway.",
+                "Page 3 of 3."
+            ]
+        );
+    }
+
+    #[cfg(feature = "sample")]
+    #[test]
+    fn test_sampling() {
+        use sample::Sampler;
+
+        let ids: Vec<String> = (0..10_000).map(|i| ids::sentence_id("corpus", i)).collect();
+        let sample = |rate, seed| -> Vec<&String> {
+            let sampler = Sampler::new(rate, seed).unwrap();
+            ids.iter().filter(|id| sampler.keep(id)).collect()
+        };
+        let first = sample(0.01, 7);
+        assert!((70..=130).contains(&first.len()), "{}", first.len());
+        assert_eq!(first, sample(0.01, 7));
+        assert_ne!(first, sample(0.01, 8));
+        assert!(sample(0.0, 7).is_empty());
+        assert_eq!(sample(1.0, 7).len(), ids.len());
+        for rate in [f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                Sampler::new(rate, 7),
+                Err(SegmenterError::InvalidOptions(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_char_offsets() -> anyhow::Result<()> {
+        let text = "Grüße aus Köln. Ça va très bien. Noch ein Satz hier.";
+        let mut segmenter = Segmenter::new(SegmentOptions::default())?;
+        let mut segments = Vec::new();
+        // Chunks end in the middle of a sentence with multi-byte characters
+        for chunk in ["Grüße aus Köln. Ça va tr", "ès bien. Noch ein Satz hier."] {
+            segments.extend(segmenter.feed_segments(chunk)?);
+        }
+        segments.extend(segmenter.finish_segments()?);
+
+        let chars: Vec<char> = text.chars().collect();
+        for segment in &segments {
+            let by_char: String = chars[segment.char_start..segment.char_end].iter().collect();
+            assert_eq!(by_char, segment.text.trim_end());
+            assert_eq!(&text[segment.start..segment.end], segment.text.trim_end());
+        }
+        assert_eq!((segments[1].start, segments[1].char_start), (19, 16));
+        Ok(())
+    }
+
+    #[test]
+    fn test_feed_bytes() -> anyhow::Result<()> {
+        let mut segmenter = Segmenter::new(SegmentOptions::default())?;
+        let mut sentences = Vec::new();
+        // "ö" and "ß" are split across chunks
+        for chunk in [&b"Sch\xc3"[..], b"\xb6n. Gro\xc3", b"\x9f und klein. Ende"] {
+            sentences.extend(segmenter.feed_bytes(chunk)?);
+        }
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(sentences, ["Schön.", "Groß und klein.", "Ende"]);
+
+        // A character still incomplete at the end is subject to the UTF-8 policy
+        let mut segmenter = Segmenter::new(SegmentOptions::default())?;
+        segmenter.feed_bytes(b"Cut off \xe2\x82")?;
+        assert!(matches!(
+            segmenter.finish(),
+            Err(SegmenterError::Utf8Error { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocking() -> anyhow::Result<()> {
+        // The same segmenter serves several documents, each with offsets from 0
+        let mut segmenter = Segmenter::new(SegmentOptions::default())?;
+        assert_eq!(
+            segmenter.segment_all("One sentence. Another one")?,
+            ["One sentence.", "Another one"]
+        );
+        let segments = segmenter.segment_all_segments("Second document. Here.")?;
+        assert_eq!((segments[1].index, segments[1].start), (1, 17));
+
+        /// Hands out three bytes per read, splitting "ü".
+        struct Trickle<'a>(&'a [u8]);
+        impl std::io::Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = self.0.len().min(buf.len()).min(3);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+        let reader = Trickle("Grüß dich. Wie geht es?".as_bytes());
+        let sentences = blocking::sentences_iter(reader, SegmentOptions::default())?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(sentences, ["Grüß dich.", "Wie geht es?"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow_policy() -> anyhow::Result<()> {
+        let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            max_buffer: 20,
+            overflow_policy: OverflowPolicy::ForceSplitAtWhitespace,
+            warnings: Some(warnings::Warnings::new(move |w| {
+                sink.lock().unwrap().push(w)
+            })),
+            ..Default::default()
+        })?;
+        let mut sentences = segmenter.feed("aaa bbb ccc ddd eee fff ggg hhh. Done")?;
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(sentences, ["aaa bbb ccc ddd eee", "fff ggg hhh.", "Done"]);
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(
+            (received[0].kind, received[0].offset),
+            (warnings::WarningKind::ForcedSplit, 20)
+        );
+
+        // Combining accents stay with their letter, even when a chunk ends between them
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            max_buffer: 5,
+            overflow_policy: OverflowPolicy::ForceSplitAtGrapheme,
+            ..Default::default()
+        })?;
+        let mut sentences = segmenter.feed("e\u{301}e\u{301}e\u{301}")?;
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(sentences, ["e\u{301}"; 3]);
+
+        // Pieces split off an oversized chunk wait for the caller instead of being dropped
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            max_buffer: 12,
+            overflow_policy: OverflowPolicy::ForceSplitAtWhitespace,
+            ..Default::default()
+        })?;
+        let limited = segmenter.feed_limited("aaa bbb ccc ddd eee fff ggg hhh iii jjj kkk", 1)?;
+        assert_eq!(limited[0].text, "aaa bbb ccc");
+        let pending: Vec<_> = segmenter
+            .drain_pending()
+            .into_iter()
+            .map(|s| s.text)
+            .collect();
+        assert_eq!(pending, ["ddd eee fff", "ggg hhh iii"]);
+        assert_eq!(segmenter.finish()?, ["jjj kkk"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_sentence_len() -> anyhow::Result<()> {
+        let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            max_sentence_len: 24,
+            warnings: Some(warnings::Warnings::new(move |w| {
+                sink.lock().unwrap().push(w)
+            })),
+            ..Default::default()
+        })?;
+        // After the comma, at whitespace, then the rest ends at its own boundary
+        let mut sentences =
+            segmenter.feed("First we went home, then we ate supper together. Short one. ")?;
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(
+            sentences,
+            [
+                "First we went home,",
+                "then we ate supper",
+                "together.",
+                "Short one."
+            ]
+        );
+        assert_eq!(received.lock().unwrap().len(), 2);
+        assert!(received
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|w| w.kind == warnings::WarningKind::ForcedSplit));
+
+        // Without whitespace the cut falls between grapheme clusters
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            max_sentence_len: 5,
+            ..Default::default()
+        })?;
+        let mut sentences = segmenter.feed("e\u{301}e\u{301}e\u{301}. ")?;
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(sentences, ["e\u{301}", "e\u{301}", "e\u{301}."]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_sentence_len() -> anyhow::Result<()> {
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            min_sentence_len: 10,
+            ..Default::default()
+        })?;
+        assert!(segmenter.feed("Yes. ")?.is_empty());
+        let mut sentences = segmenter.feed("We will go there. Right. ")?;
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(sentences, ["Yes. We will go there.", "Right."]);
+
+        // At the end, a short sentence is merged with the tail, unless the tail is dropped
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            min_sentence_len: 10,
+            tail_policy: TailPolicy::Drop,
+            ..Default::default()
+        })?;
+        let mut sentences = segmenter.feed("Yes. And then")?;
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(sentences, ["Yes."]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_boundaries() -> anyhow::Result<()> {
+        use rules::{BoundaryAction, CustomBoundary};
+
+        let segment = |rules: Vec<CustomBoundary>, text: &str| -> Result<Vec<String>> {
+            Segmenter::new(SegmentOptions {
+                custom_boundaries: rules,
+                ..Default::default()
+            })?
+            .segment_all(text)
+        };
+        let bullets = vec![CustomBoundary::new("• ", BoundaryAction::SplitBefore)];
+        assert_eq!(
+            segment(bullets, "Shopping. Buy milk • Call mom • Done")?,
+            ["Shopping.", "Buy milk", "• Call mom", "• Done"]
+        );
+        let pipes = vec![CustomBoundary::new(r" \| ", BoundaryAction::Split)];
+        assert_eq!(segment(pipes, "Home | About us")?, ["Home |", "About us"]);
+        let approx = vec![CustomBoundary::new(r"\bapprox\.", BoundaryAction::NoSplit)];
+        assert_eq!(
+            segment(approx, "It weighs approx. Two tons. Yes.")?,
+            ["It weighs approx. Two tons.", "Yes."]
+        );
+
+        let invalid = vec![CustomBoundary::new("(", BoundaryAction::Split)];
+        assert!(matches!(
+            segment(invalid, ""),
+            Err(SegmenterError::InvalidOptions(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_backend() -> anyhow::Result<()> {
+        /// Ends a record at every `||`.
+        struct Records(regex::Regex);
+
+        impl SegmentationBackend for Records {
+            fn sentence_break_regex(&self) -> &regex::Regex {
+                &self.0
+            }
+
+            fn find_boundary(&self, text: &str, mtch: regex::Match<'_>) -> Option<(usize, bool)> {
+                let rest = &text[mtch.end()..];
+                Some((text.len() - rest.trim_start().len(), false))
+            }
+        }
+
+        // The language code has no libtqsm rules, but the backend does not need them
+        let options = SegmentOptions {
+            language: "x-records".to_string(),
+            lookahead: 2,
+            ..Default::default()
+        };
+        #[cfg(not(feature = "icu"))]
+        assert!(Segmenter::new(options.clone()).is_err());
+        let backend = Box::new(Records(regex::Regex::new(r"\|\|")?));
+        let mut segmenter = Segmenter::with_backend(options, backend)?;
+        let mut records = segmenter.feed("id=1. a=2 || id=2")?;
+        assert_eq!(records, ["id=1. a=2 ||"]);
+        records.extend(segmenter.finish()?);
+        assert_eq!(records.last().map(String::as_str), Some("id=2"));
+
+        // Detection would swap the backend for the rules of the detected language
+        let records = || Box::new(Records(regex::Regex::new(r"\|\|").unwrap()));
+        for options in [
+            SegmentOptions::for_language("auto"),
+            SegmentOptions {
+                sentence_language: detect::SentenceLanguage::Switch,
+                ..Default::default()
+            },
+        ] {
+            assert!(matches!(
+                Segmenter::with_backend(options, records()),
+                Err(SegmenterError::InvalidOptions(_))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_icu_backend() -> anyhow::Result<()> {
+        let icu = |language: &str| SegmentOptions {
+            language: language.to_string(),
+            backend: backend::Backend::Icu,
+            ..Default::default()
+        };
+        #[cfg(not(feature = "icu"))]
+        assert!(matches!(
+            Segmenter::new(icu("en")),
+            Err(SegmenterError::InvalidOptions(_))
+        ));
+        #[cfg(feature = "icu")]
+        {
+            let sentences =
+                Segmenter::new(icu("en"))?.segment_all("Hi there. How are you? Fine")?;
+            assert_eq!(sentences, ["Hi there.", "How are you?", "Fine"]);
+            // Greek asks questions with `;`
+            let sentences = Segmenter::new(icu("el"))?.segment_all("Τι κάνεις; Καλά.")?;
+            assert_eq!(sentences, ["Τι κάνεις;", "Καλά."]);
+
+            // A language without libtqsm rules falls back to ICU4X
+            let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+            let sink = received.clone();
+            let mut swahili = Segmenter::new(SegmentOptions {
+                language: "sw".to_string(),
+                warnings: Some(warnings::Warnings::new(move |w| {
+                    sink.lock().unwrap().push(w.kind)
+                })),
+                ..Default::default()
+            })?;
+            let sentences = swahili.segment_all("Habari yako? Nzuri sana. Asante")?;
+            assert_eq!(sentences, ["Habari yako?", "Nzuri sana.", "Asante"]);
+            assert_eq!(
+                *received.lock().unwrap(),
+                [warnings::WarningKind::LanguageFallback]
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_language() -> anyhow::Result<()> {
+        let auto = SegmentOptions {
+            language: detect::AUTO_LANGUAGE.to_string(),
+            detection_window: 64,
+            ..Default::default()
+        };
+        #[cfg(not(feature = "detect"))]
+        assert!(matches!(
+            Segmenter::new(auto),
+            Err(SegmenterError::InvalidOptions(_))
+        ));
+        #[cfg(feature = "detect")]
+        {
+            let mut segmenter = Segmenter::new(auto)?;
+            // Nothing comes out before the detection window is full
+            assert!(segmenter.feed("Das ist ein Satz. ")?.is_empty());
+            assert_eq!(segmenter.language(), "en");
+            let mut sentences = segmenter.feed(
+                "Der Hund schläft im Garten, und die Katze sitzt auf dem Dach. Es regnet. ",
+            )?;
+            sentences.extend(segmenter.finish()?);
+            assert_eq!(segmenter.language(), "de");
+            assert_eq!(
+                sentences,
+                [
+                    "Das ist ein Satz.",
+                    "Der Hund schläft im Garten, und die Katze sitzt auf dem Dach.",
+                    "Es regnet."
+                ]
+            );
+
+            // Short input is detected when it ends
+            segmenter.reset();
+            assert_eq!(
+                segmenter
+                    .segment_all(
+                        "Où est la gare? Je ne sais pas, demandez au monsieur à la fenêtre."
+                    )?
+                    .len(),
+                2
+            );
+            assert_eq!(segmenter.language(), "fr");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_sentence_language() -> anyhow::Result<()> {
+        use detect::SentenceLanguage;
+        let options = |sentence_language| SegmentOptions {
+            sentence_language,
+            sentence_languages: vec!["de".to_string(), "en".to_string()],
+            ..Default::default()
+        };
+        #[cfg(not(feature = "detect"))]
+        assert!(matches!(
+            Segmenter::new(options(SentenceLanguage::Tag)),
+            Err(SegmenterError::InvalidOptions(_))
+        ));
+        #[cfg(feature = "detect")]
+        {
+            let text = "I think we should meet tomorrow morning at the office. \
+                        Das Treffen findet am 3. Mai im großen Saal statt. Ok.";
+            let tagged = |sentence_language| -> anyhow::Result<Vec<(String, String)>> {
+                let segments =
+                    Segmenter::new(options(sentence_language))?.segment_all_segments(text)?;
+                Ok(segments
+                    .into_iter()
+                    .map(|s| (s.text, s.language.unwrap_or_default()))
+                    .collect())
+            };
+            let pair = |text: &str, language: &str| (text.to_string(), language.to_string());
+            // "Ok." is too short to tell, so it gets the language of the rules in use
+            assert_eq!(
+                tagged(SentenceLanguage::Tag)?,
+                [
+                    pair(
+                        "I think we should meet tomorrow morning at the office.",
+                        "en"
+                    ),
+                    pair("Das Treffen findet am 3.", "de"),
+                    pair("Mai im großen Saal statt.", "de"),
+                    pair("Ok.", "en"),
+                ]
+            );
+            // The German rules keep the date together
+            assert_eq!(
+                tagged(SentenceLanguage::Switch)?,
+                [
+                    pair(
+                        "I think we should meet tomorrow morning at the office.",
+                        "en"
+                    ),
+                    pair("Das Treffen findet am 3. Mai im großen Saal statt.", "de"),
+                    pair("Ok.", "de"),
+                ]
+            );
+            assert!(Segmenter::new(options(SentenceLanguage::Off))?
+                .segment_all_segments(text)?
+                .iter()
+                .all(|s| s.language.is_none()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_paragraph_unit() -> anyhow::Result<()> {
+        let text = "Introduction\n\nIt starts here. It goes on\nover two lines.\n \n\nThe end.";
+        let unit = |unit| SegmentOptions {
+            unit,
+            ..Default::default()
+        };
+        let paragraphs =
+            Segmenter::new(unit(config::SegmentUnit::Paragraph))?.segment_all_segments(text)?;
+        let texts: Vec<_> = paragraphs.iter().map(|p| p.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [
+                "Introduction",
+                "It starts here. It goes on\nover two lines.",
+                "The end."
+            ]
+        );
+        assert_eq!((paragraphs[2].line, paragraphs[2].column), (7, 1));
+        assert_eq!(&text[paragraphs[1].start..paragraphs[1].end], texts[1]);
+
+        // The heading ends at its blank line although it has no terminator
+        let sentences =
+            Segmenter::new(unit(config::SegmentUnit::Nested))?.segment_all_segments(text)?;
+        let nested: Vec<_> = sentences
+            .iter()
+            .map(|s| (s.paragraph, s.text.trim_end()))
+            .collect();
+        assert_eq!(
+            nested,
+            [
+                (Some(0), "Introduction"),
+                (Some(1), "It starts here."),
+                (Some(1), "It goes on\nover two lines."),
+                (Some(2), "The end.")
+            ]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "compare")]
+    #[test]
+    fn test_compare_backends() -> anyhow::Result<()> {
+        use compare::Backend;
+        let text = "Ask Dr. Smith. He knows.";
+        let backends: Vec<Backend> = ["tqsm", "uax29"]
+            .iter()
+            .map(|name| name.parse())
+            .collect::<Result<_>>()?;
+        let comparison = compare::compare(text, &backends, &SegmentOptions::default())?;
+        assert_eq!(comparison.counts, [(Backend::Tqsm, 2), (Backend::Uax29, 3)]);
+        assert_eq!(comparison.differences[0].offset, 7);
+        assert!(matches!(
+            "srx".parse::<Backend>(),
+            Err(SegmenterError::UnsupportedBackend(_))
+        ));
+        #[cfg(feature = "icu")]
+        assert_eq!("icu".parse::<Backend>()?, Backend::Icu);
+        #[cfg(not(feature = "icu"))]
+        assert!(matches!(
+            "icu".parse::<Backend>(),
+            Err(SegmenterError::InvalidOptions(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn test_python_flush() -> anyhow::Result<()> {
+        // The default lookahead holds both sentences back until the end
+        let mut segmenter = python::PySegmenter::new("en", 10, 8192)?;
+        assert!(segmenter.feed("Hello there. Bye now.")?.is_empty());
+        assert_eq!(segmenter.flush()?, ["Hello there.", "Bye now."]);
+        assert!(segmenter.flush()?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_wasm_finish() {
+        let mut segmenter = wasm::WasmSegmenter::new(None, None, None).unwrap();
+        assert!(segmenter.feed("Hello there. Bye now.").unwrap().is_empty());
+        assert_eq!(segmenter.finish().unwrap(), ["Hello there.", "Bye now."]);
+    }
+
+    #[cfg(any(feature = "nats", feature = "redis"))]
+    #[test]
+    fn test_connector_relay() -> anyhow::Result<()> {
+        let mut relay = connectors::Relay::new(SegmentOptions {
+            lookahead: 0,
+            ..Default::default()
+        })?;
+        assert_eq!(relay.message(b"One. Tw", false), ["One."]);
+        assert!(relay.message(b"o", false).is_empty());
+        // Invalid UTF-8 drops the buffered "Two" and the service goes on
+        assert!(relay.message(b"\xff", false).is_empty());
+        assert_eq!(relay.message(b"Three. Fo", true), ["Three.", "Fo"]);
+        assert_eq!(relay.message("Fünf.".as_bytes(), false), ["Fünf."]);
+        assert!(relay.finish().is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "capi")]
+    #[test]
+    fn test_capi_split_character() {
+        use std::ffi::{c_char, c_void};
+
+        extern "C" fn collect(sentence: *const c_char, len: usize, user_data: *mut c_void) {
+            // SAFETY: the test passes a `Vec<String>` as `user_data`, and `sentence` is `len`
+            // bytes of UTF-8
+            unsafe {
+                let bytes = std::slice::from_raw_parts(sentence as *const u8, len);
+                let sentences = &mut *(user_data as *mut Vec<String>);
+                sentences.push(String::from_utf8_lossy(bytes).into_owned());
+            }
+        }
+
+        let mut sentences: Vec<String> = Vec::new();
+        let user_data = &mut sentences as *mut Vec<String> as *mut c_void;
+        let text = "Grüß dich. Tschüss.".as_bytes();
+        // SAFETY: every pointer passed is valid for the duration of the call
+        unsafe {
+            let mut segmenter = std::ptr::null_mut();
+            let language = c"de".as_ptr();
+            assert_eq!(
+                capi::tqsm_segmenter_new(language, 0, 8192, &mut segmenter),
+                0
+            );
+            // Splits the two bytes of "ü"
+            for chunk in [&text[..3], &text[3..]] {
+                let status = capi::tqsm_feed(
+                    segmenter,
+                    chunk.as_ptr() as *const c_char,
+                    chunk.len(),
+                    Some(collect),
+                    user_data,
+                );
+                assert_eq!(status, capi::TQSM_OK);
+            }
+            assert_eq!(capi::tqsm_flush(segmenter, Some(collect), user_data), 0);
+            capi::tqsm_segmenter_free(segmenter);
+        }
+        assert_eq!(sentences, ["Grüß dich.", "Tschüss."]);
+    }
+
+    #[test]
+    fn test_presets() -> anyhow::Result<()> {
+        use config::Preset;
+
+        let mut strict = Segmenter::new(SegmentOptions::preset(Preset::Strict))?;
+        strict.feed("Unfinished")?;
+        assert!(strict.finish_segments()?[0].incomplete);
+
+        let lenient = SegmentOptions::preset(Preset::Lenient);
+        assert_eq!(lenient.utf8_policy, decode::Utf8Policy::Replace);
+        assert!(lenient.retry.is_retryable(std::io::ErrorKind::TimedOut));
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_items_stream_end() -> anyhow::Result<()> {
+        let reader = MockReader {
+            data: vec!["It rained. ", "And then"],
+            pos: 0,
+        };
+        let options = SegmentOptions {
+            tail_policy: TailPolicy::Drop,
+            ..Default::default()
+        };
+        let items: Vec<StreamItem> = items_stream(reader, options)
+            .map(|i| i.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(items.len(), 2);
+        assert!(matches!(&items[0], StreamItem::Segment(s) if s.text == "It rained."));
+        let StreamItem::End {
+            flushed_tail,
+            stats,
+        } = &items[1]
+        else {
+            panic!("missing end marker");
+        };
+        assert_eq!(flushed_tail.as_deref(), Some("And then"));
+        assert_eq!(stats.sentences, 1);
+        assert_eq!(stats.bytes, 19);
+        assert_eq!(stats.languages["en"].sentences, 1);
+        assert_eq!(stats.languages["en"].bytes, 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_abbreviations_reload() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("async-tqsm-abbr-{}", std::process::id()));
+        std::fs::write(&path, "# domain terms\nfoo.\n")?;
+        let shared = abbreviations::SharedAbbreviations::load([&path])?;
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            lookahead: 0,
+            shared_abbreviations: Some(shared.clone()),
+            ..Default::default()
+        })?;
+
+        // A live segmenter sees the reloaded list
+        std::fs::write(&path, "foo.\napprox\n")?;
+        shared.reload()?;
+        std::fs::remove_file(&path)?;
+        let mut sentences = segmenter.feed("It weighs approx. two tons. Really.")?;
+        sentences.extend(segmenter.flush()?);
+
+        assert_eq!(shared.generation(), 2);
+        assert_eq!(sentences[0], "It weighs approx. two tons.");
+
+        // A numeric-only entry needs a number after its period
+        std::fs::write(&path, "Fol #NUMERIC_ONLY#\n")?;
+        let shared = abbreviations::SharedAbbreviations::load([&path])?;
+        std::fs::remove_file(&path)?;
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            lookahead: 0,
+            shared_abbreviations: Some(shared),
+            ..Default::default()
+        })?;
+        let mut sentences = segmenter.feed("See Fol. 5 below. Ask for the Fol. Then call.")?;
+        sentences.extend(segmenter.flush()?);
+        assert_eq!(
+            sentences,
+            ["See Fol. 5 below.", "Ask for the Fol.", "Then call."]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_utf8_decoder() -> anyhow::Result<()> {
+        use decode::{Utf8Decoder, Utf8Policy};
+
+        // "é" split across two chunks is reassembled
+        let mut decoder = Utf8Decoder::new(Utf8Policy::Strict);
+        assert_eq!(decoder.decode(b"caf\xc3")?, "caf");
+        assert_eq!(decoder.decode(b"\xa9!")?, "\u{e9}!");
+        assert!(decoder.finish()?.is_none());
+
+        let invalid = b"a\xffb\xe2\x82";
+        let mut strict = Utf8Decoder::new(Utf8Policy::Strict);
+        strict.decode(b"xyz")?;
+        let error = strict.decode(invalid).unwrap_err();
+        assert_eq!(error.offset(), Some(4));
+        assert!(error.to_string().ends_with("near \"a\u{fffd}b\u{fffd}\""));
+
+        let mut skip = Utf8Decoder::new(Utf8Policy::SkipInvalid);
+        assert_eq!(skip.decode(invalid)?, "ab");
+        assert_eq!(skip.finish()?, None);
+
+        let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let warnings = warnings::Warnings::new(move |w| sink.lock().unwrap().push(w));
+        let mut replace = Utf8Decoder::for_options(&SegmentOptions {
+            utf8_policy: Utf8Policy::Replace,
+            warnings: Some(warnings),
+            ..Default::default()
+        });
+        assert_eq!(replace.decode(invalid)?, "a\u{fffd}b");
+        assert_eq!(replace.finish()?.as_deref(), Some("\u{fffd}"));
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].kind, warnings::WarningKind::LossyDecode);
+        assert_eq!((received[0].offset, received[1].offset), (1, 3));
+        Ok(())
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_encodings() -> anyhow::Result<()> {
+        use decode::{Utf8Decoder, Utf8Policy};
+
+        let cp1252 = SegmentOptions {
+            encoding: Some("windows-1252".into()),
+            ..Default::default()
+        };
+        let mut segmenter = Segmenter::new(cp1252.clone())?;
+        let mut sentences = segmenter.feed_bytes(b"Caf\xe9 ouvert. Na\xefve \x80 text. End")?;
+        sentences.extend(segmenter.finish()?);
+        assert_eq!(sentences, ["Café ouvert.", "Naïve € text.", "End"]);
+
+        // A Shift_JIS character split across chunks is held back by the transcoder
+        let shift_jis = SegmentOptions {
+            encoding: Some("shift_jis".into()),
+            ..Default::default()
+        };
+        let mut decoder = Utf8Decoder::for_options(&shift_jis);
+        assert_eq!(decoder.decode(b"\x93\xfa\x96")?, "日");
+        assert_eq!(decoder.decode(b"\x7b")?, "本");
+        assert_eq!(decoder.finish()?, None);
+
+        let mut strict = Utf8Decoder::for_options(&shift_jis);
+        let error = strict.decode(b"ab\xffc").unwrap_err();
+        assert!(matches!(error, SegmenterError::DecodeError { .. }));
+        assert_eq!((error.offset(), error.code()), (Some(2), 11));
+        let mut replace = Utf8Decoder::for_options(&SegmentOptions {
+            utf8_policy: Utf8Policy::Replace,
+            ..shift_jis
+        });
+        assert_eq!(replace.decode(b"ab\xffc")?, "ab\u{fffd}c");
+
+        // A byte order mark overrides the configured encoding
+        let mut sniffing = Utf8Decoder::for_options(&SegmentOptions {
+            sniff_bom: true,
+            ..cp1252
+        });
+        assert_eq!(sniffing.decode(b"\xef\xbb\xbfCaf\xc3\xa9")?, "Café");
+
+        let unknown = Segmenter::new(SegmentOptions {
+            encoding: Some("klingon".into()),
+            ..Default::default()
+        });
+        assert!(matches!(unknown, Err(SegmenterError::InvalidOptions(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow_context() -> anyhow::Result<()> {
+        let mut segmenter = Segmenter::new(SegmentOptions {
+            max_buffer: 12,
+            lookahead: 100,
+            ..Default::default()
+        })?;
+        segmenter.feed("no stop")?;
+
+        match segmenter.feed(" in sight") {
+            Err(SegmenterError::BufferOverflow {
+                max,
+                offset,
+                snippet,
+                pending,
+            }) => {
+                assert_eq!((max, offset), (12, 7));
+                assert_eq!(snippet, "no stop in sight");
+                assert_eq!(pending, "no stop in sight");
+            }
+            other => panic!("expected overflow, got {:?}", other),
+        }
+        // The segmenter carries on with an empty buffer
+        assert_eq!(segmenter.buffered(), 0);
+        let segments = segmenter.feed_segments("Next.")?;
+        let tail = segmenter.flush_segment()?;
+        let first = segments.first().or(tail.as_ref()).unwrap();
+        assert_eq!(first.start, 16);
+        Ok(())
+    }
+
+    #[test]
+    fn test_io_error_conversion() {
+        use std::io::ErrorKind;
+
+        let original = std::io::Error::new(ErrorKind::BrokenPipe, "gone");
+        let unwrapped: std::io::Error = SegmenterError::IoError(original).into();
+        assert_eq!(unwrapped.kind(), ErrorKind::BrokenPipe);
+
+        let wrapped: std::io::Error = SegmenterError::UnsupportedLanguage("xx".into()).into();
+        assert_eq!(wrapped.kind(), ErrorKind::InvalidInput);
+        let inner = wrapped.into_inner().unwrap().downcast::<SegmenterError>();
+        assert!(matches!(
+            inner.as_deref(),
+            Ok(SegmenterError::UnsupportedLanguage(_))
+        ));
+    }
+
+    #[cfg(feature = "align")]
+    #[test]
+    fn test_align_by_length() {
+        let source = [
+            "Short one.",
+            "This is a much longer sentence in the source.",
+            "End.",
+        ];
+        let target = [
+            "Kurz.",
+            "Dies ist ein viel längerer Satz",
+            "in der Übersetzung.",
+            "Ende.",
+        ];
+
+        let beads = align::align(&source, &target);
+        assert_eq!(
+            beads,
+            [
+                align::Bead {
+                    source: 0..1,
+                    target: 0..1
+                },
+                align::Bead {
+                    source: 1..2,
+                    target: 1..3
+                },
+                align::Bead {
+                    source: 2..3,
+                    target: 3..4
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "duration")]
+    #[test]
+    fn test_estimate_duration() {
+        use std::time::Duration;
+
+        // 10 words at 150 wpm = 4s, plus the sentence-final pause
+        let text = "One two three four five six seven eight nine ten.";
+        let estimate = duration::estimate_duration(text, "en", Some(150.0));
+        assert_eq!(estimate, Duration::from_millis(4250));
+
+        // Character-based languages count characters, not words
+        let estimate = duration::estimate_duration("今日は", "ja", Some(180.0));
+        assert_eq!(estimate, Duration::from_secs(1));
+
+        // Rates that are not a positive number give no estimate
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                duration::estimate_duration(text, "en", Some(rate)),
+                Duration::ZERO
+            );
+        }
+        #[cfg(feature = "cli")]
+        {
+            use clap::Parser;
+            for rate in ["NaN", "inf", "0", "-5"] {
+                let args = config::CliArgs::try_parse_from(["async-tqsm", "--wpm", rate]);
+                assert!(args.is_err(), "--wpm {} was accepted", rate);
+            }
+        }
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn test_futures_io_stream() -> anyhow::Result<()> {
+        let reader = futures::io::Cursor::new("First sentence. Second sentence has no end");
+        let stream = futures_io::sentences_stream(reader, SegmentOptions::default());
+
+        let results: Vec<String> = futures::executor::block_on(stream.collect::<Vec<_>>())
+            .into_iter()
+            .collect::<Result<_>>()?;
+
+        assert_eq!(results, ["First sentence.", "Second sentence has no end"]);
+
+        let reader = futures::io::Cursor::new("Grüße. Zweiter Satz folgt hier");
+        let stream = futures_io::segments_stream(reader, SegmentOptions::default());
+        let segments: Vec<Segment> = futures::executor::block_on(stream.collect::<Vec<_>>())
+            .into_iter()
+            .collect::<Result<_>>()?;
+        assert_eq!((segments[1].start, segments[1].char_start), (9, 7));
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_stream_no_final_punctuation() -> anyhow::Result<()> {
         let options = SegmentOptions::default();
@@ -0,0 +1,39 @@
+//! Ready-made `tracing-subscriber` setup for binaries embedding the segmenter.
+//!
+//! Events carry structured fields such as `doc_id`, `offset` and `error_kind`; the JSON format
+//! emits one object per line for log aggregation, the text format is meant for terminals.
+
+use crate::error::{Result, SegmenterError};
+use tracing_subscriber::EnvFilter;
+
+/// Filter used when `RUST_LOG` is not set.
+const DEFAULT_FILTER: &str = "async_tqsm=info";
+
+/// Output format of [`init_logging`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum LogFormat {
+    /// Human-readable lines on stderr.
+    #[default]
+    Text,
+    /// One JSON object per event on stderr.
+    Json,
+}
+
+/// Installs a global subscriber writing to stderr in `format`, filtered by `RUST_LOG`
+/// (default `async_tqsm=info`).
+///
+/// Fails if a global subscriber is already installed.
+pub fn init_logging(format: LogFormat) -> Result<()> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+
+    let result = match format {
+        LogFormat::Text => builder.without_time().with_target(false).try_init(),
+        LogFormat::Json => builder.json().flatten_event(true).try_init(),
+    };
+    result.map_err(|e| SegmenterError::StreamError(e.to_string()))
+}
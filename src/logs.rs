@@ -0,0 +1,25 @@
+//! Rules for application logs ([`Mode::Log`](crate::config::Mode::Log)): every line is a
+//! record, and the timestamp and level a logger puts in front of the message are left out of
+//! the sentences, so only the message itself is segmented.
+
+use crate::error::{Result, SegmenterError};
+use regex::Regex;
+
+/// Prefix stripped from log lines unless
+/// [`SegmentOptions::log_prefix`](crate::SegmentOptions::log_prefix) says otherwise: an
+/// optional ISO 8601, syslog (`Jan  5 12:00:00`) or bare time stamp, optionally bracketed,
+/// followed by an optional level such as `INFO`, `[warn]` or `ERROR:`, and an optional
+/// `[target]` or `module:` tag.
+pub const DEFAULT_PREFIX: &str = r"(?i)(?:\[?(?:\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(?::\d{2}(?:[.,]\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?|[A-Z][a-z]{2} +\d{1,2} \d{2}:\d{2}:\d{2}|\d{2}:\d{2}:\d{2}(?:[.,]\d+)?)\]?[ \t]+)?(?:[\[<]?(?:TRACE|DEBUG|INFO|NOTICE|WARN|WARNING|ERROR|ERR|FATAL|CRIT|CRITICAL)[\]>]?:?[ \t]+)?(?:\[[^\]\n]*\][ \t]+|[\w:.-]+:[ \t]+)?";
+
+/// Compiles `pattern` (or [`DEFAULT_PREFIX`]) so it only matches at the start of the text.
+pub(crate) fn prefix_regex(pattern: Option<&str>) -> Result<Regex> {
+    let pattern = pattern.unwrap_or(DEFAULT_PREFIX);
+    Regex::new(&format!("^(?:{})", pattern))
+        .map_err(|e| SegmenterError::InvalidOptions(format!("log prefix {:?}: {}", pattern, e)))
+}
+
+/// Length of the prefix at the start of `line`; 0 if there is none.
+pub(crate) fn prefix_len(prefix: &Regex, line: &str) -> usize {
+    prefix.find(line).map_or(0, |m| m.end())
+}
@@ -0,0 +1,103 @@
+//! A memory budget shared by many segmenters, for servers running thousands of sessions whose
+//! individual `max_buffer` limits add up to more than the process can afford.
+//!
+//! Put the same [`MemoryPool`] into the [`SegmentOptions`](crate::SegmentOptions) of every
+//! session. Each segmenter accounts the text it buffers against the pool and gives it back as
+//! sentences leave the buffer (or the segmenter is dropped). When a chunk would take the pool
+//! over its capacity, the [`MemoryPolicy`] decides what happens; the server modes additionally
+//! pause reading input while the pool is [under pressure](MemoryPool::under_pressure).
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Share of the capacity above which the pool is under pressure.
+const PRESSURE_THRESHOLD: f64 = 0.9;
+
+/// What a segmenter does with a chunk the pool has no room for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum MemoryPolicy {
+    /// Emit everything the segmenter holds, splitting the incomplete sentence at the end of
+    /// its buffer, then accept the chunk. The pool may briefly exceed its capacity by up to
+    /// one chunk per session.
+    #[default]
+    Flush,
+    /// Refuse the chunk with [`MemoryBudgetExceeded`](crate::SegmenterError::MemoryBudgetExceeded),
+    /// leaving the buffer as it was, so the caller can feed it again once other sessions have
+    /// drained.
+    Reject,
+}
+
+#[derive(Debug)]
+struct Inner {
+    capacity: usize,
+    policy: MemoryPolicy,
+    used: AtomicUsize,
+}
+
+/// Byte budget for the text buffered by all segmenters sharing it; cheap to clone, clones share
+/// the budget.
+#[derive(Debug, Clone)]
+pub struct MemoryPool {
+    inner: Arc<Inner>,
+}
+
+impl MemoryPool {
+    /// A pool of `capacity` bytes with the default [`MemoryPolicy::Flush`].
+    pub fn new(capacity: usize) -> Self {
+        Self::with_policy(capacity, MemoryPolicy::default())
+    }
+
+    pub fn with_policy(capacity: usize, policy: MemoryPolicy) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                capacity,
+                policy,
+                used: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+
+    pub fn policy(&self) -> MemoryPolicy {
+        self.inner.policy
+    }
+
+    /// Bytes currently buffered by all segmenters using the pool.
+    pub fn used(&self) -> usize {
+        self.inner.used.load(Ordering::Relaxed)
+    }
+
+    /// Whether more than 90% of the capacity is in use, so new input should wait.
+    pub fn under_pressure(&self) -> bool {
+        self.used() as f64 > self.inner.capacity as f64 * PRESSURE_THRESHOLD
+    }
+
+    /// Accounts for `bytes` if they fit into the capacity.
+    pub(crate) fn try_reserve(&self, bytes: usize) -> bool {
+        self.inner
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |used| {
+                used.checked_add(bytes)
+                    .filter(|&total| total <= self.inner.capacity)
+            })
+            .is_ok()
+    }
+
+    /// Accounts for `bytes` whether they fit or not.
+    pub(crate) fn force_reserve(&self, bytes: usize) {
+        self.inner.used.fetch_add(bytes, Ordering::AcqRel);
+    }
+
+    pub(crate) fn release(&self, bytes: usize) {
+        self.inner.used.fetch_sub(bytes, Ordering::AcqRel);
+    }
+}
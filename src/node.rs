@@ -0,0 +1,67 @@
+//! Node.js bindings, built with `npm run build` in `node/` (the `node` feature, as a `cdylib`).
+//!
+//! `node/index.js` loads the native module and adds the `sentences()` async iterator over a
+//! Readable stream.
+
+use crate::config::SegmentOptions;
+use crate::error::SegmenterError;
+use crate::segmenter::Segmenter;
+use napi_derive::napi;
+
+/// Options accepted by the `SentenceSegmenter` constructor; omitted values use the defaults.
+#[napi(object)]
+pub struct SegmenterOptions {
+    pub language: Option<String>,
+    pub lookahead: Option<u32>,
+    pub max_buffer: Option<u32>,
+}
+
+/// Streaming sentence segmenter: `feed()` text chunks, `flush()` at the end.
+#[napi]
+pub struct SentenceSegmenter {
+    inner: Segmenter,
+}
+
+#[napi]
+impl SentenceSegmenter {
+    #[napi(constructor)]
+    pub fn new(options: Option<SegmenterOptions>) -> napi::Result<Self> {
+        let mut segment_options = SegmentOptions::default();
+        if let Some(options) = options {
+            if let Some(language) = options.language {
+                segment_options.language = language;
+            }
+            if let Some(lookahead) = options.lookahead {
+                segment_options.lookahead = lookahead as usize;
+            }
+            if let Some(max_buffer) = options.max_buffer {
+                segment_options.max_buffer = max_buffer as usize;
+            }
+        }
+        let inner = Segmenter::new(segment_options).map_err(to_napi_err)?;
+        Ok(Self { inner })
+    }
+
+    /// Appends a chunk and returns the sentences it completed.
+    #[napi]
+    pub fn feed(&mut self, chunk: String) -> napi::Result<Vec<String>> {
+        self.inner.feed(&chunk).map_err(to_napi_err)
+    }
+
+    /// Ends the input and returns the remaining sentences, including those still held back
+    /// for lookahead and the unterminated tail.
+    #[napi]
+    pub fn flush(&mut self) -> napi::Result<Vec<String>> {
+        self.inner.finish().map_err(to_napi_err)
+    }
+}
+
+fn to_napi_err(e: SegmenterError) -> napi::Error {
+    let status = match e {
+        SegmenterError::UnsupportedLanguage(_) | SegmenterError::Utf8Error { .. } => {
+            napi::Status::InvalidArg
+        }
+        _ => napi::Status::GenericFailure,
+    };
+    napi::Error::new(status, e.to_string())
+}
@@ -0,0 +1,151 @@
+//! Functions applied to every sentence before it is emitted, e.g. to strip markup or collapse
+//! whitespace, so consumers of the sentence streams need no extra `map` stage.
+//!
+//! Set [`SegmentOptions::post_process`](crate::SegmentOptions::post_process) to a
+//! [`PostProcess`]; every segmenter and stream built from those options applies it. Only the
+//! sentence text changes: a [`Segment`](crate::Segment)'s offsets, line and column still refer to
+//! the original input.
+//!
+//! [`strip_markup`] is a ready-made step for speech synthesis, which would otherwise read out
+//! the Markdown that language models and chat messages are full of.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// A sentence transformation; cheap to clone, clones share the function.
+#[derive(Clone)]
+pub struct PostProcess {
+    step: Arc<dyn Fn(String) -> String + Send + Sync>,
+}
+
+impl PostProcess {
+    /// Calls `step` on every sentence, on the thread that segments it.
+    pub fn new(step: impl Fn(String) -> String + Send + Sync + 'static) -> Self {
+        Self {
+            step: Arc::new(step),
+        }
+    }
+
+    /// Applies `next` to the output of this transformation.
+    pub fn then(self, next: impl Fn(String) -> String + Send + Sync + 'static) -> Self {
+        Self::new(move |sentence| next((self.step)(sentence)))
+    }
+
+    pub fn apply(&self, sentence: String) -> String {
+        (self.step)(sentence)
+    }
+
+    /// [`strip_markup`] as a post-processing step.
+    pub fn strip_markup() -> Self {
+        Self::new(|sentence| strip_markup(&sentence))
+    }
+}
+
+impl fmt::Debug for PostProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PostProcess").finish_non_exhaustive()
+    }
+}
+
+/// Bullet glyphs that start a list item when followed by a space.
+const BULLETS: [char; 8] = ['-', '*', '+', '•', '◦', '▪', '‣', '·'];
+
+/// Text with markup removed, and where each of its bytes came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stripped {
+    pub text: String,
+    /// `offsets[i]` is the byte offset in the input of byte `i` of `text`; it has one more entry,
+    /// the end of the input, so that `offsets[text.len()]` is valid too.
+    pub offsets: Vec<usize>,
+}
+
+/// Removes Markdown that should not be read aloud: `*emphasis*`, `_emphasis_`, `~~strikes~~`,
+/// backticks, heading markers (`## `) and bullet glyphs (`- `, `• `) at the start of a line.
+///
+/// Emphasis markers are only removed where they open or close a span, i.e. next to a word on
+/// one side and a space or the edge of the text on the other, so `snake_case` and `2 * 3` are
+/// left alone.
+pub fn strip_markup(text: &str) -> String {
+    strip_markup_mapped(text).text
+}
+
+/// Like [`strip_markup`], also returning the input offset of every byte of the result, e.g. to
+/// map a word boundary reported by a speech engine back into the original text.
+pub fn strip_markup_mapped(text: &str) -> Stripped {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut stripped = Stripped {
+        text: String::with_capacity(text.len()),
+        offsets: Vec::with_capacity(text.len() + 1),
+    };
+    let mut i = 0;
+    while i < chars.len() {
+        let line_start = i == 0 || chars[i - 1].1 == '\n';
+        if line_start {
+            i = skip_line_marker(&chars, i);
+            if i >= chars.len() {
+                break;
+            }
+        }
+        let (offset, c) = chars[i];
+        if c == '`' {
+            i += 1;
+            continue;
+        }
+        if matches!(c, '*' | '_' | '~') {
+            let run = chars[i..].iter().take_while(|&&(_, d)| d == c).count();
+            let before = i.checked_sub(1).map(|j| chars[j].1);
+            let after = chars.get(i + run).map(|&(_, d)| d);
+            let word = |d: Option<char>| d.is_some_and(|d| !d.is_whitespace());
+            let edge = |d: Option<char>| d.is_none_or(|d| d.is_whitespace() || is_closing(d));
+            let opens = word(after) && before.is_none_or(|d| d.is_whitespace() || is_opening(d));
+            let closes = word(before) && edge(after);
+            let delimiter = (c != '~' || run >= 2) && (opens || closes);
+            if delimiter {
+                i += run;
+                continue;
+            }
+        }
+        stripped.text.push(c);
+        stripped.offsets.extend(offset..offset + c.len_utf8());
+        i += 1;
+    }
+    stripped.offsets.push(text.len());
+    stripped
+}
+
+/// Skips leading spaces, then a heading marker or bullet and the spaces after it, returning the
+/// index of the first character to keep.
+fn skip_line_marker(chars: &[(usize, char)], start: usize) -> usize {
+    let indent = chars[start..]
+        .iter()
+        .take_while(|&&(_, c)| c == ' ')
+        .count();
+    let i = start + indent;
+    let marker = match chars.get(i).map(|&(_, c)| c) {
+        Some('#') => {
+            let hashes = chars[i..].iter().take_while(|&&(_, c)| c == '#').count();
+            (hashes <= 6).then_some(hashes)
+        }
+        Some(c) if BULLETS.contains(&c) => Some(1),
+        _ => None,
+    };
+    match marker {
+        Some(len) if chars.get(i + len).is_some_and(|&(_, c)| c == ' ') => {
+            let spaces = chars[i + len..]
+                .iter()
+                .take_while(|&&(_, c)| c == ' ')
+                .count();
+            i + len + spaces
+        }
+        _ => start,
+    }
+}
+
+fn is_opening(c: char) -> bool {
+    matches!(c, '(' | '[' | '"' | '\'' | '“' | '‘' | '«')
+}
+
+fn is_closing(c: char) -> bool {
+    (c.is_ascii_punctuation() && !matches!(c, '*' | '_' | '~' | '`'))
+        || matches!(c, '”' | '’' | '»')
+}
@@ -0,0 +1,416 @@
+//! Rules on top of the language's own for constructs it commonly gets wrong, each of which can
+//! be switched off through [`Protections`].
+
+use crate::error::{Result, SegmenterError};
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Which extra rules a segmenter applies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Protections {
+    /// Keep footnote and citation markers after a sentence (`word.[12]`, `word.¹`,
+    /// `sentence."³`) with that sentence instead of starting the next one with them.
+    pub footnotes: bool,
+    /// Never split inside a parenthetical citation (`(Smith et al., 2020)`), and keep one that
+    /// follows the sentence punctuation (`…previously. (Smith, 2020)`) with that sentence.
+    pub citations: bool,
+    /// When a period after a single capital letter (`J. R. R. Tolkien`) does not end a sentence.
+    pub initials: Strictness,
+    /// When a period after a Roman numeral (`Chapter IV. The Journey`) does not end a sentence.
+    pub roman_numerals: Strictness,
+    /// Never split inside times and dates such as `5 p.m.`, `Jan. 5, 2024` or `No. 7`, using
+    /// the built-in patterns for the segmenter's language.
+    pub dates_and_times: bool,
+    /// Lexicons of units and quantity abbreviations (`approx. 3 kg. of flour`, `Nr. 5`) whose
+    /// periods never end a sentence when a number or a lower-case word follows.
+    pub measurements: Vec<MeasurementDomain>,
+    /// Ordinal numbers written with a period (`Am 3. Mai`, `der 2. Platz`). `None` uses the
+    /// language's default: `Conservative` for languages that write ordinals this way (German,
+    /// the Nordic and most Slavic languages, …), `Off` for the others.
+    pub ordinal_dates: Option<Strictness>,
+    /// More regexes for spans no sentence ends inside, e.g. from [`read_rules_file`]. A
+    /// boundary is ruled out when a match starts before its punctuation and ends after it.
+    pub patterns: Vec<String>,
+}
+
+impl Default for Protections {
+    fn default() -> Self {
+        Self {
+            footnotes: true,
+            citations: true,
+            initials: Strictness::Conservative,
+            roman_numerals: Strictness::Conservative,
+            dates_and_times: true,
+            measurements: vec![MeasurementDomain::General],
+            ordinal_dates: None,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// How eagerly a protection rules out a boundary: `Aggressive` prevents more false splits, at
+/// the price of missing some real sentence ends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Strictness {
+    /// Leave it to the language rules.
+    Off,
+    /// Only where the construct is unambiguous: initials in a chain (`J. R. R.`) or opening
+    /// the sentence, Roman numerals after words like `Chapter` or `Part`.
+    #[default]
+    Conservative,
+    /// Also a lone initial before a capitalized word (`John F. Kennedy`, but also
+    /// `vitamin C. The`), and any Roman numeral other than `I`.
+    Aggressive,
+}
+
+/// End of the boundary after a footnote marker that directly follows sentence punctuation
+/// ending at `punctuation_end`, including the whitespace after the marker. `None` if there is
+/// no marker, or no whitespace after it yet.
+pub(crate) fn footnote_boundary(text: &str, punctuation_end: usize) -> Option<usize> {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    let marker = MARKER.get_or_init(|| {
+        Regex::new(r#"^["'”’»)]*(?:\[(?:\d{1,3}|[a-z]|citation needed)\]|[⁰¹²³⁴⁵⁶⁷⁸⁹]+)+\s+"#)
+            .expect("valid footnote pattern")
+    });
+    marker
+        .find(&text[punctuation_end..])
+        .map(|m| punctuation_end + m.end())
+}
+
+/// A parenthetical citation at the start of the text after a boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Citation {
+    None,
+    /// Bytes up to the end of the citation and the whitespace after it.
+    End(usize),
+    /// It looks like one, but its end has not arrived yet.
+    Incomplete,
+}
+
+/// Author-year citation in parentheses, such as `(Smith et al., 2020)` or `(Müller 2019a)`,
+/// optionally followed by a period.
+const CITATION: &str =
+    r"\(\s*(?:see\s+|cf\.\s+|e\.g\.,?\s+)?\p{Lu}[^()\n]{0,120}?\b\d{4}[a-z]?\)\.?";
+
+/// The beginning of a citation, up to a point where its year or closing parenthesis may still
+/// be missing: optional initials, a name, `et al.`/`and`/`&` and a co-author, a year and a
+/// page number.
+const CITATION_START: &str = r"\(\s*(?:see\s+|cf\.\s+|e\.g\.,?\s+)?(?:\p{Lu}\.\s*)*(?:\p{Lu}[\p{L}'’-]+(?:\s+(?:et\s+al\.?|and|&)(?:\s+\p{Lu}[\p{L}'’-]+)?)?,?\s*(?:\d{1,4}[a-z]?(?:,\s*(?:pp?\.\s*)?\d*)?)?)?";
+
+/// Classifies the text after a boundary.
+pub(crate) fn trailing_citation(after: &str) -> Citation {
+    static COMPLETE: OnceLock<Regex> = OnceLock::new();
+    static STARTED: OnceLock<Regex> = OnceLock::new();
+    let complete = COMPLETE
+        .get_or_init(|| Regex::new(&format!(r"^{}\s+", CITATION)).expect("valid citation pattern"));
+    let started = STARTED.get_or_init(|| {
+        Regex::new(&format!(r"^{}$", CITATION_START)).expect("valid citation pattern")
+    });
+    if let Some(m) = complete.find(after) {
+        Citation::End(m.end())
+    } else if started.is_match(after) {
+        Citation::Incomplete
+    } else {
+        Citation::None
+    }
+}
+
+/// Whether `before` ends inside an unclosed citation, as at the period of `(Smith et al.`.
+pub(crate) fn inside_citation(before: &str) -> bool {
+    static OPEN: OnceLock<Regex> = OnceLock::new();
+    let open = OPEN.get_or_init(|| {
+        Regex::new(&format!(r"{}$", CITATION_START)).expect("valid citation pattern")
+    });
+    open.is_match(before.trim_end())
+}
+
+/// Words after which a Roman numeral numbers a heading.
+const NUMBERED_HEADINGS: [&str; 24] = [
+    "chapter",
+    "part",
+    "book",
+    "section",
+    "volume",
+    "vol",
+    "act",
+    "scene",
+    "article",
+    "appendix",
+    "annex",
+    "phase",
+    "stage",
+    "kapitel",
+    "teil",
+    "band",
+    "abschnitt",
+    "chapitre",
+    "partie",
+    "tome",
+    "capítulo",
+    "parte",
+    "libro",
+    "capitolo",
+];
+
+/// The last word of `before` (which ends at a candidate boundary) and the word before it,
+/// without leading brackets or quotes.
+fn last_words(before: &str) -> (Option<&str>, &str) {
+    fn strip(word: &str) -> &str {
+        word.trim_start_matches(|c: char| !c.is_alphanumeric())
+    }
+    let mut words = before.split_whitespace().rev();
+    let last = words.next().map(strip).unwrap_or_default();
+    (words.next().map(strip), last)
+}
+
+/// `J.`, `R.` etc.
+fn is_initial(word: &str) -> bool {
+    let mut chars = word.chars();
+    matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some(c), Some('.'), None) if c.is_uppercase()
+    )
+}
+
+/// Whether `word` starts with a capital letter, ignoring leading quotes and brackets.
+fn is_capitalized(word: &str) -> bool {
+    word.chars()
+        .find(|c| c.is_alphanumeric())
+        .is_some_and(char::is_uppercase)
+}
+
+/// Whether the period ending `before` follows a personal initial that, under `strictness`,
+/// should not end the sentence; `after` is the text following the candidate boundary.
+pub(crate) fn protects_initial(before: &str, after: &str, strictness: Strictness) -> bool {
+    let (previous, last) = last_words(before);
+    if strictness == Strictness::Off || !is_initial(last) {
+        return false;
+    }
+    let next = after.split_whitespace().next().unwrap_or_default();
+    let chained = is_initial(next) || previous.is_some_and(is_initial);
+    match strictness {
+        Strictness::Off => false,
+        Strictness::Conservative => chained || (previous.is_none() && is_capitalized(next)),
+        Strictness::Aggressive => chained || is_capitalized(next),
+    }
+}
+
+/// Whether the period ending `before` follows a Roman numeral that, under `strictness`, should
+/// not end the sentence.
+pub(crate) fn protects_roman_numeral(before: &str, strictness: Strictness) -> bool {
+    let (previous, last) = last_words(before);
+    let Some(numeral) = last.strip_suffix('.') else {
+        return false;
+    };
+    if strictness == Strictness::Off || !is_roman_numeral(numeral) {
+        return false;
+    }
+    let heading = previous.is_some_and(|word| {
+        let word = word.trim_end_matches('.').to_lowercase();
+        NUMBERED_HEADINGS.contains(&word.as_str())
+    });
+    match strictness {
+        Strictness::Off => false,
+        Strictness::Conservative => heading,
+        Strictness::Aggressive => heading || numeral != "I",
+    }
+}
+
+/// An upper-case Roman numeral such as `IV` or `XLII`.
+fn is_roman_numeral(word: &str) -> bool {
+    static ROMAN: OnceLock<Regex> = OnceLock::new();
+    let roman = ROMAN.get_or_init(|| {
+        Regex::new(r"^M{0,4}(?:CM|CD|D?C{0,3})(?:XC|XL|L?X{0,3})(?:IX|IV|V?I{0,3})$")
+            .expect("valid Roman numeral pattern")
+    });
+    !word.is_empty() && roman.is_match(word)
+}
+
+/// Built-in patterns for times and dates, by language. Month abbreviations only protect the
+/// period when a day number follows.
+fn date_and_time_patterns(language: &str) -> &'static [&'static str] {
+    match language {
+        "en" => &[
+            r"\b\d{1,2}(?::\d{2})?\s?[ap]\.\s?m\.",
+            r"\b(?:Jan|Feb|Mar|Apr|Jun|Jul|Aug|Sep|Sept|Oct|Nov|Dec)\.\s+\d{1,2}\b",
+            r"\bNos?\.\s+\d",
+        ],
+        "de" => &[
+            r"\b(?:Jan|Feb|Febr|Mär|Apr|Jun|Jul|Aug|Sep|Sept|Okt|Nov|Dez)\.\s+\d{1,2}\b",
+            r"\bNr\.\s+\d",
+        ],
+        "fr" => &[
+            r"\b(?:janv|févr|avr|juil|sept|oct|nov|déc)\.\s+\d{1,2}\b",
+            r"\b[Nn]o\.\s+\d",
+        ],
+        "es" => &[
+            r"\b\d{1,2}(?::\d{2})?\s?[ap]\.\s?m\.",
+            r"\b(?:ene|feb|abr|jun|jul|ago|sept?|oct|nov|dic)\.\s+\d{1,2}\b",
+            r"\b[Nn]úm\.\s+\d",
+        ],
+        _ => &[r"\b\d{1,2}(?::\d{2})?\s?[ap]\.\s?m\."],
+    }
+}
+
+/// A lexicon of abbreviated units and quantities, to be enabled for corpora of that kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum MeasurementDomain {
+    /// `approx.`, `ca.`, `Nr.`, currency amounts and common units of weight, length and time.
+    General,
+    /// `tsp.`, `tbsp.`, `oz.`, `lb.`, `pt.`, `qt.` and the like in recipes.
+    Cooking,
+    /// `Fig. 3`, `Eq. 2`, `resp.` and units such as `mol.` or `kHz.`.
+    Science,
+    /// `Mio. Euro`, `bn. dollars`, `Rs. 500` and similar amounts.
+    Finance,
+}
+
+impl MeasurementDomain {
+    fn patterns(self) -> &'static [&'static str] {
+        match self {
+            MeasurementDomain::General => &[
+                r"\b(?:approx|appr|ca|env|aprox|est)\.\s+[\d$€£¥]",
+                r"\b(?:Nr|Nrn|nr)\.\s+\d",
+                r"[$€£¥]\s?\d+(?:[.,]\d+)+",
+                r"\b\d+(?:[.,]\d+)?\s?(?:kg|mg|km|cm|mm|ml|ft|in|mi|yd|gal|lbs?|hrs?|mins?|secs?)\.\s+\p{Ll}",
+            ],
+            MeasurementDomain::Cooking => &[
+                r"\b(?:tsp|tbsp|tbs|oz|lbs?|pt|qt|pkg|doz|EL|TL|Msp|Pck)\.\s+[\p{Ll}\d]",
+                r"\b\d+(?:[.,/]\d+)?\s?c\.\s+\p{Ll}",
+            ],
+            MeasurementDomain::Science => &[
+                r"\b(?:Fig|Figs|Eq|Eqs|Tab|Ref|Refs|Abb|Gl)\.\s+\(?\d",
+                r"\b(?:resp|cf|approx|vs)\.\s+\p{Ll}",
+                r"\b\d+(?:[.,]\d+)?\s?(?:mol|mmol|Hz|kHz|MHz|GHz|mA|mV|kV|kW|cal|kcal|atm|Pa|kPa)\.\s+\p{Ll}",
+            ],
+            MeasurementDomain::Finance => &[
+                r"\b\d+(?:[.,]\d+)?\s?(?:Mio|Mrd|Tsd|Bio|bn|mn|mln|mld)\.\s+[\p{L}$€£¥]",
+                r"\b(?:Rs|Fr|SFr|Kč|zł)\.\s*\d",
+            ],
+        }
+    }
+}
+
+/// Month names, in the form they take after a day number, for languages that write ordinals
+/// with a period; `None` for the others.
+fn ordinal_months(language: &str) -> Option<&'static str> {
+    Some(match language {
+        "de" => "Jan|Januar|Jänner|Feb|Febr|Februar|Mär|März|Apr|April|Mai|Jun|Juni|Jul|Juli|Aug|August|Sep|Sept|September|Okt|Oktober|Nov|November|Dez|Dezember",
+        "da" | "no" | "nb" | "nn" => "januar|februar|marts|mars|april|maj|mai|juni|juli|august|september|oktober|november|december|desember",
+        "fi" => "tammikuuta|helmikuuta|maaliskuuta|huhtikuuta|toukokuuta|kesäkuuta|heinäkuuta|elokuuta|syyskuuta|lokakuuta|marraskuuta|joulukuuta",
+        "cs" => "ledna|února|března|dubna|května|června|července|srpna|září|října|listopadu|prosince",
+        "sk" => "januára|februára|marca|apríla|mája|júna|júla|augusta|septembra|októbra|novembra|decembra",
+        "pl" => "stycznia|lutego|marca|kwietnia|maja|czerwca|lipca|sierpnia|września|października|listopada|grudnia",
+        "sl" => "januarja|februarja|marca|aprila|maja|junija|julija|avgusta|septembra|oktobra|novembra|decembra",
+        "hr" => "siječnja|veljače|ožujka|travnja|svibnja|lipnja|srpnja|kolovoza|rujna|listopada|studenoga|prosinca",
+        "is" => "janúar|febrúar|mars|apríl|maí|júní|júlí|ágúst|september|október|nóvember|desember",
+        _ => return None,
+    })
+}
+
+/// Patterns for ordinal numbers written with a period. `Conservative` covers day numbers
+/// before a month (`3. Mai`) and numeric dates (`3. 5. 2024`); `Aggressive` any number of up
+/// to three digits followed by a word (`der 2. Platz`, `§ 3. Absatz`), which suits legal text
+/// but also keeps `Es waren 3. Dann` together.
+fn ordinal_patterns(language: &str, strictness: Option<Strictness>) -> Vec<String> {
+    let months = ordinal_months(language);
+    let default = match months {
+        Some(_) => Strictness::Conservative,
+        None => Strictness::Off,
+    };
+    let strictness = strictness.unwrap_or(default);
+    if strictness == Strictness::Off {
+        return Vec::new();
+    }
+    let mut patterns =
+        vec![r"\b(?:0?[1-9]|[12]\d|3[01])\.\s?(?:0?[1-9]|1[0-2])\.\s?\d".to_string()];
+    if let Some(months) = months {
+        patterns.push(format!(r"\b(?:0?[1-9]|[12]\d|3[01])\.\s+(?:{})\b", months));
+    }
+    if strictness == Strictness::Aggressive {
+        patterns.push(r"\b\d{1,3}\.\s+\p{L}".to_string());
+    }
+    patterns
+}
+
+/// Compiles the span patterns that apply to `language` under `protections`.
+pub(crate) fn span_patterns(protections: &Protections, language: &str) -> Result<Vec<Regex>> {
+    let language = crate::scripts::primary_subtag(language);
+    let ordinals = ordinal_patterns(language, protections.ordinal_dates);
+    let builtin = if protections.dates_and_times {
+        date_and_time_patterns(language)
+    } else {
+        &[]
+    };
+    let measurements = protections
+        .measurements
+        .iter()
+        .flat_map(|domain| domain.patterns());
+    builtin
+        .iter()
+        .chain(measurements)
+        .copied()
+        .chain(ordinals.iter().map(String::as_str))
+        .chain(protections.patterns.iter().map(String::as_str))
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| {
+                SegmenterError::InvalidOptions(format!("protected pattern {:?}: {}", pattern, e))
+            })
+        })
+        .collect()
+}
+
+/// Whether one of `patterns` matches a span of `text` that runs across `position`, the end of
+/// the sentence punctuation of a candidate boundary.
+pub(crate) fn inside_span(patterns: &[Regex], text: &str, position: usize) -> bool {
+    // Protected spans are short; don't rescan the whole buffer for every candidate
+    let mut start = position.saturating_sub(64);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    patterns.iter().any(|pattern| {
+        pattern
+            .find_iter(&text[start..])
+            .take_while(|m| start + m.start() < position)
+            .any(|m| start + m.end() > position)
+    })
+}
+
+/// Reads the patterns for `language` from a rules file: one regex per line; lines starting with
+/// `#` and blank lines are ignored. Patterns before the first `[language]` header apply to every
+/// language, the ones after a header only to that language (`[de]` also covers `de-AT`).
+pub fn read_rules_file(path: impl AsRef<Path>, language: &str) -> Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path)?;
+    let language = crate::scripts::primary_subtag(language);
+    let mut applies = true;
+    let mut patterns = Vec::new();
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let section = line
+            .strip_prefix('[')
+            .and_then(|l| l.strip_suffix(']'))
+            .filter(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphabetic() || c == '-'));
+        if let Some(section) = section {
+            applies = section.eq_ignore_ascii_case(language);
+        } else if applies {
+            patterns.push(line.to_string());
+        }
+    }
+    Ok(patterns)
+}
@@ -0,0 +1,77 @@
+//! Python bindings, built with `maturin` and the `python` feature.
+//!
+//! The native module is `async_tqsm._native`; `python/async_tqsm/__init__.py` re-exports it and
+//! adds the `asentences` async-iterator wrapper.
+
+use crate::config::SegmentOptions;
+use crate::error::SegmenterError;
+use crate::segmenter::{segment_document, Segmenter};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+fn to_py_err(e: SegmenterError) -> PyErr {
+    match e {
+        SegmenterError::UnsupportedLanguage(_) | SegmenterError::Utf8Error { .. } => {
+            PyValueError::new_err(e.to_string())
+        }
+        _ => PyRuntimeError::new_err(e.to_string()),
+    }
+}
+
+fn options(language: &str, lookahead: usize, max_buffer: usize) -> SegmentOptions {
+    SegmentOptions {
+        language: language.to_string(),
+        lookahead,
+        max_buffer,
+        ..Default::default()
+    }
+}
+
+/// Streaming sentence segmenter: `feed()` text chunks, `flush()` at the end.
+#[pyclass(name = "Segmenter", module = "async_tqsm")]
+pub(crate) struct PySegmenter {
+    inner: Segmenter,
+}
+
+#[pymethods]
+impl PySegmenter {
+    #[new]
+    #[pyo3(signature = (language = "en", lookahead = 10, max_buffer = 8192))]
+    pub(crate) fn new(language: &str, lookahead: usize, max_buffer: usize) -> PyResult<Self> {
+        let inner = Segmenter::new(options(language, lookahead, max_buffer)).map_err(to_py_err)?;
+        Ok(Self { inner })
+    }
+
+    /// Appends a chunk and returns the sentences it completed.
+    pub(crate) fn feed(&mut self, chunk: &str) -> PyResult<Vec<String>> {
+        self.inner.feed(chunk).map_err(to_py_err)
+    }
+
+    /// Ends the input and returns the remaining sentences, including those still held back
+    /// for lookahead and the unterminated tail.
+    pub(crate) fn flush(&mut self) -> PyResult<Vec<String>> {
+        self.inner.finish().map_err(to_py_err)
+    }
+}
+
+/// Segments a complete string and returns its sentences.
+#[pyfunction]
+#[pyo3(signature = (text, language = "en", lookahead = 10, max_buffer = 8192))]
+fn segment_str(
+    text: &str,
+    language: &str,
+    lookahead: usize,
+    max_buffer: usize,
+) -> PyResult<Vec<String>> {
+    let segments =
+        segment_document(text, options(language, lookahead, max_buffer)).map_err(to_py_err)?;
+    Ok(segments.into_iter().map(|s| s.text).collect())
+}
+
+#[pymodule]
+#[pyo3(name = "_native")]
+fn native(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySegmenter>()?;
+    m.add_function(wrap_pyfunction!(segment_str, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Quote and bracket nesting carried across chunks, so a boundary inside a quotation is not
+//! accepted just because the closing quote has not been read yet.
+//!
+//! The language rules only see the buffered text; once the opening half of a quotation has been
+//! emitted or the closing half is still unread, they cannot tell that a period sits inside it.
+//!
+//! Quotes and brackets nest (quotes within parentheses within quotes) up to
+//! [`SegmentOptions::max_quote_depth`](crate::SegmentOptions::max_quote_depth); a closing
+//! character that does not match the innermost opener is handled by a [`QuoteRecovery`].
+
+/// Opening characters and the character that closes each.
+const PAIRS: [(char, char); 18] = [
+    ('"', '"'),
+    ('“', '”'),
+    ('„', '“'),
+    ('‘', '’'),
+    ('«', '»'),
+    ('‹', '›'),
+    ('「', '」'),
+    ('『', '』'),
+    ('（', '）'),
+    ('【', '】'),
+    ('〔', '〕'),
+    ('《', '》'),
+    ('〈', '〉'),
+    ('〖', '〗'),
+    ('〘', '〙'),
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+];
+
+/// What to do with a closing quote or bracket that does not match the innermost open one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum QuoteRecovery {
+    /// Ignore it; right for apostrophes such as the `’` in `don’t`.
+    #[default]
+    Ignore,
+    /// Close the nearest enclosing opener it matches, together with everything opened inside
+    /// it, as if those had been closed without their closing character.
+    CloseToMatch,
+    /// Consider every quote and bracket closed.
+    Reset,
+}
+
+/// The quotes and brackets open at some point of the stream, innermost last.
+#[derive(Debug, Clone)]
+pub(crate) struct QuoteState {
+    /// Expected closing character and stream offset of each opener.
+    open: Vec<(char, usize)>,
+    /// Openers nested deeper than this are not tracked.
+    max_depth: usize,
+    recovery: QuoteRecovery,
+}
+
+impl QuoteState {
+    pub(crate) fn new(max_depth: usize, recovery: QuoteRecovery) -> Self {
+        Self {
+            open: Vec::new(),
+            max_depth,
+            recovery,
+        }
+    }
+
+    /// Updates the state with `text`, which starts at stream offset `offset`.
+    pub(crate) fn scan(&mut self, text: &str, offset: usize) {
+        for (i, c) in text.char_indices() {
+            if self.open.last().is_some_and(|(close, _)| *close == c) {
+                self.open.pop();
+            } else if let Some((_, close)) = PAIRS.iter().find(|(open, _)| *open == c) {
+                if self.open.len() < self.max_depth {
+                    self.open.push((*close, offset + i));
+                }
+            } else if PAIRS.iter().any(|(_, close)| *close == c) {
+                self.recover(c);
+            }
+        }
+    }
+
+    /// Handles the closing character `c`, which does not match the innermost opener.
+    fn recover(&mut self, c: char) {
+        match self.recovery {
+            QuoteRecovery::Ignore => {}
+            QuoteRecovery::CloseToMatch => {
+                if let Some(position) = self.open.iter().rposition(|(close, _)| *close == c) {
+                    self.open.truncate(position);
+                }
+            }
+            QuoteRecovery::Reset => self.open.clear(),
+        }
+    }
+
+    /// Stream offset of the outermost opener that is still open.
+    pub(crate) fn outermost(&self) -> Option<usize> {
+        self.open.first().map(|(_, offset)| *offset)
+    }
+
+    /// Forgets openers before stream offset `offset`, treating them as never closed.
+    pub(crate) fn expire(&mut self, offset: usize) {
+        self.open.retain(|(_, opened)| *opened >= offset);
+    }
+}
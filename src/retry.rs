@@ -0,0 +1,63 @@
+//! Retrying reads that failed with a transient I/O error instead of ending the stream.
+
+use std::io::ErrorKind;
+use std::time::Duration;
+
+/// Which read errors the sentence streams retry, and how often.
+///
+/// `Interrupted` is always retryable; `WouldBlock` and `TimedOut` only when enabled. The count
+/// applies to consecutive failures and resets after every successful read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Consecutive retries before the error is passed on; 0 disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one (tokio readers only; the
+    /// `futures-io` reader retries immediately).
+    pub backoff: Duration,
+    /// Also retry `ErrorKind::WouldBlock`.
+    pub retry_would_block: bool,
+    /// Also retry `ErrorKind::TimedOut`.
+    pub retry_timed_out: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: Duration::from_millis(10),
+            retry_would_block: false,
+            retry_timed_out: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Whether an error of `kind` is worth another attempt.
+    pub fn is_retryable(&self, kind: ErrorKind) -> bool {
+        match kind {
+            ErrorKind::Interrupted => true,
+            ErrorKind::WouldBlock => self.retry_would_block,
+            ErrorKind::TimedOut => self.retry_timed_out,
+            _ => false,
+        }
+    }
+
+    /// Whether to retry after the `attempt`-th consecutive failure (starting at 1) of `kind`.
+    pub fn should_retry(&self, kind: ErrorKind, attempt: u32) -> bool {
+        attempt <= self.max_retries && self.is_retryable(kind)
+    }
+
+    /// Delay before retry number `attempt` (starting at 1).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
@@ -0,0 +1,179 @@
+//! Export of the effective segmentation rules, so the exact behaviour used for a dataset can be
+//! audited and shared.
+//!
+//! Only the declarative part of a language's rules can be exported: the sentence-break pattern,
+//! quote handling and the abbreviation list. The context checks libtqsm runs around each
+//! candidate boundary are code, not data, and have no SRX equivalent.
+//!
+//! [`CustomBoundary`] rules added through
+//! [`SegmentOptions::custom_boundaries`](crate::SegmentOptions::custom_boundaries) are part of
+//! the rule set and exported with it.
+
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use regex::Regex;
+use std::collections::BTreeSet;
+
+/// What a [`CustomBoundary`] does where its pattern matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum BoundaryAction {
+    /// A sentence ends after the match, e.g. for ` • ` or ` | ` used as a terminator.
+    Split,
+    /// A sentence starts at the match, e.g. for bullets or chat-log prefixes such as
+    /// `[12:00] <alice>`.
+    SplitBefore,
+    /// No sentence ends inside the match or right after it, e.g. for `\bapprox\.`.
+    NoSplit,
+}
+
+/// A user-defined boundary rule, applied after the language's rules: split rules add
+/// boundaries where the language finds none earlier, no-split rules veto the language's
+/// boundaries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CustomBoundary {
+    /// Regex the rule applies at.
+    pub pattern: String,
+    pub action: BoundaryAction,
+}
+
+impl CustomBoundary {
+    pub fn new(pattern: impl Into<String>, action: BoundaryAction) -> Self {
+        Self {
+            pattern: pattern.into(),
+            action,
+        }
+    }
+
+    pub(crate) fn compile(&self) -> Result<Regex> {
+        Regex::new(&self.pattern).map_err(|e| {
+            SegmenterError::InvalidOptions(format!(
+                "custom boundary pattern {:?}: {}",
+                self.pattern, e
+            ))
+        })
+    }
+}
+
+/// The rules a [`Segmenter`](crate::Segmenter) built from the same options applies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuleSet {
+    pub language: String,
+    /// Regex matching candidate sentence-final punctuation.
+    pub break_pattern: String,
+    /// Whether punctuation closing a quoted span may end a sentence.
+    pub punctuation_between_quotes: bool,
+    /// Words whose trailing period never ends a sentence, without the period: the built-in
+    /// list of the language and the extra and shared abbreviations, less the removed ones.
+    /// libtqsm's own lists are part of its code and not included.
+    pub abbreviations: Vec<String>,
+    /// Characters of context required after a boundary before it is emitted.
+    pub lookahead: usize,
+    /// User-defined rules applied after the language's.
+    pub custom_boundaries: Vec<CustomBoundary>,
+}
+
+impl RuleSet {
+    /// Collects the rules for `options.language`, or for its `fallback_language`.
+    pub fn for_options(options: &SegmentOptions) -> Result<Self> {
+        let mut options = options.clone();
+        let language = crate::scripts::resolve_language(&mut options)?;
+
+        Ok(Self {
+            language: options.language.clone(),
+            break_pattern: language.sentence_break_regex().as_str().to_string(),
+            punctuation_between_quotes: language.is_punctuation_between_quotes(),
+            abbreviations: effective_abbreviations(&options),
+            lookahead: options.lookahead,
+            custom_boundaries: options.custom_boundaries.clone(),
+        })
+    }
+
+    /// Serializes the rules as an SRX 2.0 document with a single language rule.
+    pub fn to_srx(&self) -> String {
+        let name = xml_escape(&self.language);
+        let mut srx = String::new();
+        srx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        srx.push_str("<srx xmlns=\"http://www.lisa.org/srx20\" version=\"2.0\">\n");
+        srx.push_str("  <header segmentsubflows=\"yes\" cascade=\"no\"/>\n");
+        srx.push_str("  <body>\n    <languagerules>\n");
+        srx.push_str(&format!(
+            "      <languagerule languagerulename=\"{name}\">\n"
+        ));
+        // SRX applies the first matching rule, so exceptions come before the break rule
+        for abbreviation in &self.abbreviations {
+            srx.push_str(&srx_rule(
+                false,
+                &format!(
+                    r"\b{}\.",
+                    regex::escape(abbreviation.strip_suffix('.').unwrap_or(abbreviation))
+                ),
+                r"\s",
+            ));
+        }
+        let (no_splits, splits): (Vec<_>, Vec<_>) = self
+            .custom_boundaries
+            .iter()
+            .partition(|rule| rule.action == BoundaryAction::NoSplit);
+        for rule in no_splits {
+            srx.push_str(&srx_rule(false, &rule.pattern, ""));
+        }
+        srx.push_str(&srx_rule(true, &self.break_pattern, r"\s"));
+        for rule in splits {
+            srx.push_str(&match rule.action {
+                BoundaryAction::SplitBefore => srx_rule(true, "", &rule.pattern),
+                _ => srx_rule(true, &rule.pattern, ""),
+            });
+        }
+        srx.push_str("      </languagerule>\n    </languagerules>\n");
+        srx.push_str("    <maprules>\n");
+        srx.push_str(&format!(
+            "      <languagemap languagepattern=\"{}.*\" languagerulename=\"{name}\"/>\n",
+            xml_escape(&regex::escape(&self.language))
+        ));
+        srx.push_str("    </maprules>\n  </body>\n</srx>\n");
+        srx
+    }
+}
+
+/// The abbreviations of `options`, sorted and without their periods.
+fn effective_abbreviations(options: &SegmentOptions) -> Vec<String> {
+    let strip = |word: &str| word.strip_suffix('.').unwrap_or(word).to_string();
+    let removed: BTreeSet<String> = options
+        .removed_abbreviations
+        .iter()
+        .map(|word| strip(word))
+        .collect();
+    let mut words: BTreeSet<String> = crate::scripts::abbreviations(&options.language)
+        .iter()
+        .map(|word| strip(word))
+        .collect();
+    words.extend(options.extra_abbreviations.iter().map(|word| strip(word)));
+    if let Some(shared) = &options.shared_abbreviations {
+        words.extend(shared.current().iter().map(|word| strip(word)));
+    }
+    words.retain(|word| !removed.contains(word));
+    words.into_iter().collect()
+}
+
+fn srx_rule(is_break: bool, before: &str, after: &str) -> String {
+    format!(
+        "        <rule break=\"{}\">\n          <beforebreak>{}</beforebreak>\n          <afterbreak>{}</afterbreak>\n        </rule>\n",
+        if is_break { "yes" } else { "no" },
+        xml_escape(before),
+        xml_escape(after)
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
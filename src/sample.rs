@@ -0,0 +1,50 @@
+//! Reproducible random subsets of a corpus, e.g. to draw an evaluation sample in the same pass
+//! as segmentation.
+//!
+//! Whether a sentence is kept depends only on the seed and the sentence's identifier, not on a
+//! random number generator's position, so the same seed selects the same sentences even when
+//! files are segmented in parallel and finish in a different order.
+
+use crate::error::{Result, SegmenterError};
+use crate::ids::content_hash;
+
+/// Keeps each sentence with a fixed probability.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sampler {
+    rate: f64,
+    seed: u64,
+}
+
+impl Sampler {
+    /// Keeps a share of `rate` (between 0 and 1) of the sentences; a different `seed` selects a
+    /// different, independent subset. Fails with [`SegmenterError::InvalidOptions`] if `rate` is
+    /// NaN or infinite.
+    pub fn new(rate: f64, seed: u64) -> Result<Self> {
+        if !rate.is_finite() {
+            return Err(SegmenterError::InvalidOptions(format!(
+                "sample rate must be a number between 0 and 1, got {}",
+                rate
+            )));
+        }
+        Ok(Self {
+            rate: rate.clamp(0.0, 1.0),
+            seed,
+        })
+    }
+
+    /// Whether the sentence identified by `id` (e.g. a [`sentence_id`](crate::ids::sentence_id))
+    /// belongs to the sample.
+    pub fn keep(&self, id: &str) -> bool {
+        // 53 random bits, uniform in [0, 1)
+        let draw = (mix(self.seed ^ content_hash(id)) >> 11) as f64 / (1u64 << 53) as f64;
+        draw < self.rate
+    }
+}
+
+/// SplitMix64 finalizer: spreads every input bit over the whole output.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
@@ -0,0 +1,361 @@
+//! Handling for scripts whose punctuation the language rules, written for space-separated
+//! text, get wrong: CJK text puts no space after a sentence, so a full-width terminator ends
+//! one even when the next sentence follows directly.
+//!
+//! Thai and Lao mostly end sentences without punctuation; a space between two words of the
+//! script separates phrases and sentences. Spaces are taken as boundaries unless a small
+//! dictionary of conjunctions and particles shows the sentence goes on. Khmer ends sentences
+//! with `។` or `៕`. Languages without a `libtqsm` rule set of their own (`th`, `lo`, `km`) use
+//! the English rules for any Latin punctuation in the text.
+//!
+//! Hindi, Marathi, Nepali and the other languages written in Devanagari or Bengali script end
+//! sentences with a danda (`।`, or `॥` in verse); a short list of their period abbreviations
+//! (`डॉ.`, `प्रो.`) keeps those from ending one.
+//!
+//! Ethiopic scripts end sentences with `።` (or two word separators, `፡፡`) and `፧`, Armenian
+//! with `։`, Myanmar with `။`, and Greek questions with `;` (or U+037E).
+//!
+//! With [`SegmentOptions::mixed_scripts`](crate::SegmentOptions::mixed_scripts), these rules
+//! also apply to runs of their script inside text of another language, e.g. a Thai or Hindi
+//! quotation in an English post.
+//!
+//! Urdu ends sentences with `۔` (and `؟`), Persian and Pashto with `.` or `؟`; they fall back to
+//! the Arabic rules otherwise. In right-to-left text a terminator is often followed by a
+//! directional mark (U+200F and friends) before the space, which does not prevent a boundary.
+//! Marks are never trimmed from sentences, and offsets and columns count them like any other
+//! character.
+
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use crate::warnings::WarningKind;
+use libtqsm::{get_language, Language};
+
+/// Sentence terminators of scripts that do not separate sentences with spaces.
+const UNSPACED_TERMINATORS: [char; 4] = ['。', '！', '？', '．'];
+
+/// Closing quotes and brackets that stay with the sentence they end.
+const CLOSERS: [char; 15] = [
+    '」', '』', '）', '】', '〕', '》', '〉', '〗', '〙', '”', '’', '"', '\'', ')', '»',
+];
+
+/// The primary subtag of a language code, e.g. `zh` for `zh-Hant`.
+pub(crate) fn primary_subtag(language: &str) -> &str {
+    language.split(['-', '_']).next().unwrap_or_default()
+}
+
+/// Whether `language` is written in Chinese, Japanese or Korean script.
+pub fn is_cjk(language: &str) -> bool {
+    matches!(primary_subtag(language), "zh" | "ja" | "ko" | "yue" | "wuu")
+}
+
+/// Whether `language` is Thai, Lao or Khmer, whose sentence boundaries come from
+/// [`script_boundary`] rather than punctuation.
+pub fn is_southeast_asian(language: &str) -> bool {
+    matches!(primary_subtag(language), "th" | "lo" | "km")
+}
+
+/// The `libtqsm` rules for `language`, falling back to a related rule set for the languages
+/// handled by [`script_boundary`].
+pub(crate) fn language_rules(language: &str) -> Option<&'static (dyn Language + Send + Sync)> {
+    get_language(language).or_else(|| {
+        let fallback = match primary_subtag(language) {
+            "th" | "lo" | "km" => "en",
+            "ur" | "fa" | "ps" | "sd" => "ar",
+            "mr" | "ne" | "sa" | "bn" | "as" | "mai" => "hi",
+            "am" | "ti" | "hy" | "my" | "el" => "en",
+            _ => return None,
+        };
+        get_language(fallback)
+    })
+}
+
+/// The rules for `options.language`; for a language without any, the rules of
+/// `options.fallback_language`, which then replaces `options.language` and is reported as a
+/// [`WarningKind::LanguageFallback`](crate::warnings::WarningKind::LanguageFallback).
+pub(crate) fn resolve_language(
+    options: &mut SegmentOptions,
+) -> Result<&'static (dyn Language + Send + Sync)> {
+    if let Some(rules) = language_rules(&options.language) {
+        return Ok(rules);
+    }
+    let Some(fallback) = options.fallback_language.clone() else {
+        return Err(SegmenterError::UnsupportedLanguage(
+            options.language.clone(),
+        ));
+    };
+    let rules = language_rules(&fallback)
+        .ok_or_else(|| SegmenterError::UnsupportedLanguage(fallback.clone()))?;
+    #[cfg(feature = "tracing")]
+    tracing::warn!(language = %options.language, fallback = %fallback, "language not supported");
+    if let Some(warnings) = &options.warnings {
+        warnings.report(
+            WarningKind::LanguageFallback,
+            0,
+            format!(
+                "no rules for language '{}', using '{}'",
+                options.language, fallback
+            ),
+        );
+    }
+    options.language = fallback;
+    Ok(rules)
+}
+
+/// Lookahead suited to `language`: full-width terminators are unambiguous and followed by no
+/// space, so CJK text only needs to see the next character (at most 3 bytes in UTF-8) to rule
+/// out a closing bracket, rather than the default 10 bytes of context abbreviations need.
+/// Thai and Lao need the whole next word (up to 8 characters of 3 bytes) to check it against
+/// the dictionary.
+pub fn default_lookahead(language: &str) -> usize {
+    if is_cjk(language) {
+        3
+    } else if is_southeast_asian(language) {
+        24
+    } else {
+        10
+    }
+}
+
+/// End of a boundary after a full-width terminator in `text[..punctuation_end]`, including any
+/// closing quotes or brackets and whitespace after it. `None` if the punctuation does not end
+/// in such a terminator.
+pub(crate) fn unspaced_boundary(text: &str, punctuation_end: usize) -> Option<usize> {
+    let punctuation = text[..punctuation_end].trim_end_matches(CLOSERS);
+    if !punctuation.ends_with(UNSPACED_TERMINATORS) {
+        return None;
+    }
+    let rest = &text[punctuation_end..];
+    let after_closers = rest.trim_start_matches(CLOSERS);
+    let after_space = after_closers.trim_start();
+    Some(text.len() - after_space.len())
+}
+
+/// Words that continue a sentence when they follow a space: conjunctions, relative pronouns
+/// and prepositions.
+const THAI_CONTINUATIONS: [&str; 17] = [
+    "และ",
+    "หรือ",
+    "แต่",
+    "ซึ่ง",
+    "ที่",
+    "ว่า",
+    "เพราะ",
+    "ก็",
+    "จึง",
+    "โดย",
+    "เช่น",
+    "คือ",
+    "กับ",
+    "ของ",
+    "ใน",
+    "จาก",
+    "เพื่อ",
+];
+
+const LAO_CONTINUATIONS: [&str; 14] = [
+    "ແລະ",
+    "ຫຼື",
+    "ແຕ່",
+    "ຊຶ່ງ",
+    "ທີ່",
+    "ວ່າ",
+    "ເພາະ",
+    "ກໍ",
+    "ຈຶ່ງ",
+    "ໂດຍ",
+    "ເຊັ່ນ",
+    "ຄື",
+    "ກັບ",
+    "ຂອງ",
+];
+
+/// Abbreviations (without their period) that never end a sentence in `language`, on top of
+/// its `libtqsm` rules.
+pub(crate) fn abbreviations(language: &str) -> &'static [&'static str] {
+    match primary_subtag(language) {
+        "hi" | "mr" | "ne" | "sa" | "mai" => &[
+            "डॉ",
+            "डा",
+            "प्रो",
+            "श्री",
+            "श्रीमती",
+            "सुश्री",
+            "मि",
+            "कु",
+            "सं",
+            "पृ",
+            "ई",
+            "ई.पू",
+            "पू",
+            "क्र",
+            "रु",
+            "लि",
+            "प्रा",
+            "वि",
+        ],
+        "bn" | "as" => &["ডা", "প্রফ", "শ্রী", "মি", "পৃ", "খ্রি", "সং"],
+        _ => &[],
+    }
+}
+
+/// Sentence terminators of Khmer.
+const KHMER_TERMINATORS: [char; 2] = ['។', '៕'];
+
+/// End of the first boundary the script rules of `language` find in `text`, including the
+/// whitespace after it; `None` for languages without script rules.
+pub(crate) fn script_boundary(language: &str, text: &str) -> Option<usize> {
+    match primary_subtag(language) {
+        "th" => space_boundary(text, is_thai, &THAI_CONTINUATIONS),
+        "lo" => space_boundary(text, is_lao, &LAO_CONTINUATIONS),
+        "km" => terminator_boundary(text, &KHMER_TERMINATORS, false),
+        "hi" | "mr" | "ne" | "sa" | "bn" | "as" | "mai" => {
+            terminator_boundary(text, &['।', '॥'], false)
+        }
+        "ur" | "sd" => terminator_boundary(text, &['۔', '؟', '!'], true),
+        "am" | "ti" => {
+            let double_separator = text.find("፡፡").map(|position| {
+                let after = text[position..].trim_start_matches('፡');
+                boundary_after(text, text.len() - after.len())
+            });
+            match (
+                terminator_boundary(text, &['።', '፧', '፨'], false),
+                double_separator,
+            ) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        "hy" => terminator_boundary(text, &['։'], false),
+        "my" => terminator_boundary(text, &['။'], false),
+        "el" => terminator_boundary(text, &[';', '\u{037E}'], true),
+        "fa" | "ps" => terminator_boundary(text, &['.', '۔', '؟', '!'], true),
+        _ => None,
+    }
+}
+
+/// End of the first run of `terminators` in `text`, with the closing quotes, directional marks
+/// and whitespace after it. With `needs_space`, a run that is not followed by whitespace (as in
+/// `3.5`) is skipped.
+fn terminator_boundary(text: &str, terminators: &[char], needs_space: bool) -> Option<usize> {
+    let mut search = 0;
+    while let Some(found) = text[search..].find(terminators) {
+        let after = text[search + found..].trim_start_matches(terminators);
+        let punctuation_end = text.len() - after.len();
+        let end = boundary_after(text, punctuation_end);
+        if !needs_space || text[..end].ends_with(char::is_whitespace) {
+            return Some(end);
+        }
+        search = punctuation_end;
+    }
+    None
+}
+
+/// End of a boundary after the punctuation ending at `punctuation_end`: past the closing
+/// quotes, directional marks and whitespace that follow it.
+fn boundary_after(text: &str, punctuation_end: usize) -> usize {
+    let after = text[punctuation_end..]
+        .trim_start_matches(|c| CLOSERS.contains(&c) || BIDI_MARKS.contains(&c))
+        .trim_start();
+    text.len() - after.len()
+}
+
+/// Directional marks and embedding controls that may sit between a terminator and the space
+/// after it in bidirectional text.
+const BIDI_MARKS: [char; 12] = [
+    '\u{200E}', '\u{200F}', '\u{061C}', '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}',
+    '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+/// End of a boundary after the punctuation ending at `punctuation_end` when only directional
+/// marks separate it from the following whitespace, including that whitespace.
+pub(crate) fn bidi_boundary(text: &str, punctuation_end: usize) -> Option<usize> {
+    let after = &text[punctuation_end..];
+    let unmarked = after.trim_start_matches(BIDI_MARKS);
+    if unmarked.len() == after.len() || !unmarked.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(text.len() - unmarked.trim_start().len())
+}
+
+fn is_thai(c: char) -> bool {
+    ('\u{0E01}'..='\u{0E5B}').contains(&c)
+}
+
+fn is_lao(c: char) -> bool {
+    ('\u{0E81}'..='\u{0EDF}').contains(&c)
+}
+
+/// The first run of spaces between two letters of a script that is neither preceded by a
+/// continuation word (or the repetition mark `ๆ`) nor followed by one.
+fn space_boundary(
+    text: &str,
+    in_script: fn(char) -> bool,
+    continuations: &[&str],
+) -> Option<usize> {
+    let mut search = 0;
+    while let Some(found) = text[search..].find(' ') {
+        let start = search + found;
+        let end = start + text[start..].len() - text[start..].trim_start_matches(' ').len();
+        search = end;
+        let (before, after) = (&text[..start], &text[end..]);
+        let (Some(last), Some(next)) = (before.chars().next_back(), after.chars().next()) else {
+            continue;
+        };
+        if !in_script(last) || !in_script(next) || last == 'ๆ' {
+            continue;
+        }
+        let previous_word = before.rsplit(' ').next().unwrap_or_default();
+        let continues = continuations
+            .iter()
+            .any(|word| after.starts_with(word) || previous_word.ends_with(word));
+        if !continues {
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// Language whose script rules apply to `c`: `Some("")` for letters of scripts without such
+/// rules, `None` for characters that belong to no script (spaces, punctuation, digits).
+fn script_language(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{0E00}'..='\u{0E7F}' => "th",
+        '\u{0E80}'..='\u{0EFF}' => "lo",
+        '\u{1780}'..='\u{17FF}' => "km",
+        '\u{0900}'..='\u{097F}' => "hi",
+        '\u{0980}'..='\u{09FF}' => "bn",
+        '\u{1200}'..='\u{139F}' => "am",
+        '\u{0530}'..='\u{058F}' => "hy",
+        '\u{1000}'..='\u{109F}' => "my",
+        '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => "el",
+        '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' => "ur",
+        _ if c.is_alphabetic() => "",
+        _ => return None,
+    })
+}
+
+/// Like [`script_boundary`], but also applies the script rules of every other script found in
+/// `text` to its runs of that script. A run extends over the spaces and punctuation that
+/// follow it, so it includes the terminator and whitespace ending the sentence.
+pub(crate) fn mixed_script_boundary(language: &str, text: &str) -> Option<usize> {
+    let own = script_boundary(language, text);
+    let mut best = own;
+    let mut runs = Vec::new();
+    for (position, c) in text.char_indices() {
+        match (script_language(c), runs.last_mut()) {
+            (None, _) => {}
+            (Some(script), Some((current, _))) if *current == script => {}
+            (Some(script), _) => runs.push((script, position)),
+        }
+    }
+    for (index, &(script, start)) in runs.iter().enumerate() {
+        if script.is_empty() || best.is_some_and(|end| end <= start) {
+            continue;
+        }
+        let end = runs.get(index + 1).map_or(text.len(), |&(_, next)| next);
+        if let Some(boundary) = script_boundary(script, &text[start..end]) {
+            best = Some(best.map_or(start + boundary, |b| b.min(start + boundary)));
+        }
+    }
+    best
+}
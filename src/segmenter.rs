@@ -1,142 +1,1499 @@
-use crate::config::SegmentOptions;
-use crate::error::{Result, SegmenterError};
-use libtqsm::{get_language, GraphemeCursor, Language}; // Language trait is now needed
-use std::collections::HashMap;
+use crate::audit::{Boundary, BoundaryRule};
+use crate::backend::SegmentationBackend;
+use crate::chat;
+use crate::config::{Mode, SegmentOptions, SegmentUnit};
+use crate::decode::Utf8Decoder;
+use crate::detect::{SentenceLanguage, AUTO_LANGUAGE};
+use crate::error::{snippet, Result, SegmenterError};
+use crate::logs;
+use crate::memory::MemoryPolicy;
+use crate::protect::{self, footnote_boundary, Citation};
+use crate::quotes::QuoteState;
+use crate::rules::BoundaryAction;
+use crate::scripts::{
+    self, bidi_boundary, mixed_script_boundary, script_boundary, unspaced_boundary,
+};
+use crate::warnings::WarningKind;
+use regex::Regex;
+use std::collections::VecDeque;
 use unicode_segmentation::UnicodeSegmentation; // Add this line
 
+/// A sentence together with its position in the input stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    /// Zero-based position of the sentence in the stream.
+    pub index: usize,
+    /// Byte offset of the first byte of the sentence in the whole input stream.
+    pub start: usize,
+    /// Byte offset one past the last byte of the sentence in the whole input stream.
+    pub end: usize,
+    /// Like `start`, counted in characters (Unicode scalar values), e.g. for JavaScript or
+    /// Python consumers that index strings by character.
+    pub char_start: usize,
+    /// Like `end`, counted in characters.
+    pub char_end: usize,
+    /// Line of the first character of `text`, counting from 1; lines end at `\n`.
+    pub line: usize,
+    /// Column of the first character of `text` within its line, in characters, counting
+    /// from 1.
+    pub column: usize,
+    /// The sentence text, trimmed of surrounding spaces (only trailing ones with
+    /// [`keep_indentation`](SegmentOptions::keep_indentation)).
+    ///
+    /// With a [`post_process`](SegmentOptions::post_process) step, this is its output, so it
+    /// need not match the input between `start` and `end` any more, e.g. once
+    /// [`strip_markup`](crate::postprocess::strip_markup) removed `**`. The offsets, line and
+    /// column still describe the input; to map a position in stripped text back to it, run
+    /// [`strip_markup_mapped`](crate::postprocess::strip_markup_mapped) on that input slice.
+    pub text: String,
+    /// Set on a trailing fragment flushed at the end of the input without a sentence boundary,
+    /// under [`TailPolicy::EmitMarkedIncomplete`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub incomplete: bool,
+    /// Language detected for this sentence under
+    /// [`sentence_language`](SegmentOptions::sentence_language), or, for a sentence too short
+    /// to tell, the language whose rules segmented it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub language: Option<String>,
+    /// Zero-based position of the sentence's paragraph in the stream, under
+    /// [`SegmentUnit::Nested`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub paragraph: Option<usize>,
+}
+
+/// What flushing does with text left over at the end of the input that never reached a
+/// sentence boundary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TailPolicy {
+    /// Emit it like any other sentence.
+    #[default]
+    Emit,
+    /// Discard it, so consumers only ever see complete sentences.
+    Drop,
+    /// Emit it with [`Segment::incomplete`] set.
+    #[cfg_attr(feature = "cli", value(name = "mark"))]
+    EmitMarkedIncomplete,
+}
+
+/// What a segmenter does when text without a sentence boundary fills its
+/// [`max_buffer`](SegmentOptions::max_buffer).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum OverflowPolicy {
+    /// Fail with [`SegmenterError::BufferOverflow`], handing the text back.
+    #[default]
+    Error,
+    /// Emit the buffered text up to its last whitespace as a segment of its own and go on;
+    /// text without whitespace is split like [`ForceSplitAtGrapheme`](Self::ForceSplitAtGrapheme).
+    #[cfg_attr(feature = "cli", value(name = "whitespace"))]
+    ForceSplitAtWhitespace,
+    /// Emit the buffered text up to its last grapheme cluster as a segment of its own and go
+    /// on, so no character sequence is torn apart.
+    #[cfg_attr(feature = "cli", value(name = "grapheme"))]
+    ForceSplitAtGrapheme,
+}
+
+/// Incremental sentence segmentation as the streams drive it, so another implementation (an
+/// ICU, SRX or ML backend, or a mock in tests) can be used with
+/// [`segments_stream_with`](crate::segments_stream_with) and
+/// [`sentences_stream_with`](crate::sentences_stream_with).
+///
+/// The methods are named after [`Segmenter`]'s own [`feed_segments`](Segmenter::feed_segments)
+/// and [`finish_segments`](Segmenter::finish_segments), which they call; its inherent `feed` and
+/// `flush` return plain strings.
+pub trait SentenceSegmenter {
+    /// Appends `chunk` to the input and returns the sentences it completes.
+    fn feed_segments(&mut self, chunk: &str) -> Result<Vec<Segment>>;
+
+    /// Ends the input, returning every sentence still buffered.
+    fn finish_segments(&mut self) -> Result<Vec<Segment>>;
+
+    /// Discards the buffered input and starts a new one, with offsets and indices counting from
+    /// 0 again.
+    fn reset(&mut self);
+
+    /// Like [`finish_segments`](Self::finish_segments), also returning the trailing fragment that had no sentence
+    /// boundary, whether or not it was emitted, for [`StreamItem::End`](crate::StreamItem::End).
+    /// The default reports none.
+    fn finish_with_tail(&mut self) -> Result<(Vec<Segment>, Option<String>)> {
+        Ok((self.finish_segments()?, None))
+    }
+
+    /// Emits what is buffered because the input paused for
+    /// [`flush_after`](SegmentOptions::flush_after); more input may follow. Defaults to
+    /// [`finish_segments`](Self::finish_segments).
+    fn flush_idle(&mut self) -> Result<Vec<Segment>> {
+        self.finish_segments()
+    }
+
+    /// Language code the sentences are counted under in [`StreamStats`](crate::StreamStats).
+    fn language(&self) -> &str {
+        "und"
+    }
+}
+
 pub struct Segmenter {
     buffer: String,
     options: SegmentOptions,
-    language: &'static (dyn Language + Send + Sync),
+    backend: Box<dyn SegmentationBackend>,
+    /// Bytes already drained from the front of `buffer` since the stream started.
+    consumed: usize,
+    /// `consumed` in characters.
+    consumed_chars: usize,
+    /// Line and column at `consumed`, both counting from 1.
+    line: usize,
+    column: usize,
+    /// Quotes and brackets still open at `consumed`.
+    quotes: QuoteState,
+    /// Index assigned to the next emitted segment.
+    next_index: usize,
+    /// Index of the paragraph the next segment belongs to.
+    paragraph: usize,
+    /// Compiled protected-span patterns of `options.protections`.
+    spans: Vec<Regex>,
+    /// Compiled `options.log_prefix`, in log mode.
+    log_prefix: Option<Regex>,
+    /// Compiled split rules of `options.custom_boundaries`.
+    custom_splits: Vec<(Regex, BoundaryAction)>,
+    /// Compiled no-split rules of `options.custom_boundaries`.
+    custom_no_splits: Vec<Regex>,
+    /// Bytes of `buffer` accounted against `options.memory_pool`.
+    reserved: usize,
+    /// Set while the language of a `language: "auto"` stream is still to be detected.
+    detecting: bool,
+    /// Whether the language was `"auto"`, so a [`reset`](Self::reset) detects it again.
+    auto_language: bool,
+    /// The language the segmenter started with, which a [`reset`](Self::reset) goes back to
+    /// after detection switched the rules.
+    initial_language: String,
+    /// The backend and protected spans of `initial_language`, kept while detection has
+    /// switched to those of another language.
+    initial_rules: Option<(Box<dyn SegmentationBackend>, Vec<Regex>)>,
+    /// Holds back characters split between chunks passed to [`feed_bytes`](Self::feed_bytes).
+    decoder: Utf8Decoder,
+    /// Segments already cut from the buffer, e.g. to make room in the memory pool, that have
+    /// not been returned yet; they come out before any further ones.
+    ready: VecDeque<Segment>,
 }
 
 impl Segmenter {
-    pub fn new(options: SegmentOptions) -> Result<Self> {
-        let language_impl = get_language(&options.language)
-            .ok_or_else(|| SegmenterError::UnsupportedLanguage(options.language.clone()))?;
+    pub fn new(mut options: SegmentOptions) -> Result<Self> {
+        if options.language == AUTO_LANGUAGE {
+            return Self::detecting(options);
+        }
+        let backend = crate::backend::for_options(&mut options)?;
+        Self::build(options, backend)
+    }
+
+    /// A segmenter for `language: "auto"`, which detects the language once
+    /// `detection_window` bytes are buffered. Until then it has the rules of the
+    /// `fallback_language` (or English), which are kept if detection fails.
+    fn detecting(options: SegmentOptions) -> Result<Self> {
+        if cfg!(not(feature = "detect")) {
+            return Err(SegmenterError::InvalidOptions(
+                "language \"auto\" needs the `detect` feature".to_string(),
+            ));
+        }
+        let mut placeholder = options.clone();
+        placeholder.language = default_detected(&options);
+        let backend = crate::backend::for_options(&mut placeholder)?;
+        let mut segmenter = Self::build(placeholder, backend)?;
+        segmenter.detecting = true;
+        segmenter.auto_language = true;
+        Ok(segmenter)
+    }
+
+    /// Like [`new`](Self::new), but finds boundaries with `backend` instead of the `libtqsm`
+    /// rules, so `options.language` need not be one they support. The language code still
+    /// selects the script rules, protections and abbreviations applied around the backend.
+    ///
+    /// `language: "auto"` and [`SentenceLanguage::Switch`] are rejected, as both would replace
+    /// `backend` with the rules of the detected language.
+    pub fn with_backend(
+        options: SegmentOptions,
+        backend: Box<dyn SegmentationBackend>,
+    ) -> Result<Self> {
+        if options.language == AUTO_LANGUAGE {
+            return Err(SegmenterError::InvalidOptions(
+                "language \"auto\" cannot be used with a custom backend".to_string(),
+            ));
+        }
+        if options.sentence_language == SentenceLanguage::Switch {
+            return Err(SegmenterError::InvalidOptions(
+                "sentence_language \"switch\" cannot be used with a custom backend".to_string(),
+            ));
+        }
+        Self::build(options, backend)
+    }
+
+    /// Compiles the patterns of `options` around `backend`.
+    fn build(options: SegmentOptions, backend: Box<dyn SegmentationBackend>) -> Result<Self> {
+        crate::decode::check_encoding(&options)?;
+        if options.sentence_language != SentenceLanguage::Off {
+            #[cfg(feature = "detect")]
+            crate::detect::check_candidates(&options.sentence_languages)?;
+            #[cfg(not(feature = "detect"))]
+            return Err(SegmenterError::InvalidOptions(
+                "sentence_language needs the `detect` feature".to_string(),
+            ));
+        }
+
+        let spans = span_patterns(&options)?;
+
+        let log_prefix = match options.mode {
+            Mode::Log => Some(logs::prefix_regex(options.log_prefix.as_deref())?),
+            _ => None,
+        };
+
+        let mut custom_splits = Vec::new();
+        let mut custom_no_splits = Vec::new();
+        for rule in &options.custom_boundaries {
+            let pattern = rule.compile()?;
+            match rule.action {
+                BoundaryAction::NoSplit => custom_no_splits.push(pattern),
+                action => custom_splits.push((pattern, action)),
+            }
+        }
 
         Ok(Self {
             buffer: String::with_capacity(options.max_buffer / 4),
+            custom_splits,
+            custom_no_splits,
+            decoder: Utf8Decoder::for_options(&options),
+            spans,
+            log_prefix,
+            quotes: QuoteState::new(options.max_quote_depth, options.quote_recovery),
+            initial_language: options.language.clone(),
+            initial_rules: None,
             options,
-            language: language_impl,
+            backend,
+            consumed: 0,
+            consumed_chars: 0,
+            line: 1,
+            column: 1,
+            next_index: 0,
+            paragraph: 0,
+            reserved: 0,
+            detecting: false,
+            auto_language: false,
+            ready: VecDeque::new(),
         })
     }
 
     pub fn feed(&mut self, chunk: &str) -> Result<Vec<String>> {
+        Ok(self
+            .feed_segments(chunk)?
+            .into_iter()
+            .map(|segment| segment.text)
+            .collect())
+    }
+
+    /// Like [`feed`](Self::feed), but returns each sentence with its stream offsets.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(chunk_len = chunk.len(), buffered = self.buffer.len())
+        )
+    )]
+    pub fn feed_segments(&mut self, chunk: &str) -> Result<Vec<Segment>> {
+        self.push_chunk(chunk)?;
+        self.process_buffer(self.options.lookahead)
+    }
+
+    /// Like [`feed`](Self::feed), for raw UTF-8 input: a multi-byte character split between two
+    /// chunks is held back until its remaining bytes arrive, and invalid bytes are handled per
+    /// [`utf8_policy`](SegmentOptions::utf8_policy). [`finish`](Self::finish) deals with a
+    /// character still incomplete at the end.
+    pub fn feed_bytes(&mut self, chunk: &[u8]) -> Result<Vec<String>> {
+        let text = self.decoder.decode(chunk)?;
+        self.feed(&text)
+    }
+
+    /// Like [`feed_bytes`](Self::feed_bytes), but returns each sentence with its stream offsets.
+    pub fn feed_bytes_segments(&mut self, chunk: &[u8]) -> Result<Vec<Segment>> {
+        let text = self.decoder.decode(chunk)?;
+        self.feed_segments(&text)
+    }
+
+    /// Like [`feed_segments`](Self::feed_segments), but finds the sentences lazily, one per
+    /// call to `next`, without collecting them first. Sentences the iterator is not advanced
+    /// to stay buffered and come out of the next call.
+    pub fn feed_iter(&mut self, chunk: &str) -> Result<impl Iterator<Item = Segment> + '_> {
+        self.push_chunk(chunk)?;
+        let lookahead = self.options.lookahead;
+        Ok(std::iter::from_fn(move || self.next_segment(lookahead)))
+    }
+
+    /// Like [`feed_segments`](Self::feed_segments), but emits at most `max_sentences` and keeps
+    /// the rest buffered, so interactive callers can do other work between sentences. If it
+    /// returned `max_sentences`, more may be ready: get them with another
+    /// `feed_limited("", n)` or with [`drain_pending`](Self::drain_pending).
+    pub fn feed_limited(&mut self, chunk: &str, max_sentences: usize) -> Result<Vec<Segment>> {
+        Ok(self.feed_iter(chunk)?.take(max_sentences).collect())
+    }
+
+    /// Emits every buffered sentence whose boundary is already confirmed, e.g. those held
+    /// back by [`feed_limited`](Self::feed_limited). Text still waiting for a boundary stays
+    /// buffered.
+    pub fn drain_pending(&mut self) -> Vec<Segment> {
+        let lookahead = self.options.lookahead;
+        std::iter::from_fn(|| self.next_segment(lookahead)).collect()
+    }
+
+    /// Appends `chunk` to the buffer, or fails if that would exceed `max_buffer` under
+    /// [`OverflowPolicy::Error`]. Segments flushed early to make room in the memory pool or the
+    /// buffer are queued in `ready`.
+    fn push_chunk(&mut self, chunk: &str) -> Result<()> {
         if self.buffer.len() + chunk.len() > self.options.max_buffer {
-            return Err(SegmenterError::BufferOverflow(self.options.max_buffer));
+            if self.options.overflow_policy != OverflowPolicy::Error {
+                return self.push_oversized(chunk);
+            }
+            #[cfg(feature = "tracing")]
+            tracing::warn!(max_buffer = self.options.max_buffer, "buffer overflow");
+            let offset = self.consumed + self.buffer.len();
+            let snippet = snippet(&self.buffer, chunk);
+            // Hand the text back instead of stranding it in the buffer
+            let mut pending = std::mem::take(&mut self.buffer);
+            pending.push_str(chunk);
+            self.advance(&pending);
+            return Err(SegmenterError::BufferOverflow {
+                max: self.options.max_buffer,
+                offset,
+                snippet,
+                pending,
+            });
+        }
+        if let Some(pool) = self.options.memory_pool.clone() {
+            if !pool.try_reserve(chunk.len()) {
+                if pool.policy() == MemoryPolicy::Reject {
+                    return Err(SegmenterError::MemoryBudgetExceeded {
+                        capacity: pool.capacity(),
+                        offset: self.consumed + self.buffer.len(),
+                        pending: chunk.to_string(),
+                    });
+                }
+                let forced = self.flush_for_memory();
+                self.ready.extend(forced);
+                pool.force_reserve(chunk.len());
+            }
+            self.reserved += chunk.len();
         }
         self.buffer.push_str(chunk);
-        self.process_buffer()
+        Ok(())
     }
 
-    fn process_buffer(&mut self) -> Result<Vec<String>> {
-        let mut completed_sentences = Vec::new();
-        let mut current_offset = 0;
-
+    /// Appends a `chunk` that does not fit, under a splitting [`OverflowPolicy`]: it is added
+    /// piece by piece, and whenever the buffer is full, the sentences ending in it are queued in
+    /// `ready`, or if there are none, a [`forced split`](Self::force_split) makes room.
+    fn push_oversized(&mut self, chunk: &str) -> Result<()> {
+        let mut rest = chunk;
         loop {
-            let buffer_len = self.buffer.len();
-            if current_offset >= buffer_len {
+            let room = self.options.max_buffer.saturating_sub(self.buffer.len());
+            let mut take = room.min(rest.len());
+            while !rest.is_char_boundary(take) {
+                take -= 1;
+            }
+            if take == 0 && self.buffer.is_empty() {
+                // A `max_buffer` smaller than one character still has to make progress
+                take = rest.chars().next().map_or(0, char::len_utf8);
+            }
+            if take > 0 {
+                self.push_chunk(&rest[..take])?;
+                rest = &rest[take..];
+            }
+            if rest.is_empty() {
+                return Ok(());
+            }
+            let buffered = self.buffer.len();
+            while let Some(segment) = self.cut_segment(self.options.lookahead) {
+                self.ready.push_back(segment);
+            }
+            if self.buffer.len() == buffered {
+                if let Some(segment) = self.force_split() {
+                    self.ready.push_back(segment);
+                }
+            }
+        }
+    }
+
+    /// Cuts a segment off the front of the full buffer where the overflow policy allows, and
+    /// reports it as a [`WarningKind::ForcedSplit`].
+    fn force_split(&mut self) -> Option<Segment> {
+        let at_whitespace = || {
+            let content = self.buffer.trim_start_matches(SPACES).len();
+            let leading = self.buffer.len() - content;
+            self.buffer[leading..]
+                .char_indices()
+                .rev()
+                .find(|&(_, c)| c.is_whitespace())
+                .map(|(i, c)| leading + i + c.len_utf8())
+                .filter(|&end| end > leading + 1)
+        };
+        // The last grapheme may still be extended by the next chunk, so it stays buffered
+        let at_grapheme = || {
+            self.buffer
+                .grapheme_indices(true)
+                .next_back()
+                .map(|(i, _)| i)
+                .filter(|&i| i > 0)
+                .unwrap_or(self.buffer.len())
+        };
+        let (end, at) = match self.options.overflow_policy {
+            OverflowPolicy::ForceSplitAtWhitespace => match at_whitespace() {
+                Some(end) => (end, "whitespace"),
+                None => (at_grapheme(), "a grapheme boundary"),
+            },
+            _ => (at_grapheme(), "a grapheme boundary"),
+        };
+        if self.buffer[..end].trim_matches(SPACES).is_empty() {
+            self.discard_buffer_to(end);
+            return None;
+        }
+        self.report_forced_split(
+            end,
+            format!(
+                "no sentence boundary within {} bytes, split at {}",
+                self.options.max_buffer, at
+            ),
+        );
+        Some(self.take_segment(end))
+    }
+
+    /// Where to split the sentence ending at `buffer[end]` if it is longer than
+    /// [`max_sentence_len`](SegmentOptions::max_sentence_len): after the last clause mark in the
+    /// second half of the limit, else before the last whitespace, else at the last grapheme
+    /// boundary within it. Also returns what the split is at, for the warning.
+    fn length_split(&self, end: usize) -> Option<(usize, &'static str)> {
+        let max = self.options.max_sentence_len;
+        let leading = self.buffer.len() - self.buffer.trim_start_matches(SPACES).len();
+        let limit = leading + max;
+        if max == 0 || self.buffer[..end].trim_end().len() <= limit {
+            return None;
+        }
+        let text = &self.buffer[leading..];
+        let (mut clause, mut space, mut grapheme) = (None, None, None);
+        for (i, g) in text.grapheme_indices(true) {
+            let at = leading + i;
+            if at > limit {
                 break;
             }
+            let first = g.chars().next().unwrap_or_default();
+            if i > 0 {
+                grapheme = Some(at);
+                if first.is_whitespace() {
+                    space = Some(at);
+                }
+            }
+            let after = at + g.len();
+            let spaced = text[i + g.len()..]
+                .chars()
+                .next()
+                .is_some_and(char::is_whitespace);
+            let clause_mark = (CLAUSE_MARKS.contains(&first) && spaced)
+                || FULL_WIDTH_CLAUSE_MARKS.contains(&first);
+            if clause_mark && after <= limit {
+                clause = Some(after);
+            }
+        }
+        clause
+            .filter(|&at| at >= leading + max / 2)
+            .map(|at| (at, "a clause boundary"))
+            .or(space.map(|at| (at, "whitespace")))
+            .or(grapheme.map(|at| (at, "a grapheme boundary")))
+            // A single grapheme longer than the limit is kept whole
+            .or_else(|| {
+                let next = text.grapheme_indices(true).nth(1)?.0;
+                Some((leading + next, "a grapheme boundary"))
+            })
+    }
 
-            let mut boundary_found_in_iteration = false;
-            let remaining_buffer_slice = &self.buffer[current_offset..];
+    /// Reports a split that no sentence boundary justified, ending at `buffer[end]`.
+    fn report_forced_split(&self, end: usize, message: String) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(bytes = end, "forced split");
+        if let Some(warnings) = &self.options.warnings {
+            warnings.report(WarningKind::ForcedSplit, self.consumed + end, message);
+        }
+        self.audit_boundary(end, BoundaryRule::ForcedSplit, 0);
+    }
 
-            // --- Create GraphemeCursor locally ---
-            let grapheme_indices: HashMap<usize, &str> =
-                remaining_buffer_slice.grapheme_indices(false).collect();
-            let mut grapheme_offsets: Vec<usize> = grapheme_indices.keys().copied().collect();
-            grapheme_offsets.sort_unstable();
-            // Use the public constructor (assuming you added `pub fn new(...)` to libtqsm)
-            let cursor = GraphemeCursor::new(grapheme_offsets);
-            // ---
+    /// Empties the buffer to make room in the memory pool: the sentences in it are emitted
+    /// without waiting for lookahead, followed by the unfinished one.
+    fn flush_for_memory(&mut self) -> Vec<Segment> {
+        self.settle_language();
+        let mut segments: Vec<Segment> = std::iter::from_fn(|| self.cut_segment(0)).collect();
+        if self.buffer.trim_matches(' ').is_empty() {
+            self.discard_buffer();
+            return segments;
+        }
+        if let Some(warnings) = &self.options.warnings {
+            warnings.report(
+                WarningKind::MemoryPressure,
+                self.consumed + self.buffer.len(),
+                format!(
+                    "memory pool full, flushed {} buffered bytes early",
+                    self.buffer.len()
+                ),
+            );
+        }
+        self.audit_boundary(self.buffer.len(), BoundaryRule::EndOfInput, 0);
+        segments.push(self.take_segment(self.buffer.len()));
+        segments
+    }
 
-            let skippable_ranges = self.language.get_skippable_ranges(remaining_buffer_slice);
-            let mut best_boundary: Option<(usize, usize)> = None; // (relative_pos, absolute_pos_in_buffer)
+    /// Emits the sentences found in the buffer, accepting a boundary only once `lookahead`
+    /// bytes follow it.
+    fn process_buffer(&mut self, lookahead: usize) -> Result<Vec<Segment>> {
+        let mut completed_sentences = Vec::new();
+        while let Some(segment) = self.next_segment(lookahead) {
+            completed_sentences.push(segment);
+        }
+        Ok(completed_sentences)
+    }
 
-            for mtch in self
-                .language
-                .sentence_break_regex()
-                .find_iter(remaining_buffer_slice)
+    /// Emits the oldest segment in `ready`, or else the next one [cut](Self::cut_segment) from
+    /// the buffer.
+    fn next_segment(&mut self, lookahead: usize) -> Option<Segment> {
+        self.ready
+            .pop_front()
+            .or_else(|| self.cut_segment(lookahead))
+    }
+
+    /// Cuts the first sentence off the buffer if its boundary is followed by `lookahead` bytes.
+    fn cut_segment(&mut self, lookahead: usize) -> Option<Segment> {
+        let buffer_len = self.buffer.len();
+        if buffer_len == 0 {
+            return None;
+        }
+        if self.detecting {
+            let window = self.options.detection_window.min(self.options.max_buffer);
+            if buffer_len < window {
+                return None;
+            }
+            self.detect_language();
+        }
+        let mut best_boundary = self.first_sentence_end(lookahead)?;
+        // A sentence in another language is segmented again with that language's rules
+        if let Some((end, _)) = best_boundary {
+            if self.follow_sentence_language(end) {
+                best_boundary = self.first_sentence_end(lookahead)?;
+            }
+        }
+
+        // Sentences over the length limit are cut short, also before their boundary arrives
+        let sentence_end = best_boundary.map_or(buffer_len, |(end, _)| end);
+        if let Some((split, at)) = self.length_split(sentence_end) {
+            self.report_forced_split(
+                split,
+                format!(
+                    "sentence longer than {} bytes, split at {}",
+                    self.options.max_sentence_len, at
+                ),
+            );
+            return Some(self.take_segment(split));
+        }
+
+        let (absolute_end, rule) = best_boundary?;
+        self.audit_boundary(absolute_end, rule, buffer_len - absolute_end);
+        Some(self.take_segment(absolute_end))
+    }
+
+    /// The end of the first sentence in the buffer: `Some(None)` if no boundary is followed by
+    /// `lookahead` bytes yet, `None` if a sentence under the minimum length has to wait for the
+    /// next one to be merged with.
+    fn first_sentence_end(&self, lookahead: usize) -> Option<Option<(usize, BoundaryRule)>> {
+        let mut best_boundary = self.find_sentence_end(0, lookahead);
+
+        // A sentence under the minimum length waits to be merged with the next one
+        while let Some((end, _)) = best_boundary.filter(|&(end, _)| self.is_short(end)) {
+            match self
+                .find_sentence_end(end, lookahead)
+                .filter(|&(next, _)| next > end)
             {
-                let (match_start, match_end) = (mtch.start(), mtch.end());
-
-                // --- Handle skippable ranges *before* calling find_boundary ---
-                let mut in_range = false;
-                for (skip_start, skip_end) in skippable_ranges.iter() {
-                    if match_start >= *skip_start && match_end <= *skip_end {
-                        if match_end == *skip_end && self.language.is_punctuation_between_quotes() {
-                            // It's the closing punctuation of a skippable range
-                            // Treat this match end as the potential boundary point
-                            best_boundary = Some((*skip_end, current_offset + *skip_end));
-                            boundary_found_in_iteration = true;
-                            in_range = true; // Mark as handled within range logic
-                            break; // Process this boundary
-                        } else {
-                            // Boundary is fully inside skip range, ignore it
-                            in_range = true;
-                            break; // Stop checking ranges for this match
-                        }
-                    }
-                }
-                if in_range {
-                    if best_boundary.is_some() {
-                        break;
-                    }
-                    // Break outer loop if boundary was found
-                    else {
-                        continue;
-                    } // Continue to next match if ignored
+                Some(next) => best_boundary = Some(next),
+                // Flushing: a complete sentence is not merged into a fragment that may be dropped
+                None if lookahead == 0
+                    && (self.buffer[end..].trim().is_empty()
+                        || self.options.tail_policy == TailPolicy::Drop) =>
+                {
+                    break
                 }
-                // --- End skippable range handling ---
-
-                // Call the original find_boundary from the trait
-                // Ensure grapheme_indices are mapped correctly if necessary (here assumed relative to slice)
-                if let Some((relative_boundary_end, is_num_ref)) = self.language.find_boundary(
-                    remaining_buffer_slice,
-                    &grapheme_indices,
-                    &cursor,
-                    mtch,
-                ) {
-                    let absolute_boundary_end = current_offset + relative_boundary_end;
-
-                    if is_num_ref || buffer_len >= absolute_boundary_end + self.options.lookahead {
-                        best_boundary = Some((relative_boundary_end, absolute_boundary_end));
-                        boundary_found_in_iteration = true;
+                None => return None,
+            }
+        }
+        Some(best_boundary)
+    }
+
+    /// Whether the sentence ending at `buffer[end]` is shorter than
+    /// [`min_sentence_len`](SegmentOptions::min_sentence_len).
+    fn is_short(&self, end: usize) -> bool {
+        self.buffer[..end].trim().len() < self.options.min_sentence_len
+    }
+
+    /// Finds the end of the first sentence in `buffer[from..]` whose boundary is followed by
+    /// `lookahead` bytes, as a position in `buffer`.
+    fn find_sentence_end(&self, from: usize, lookahead: usize) -> Option<(usize, BoundaryRule)> {
+        let remaining_buffer_slice = &self.buffer[from..];
+        let buffer_len = remaining_buffer_slice.len();
+        if self.options.unit == SegmentUnit::Paragraph {
+            return paragraph_break(remaining_buffer_slice)
+                .map(|end| (from + end, BoundaryRule::ParagraphBreak));
+        }
+
+        let skippable_ranges = self.backend.skippable_ranges(remaining_buffer_slice);
+        let mut best_boundary: Option<(usize, BoundaryRule)> = None; // (end position in buffer, rule)
+
+        // Script and chat boundaries, which may come before any punctuation
+        let script_boundary = self.script_boundary(from);
+        let mut script_boundary_reached = script_boundary.is_some();
+        // A candidate boundary waiting for more input
+        let mut deferred = None;
+
+        for mtch in self
+            .backend
+            .sentence_break_regex()
+            .find_iter(remaining_buffer_slice)
+        {
+            let (match_start, match_end) = (mtch.start(), mtch.end());
+            if script_boundary.is_some_and(|(end, _)| match_start >= end) {
+                break;
+            }
+
+            // --- Handle skippable ranges *before* calling find_boundary ---
+            let mut in_range = false;
+            for (skip_start, skip_end) in skippable_ranges.iter() {
+                if match_start >= *skip_start && match_end <= *skip_end {
+                    if match_end == *skip_end && self.backend.is_punctuation_between_quotes() {
+                        // It's the closing punctuation of a skippable range
+                        // Treat this match end as the potential boundary point
+                        best_boundary = Some((*skip_end, BoundaryRule::ClosingQuote));
+                        in_range = true; // Mark as handled within range logic
                         break; // Process this boundary
                     } else {
-                        boundary_found_in_iteration = false;
+                        // Boundary is fully inside skip range, ignore it
+                        in_range = true;
+                        break; // Stop checking ranges for this match
+                    }
+                }
+            }
+            if in_range {
+                if best_boundary.is_some() {
+                    break;
+                }
+                // Break outer loop if boundary was found
+                else {
+                    continue;
+                } // Continue to next match if ignored
+            }
+            // --- End skippable range handling ---
+
+            let footnote = Some(&self.options.protections)
+                .filter(|protections| protections.footnotes)
+                .and_then(|_| footnote_boundary(remaining_buffer_slice, match_end));
+            let candidate = match footnote {
+                Some(end) => Some((end, BoundaryRule::FootnoteMarker)),
+                None => self
+                    .backend
+                    .find_boundary(remaining_buffer_slice, mtch)
+                    .map(|(end, is_num_ref)| {
+                        let rule = if is_num_ref {
+                            BoundaryRule::NumberedReference
+                        } else {
+                            BoundaryRule::Terminator
+                        };
+                        (end, rule)
+                    })
+                    // No space follows a full-width terminator in CJK text, and a
+                    // directional mark may come before the space in RTL text
+                    .or_else(|| {
+                        unspaced_boundary(remaining_buffer_slice, match_end)
+                            .or_else(|| bidi_boundary(remaining_buffer_slice, match_end))
+                            .or_else(|| {
+                                self.removed_abbreviation_boundary(remaining_buffer_slice, &mtch)
+                            })
+                            .or_else(|| {
+                                (self.options.mode == Mode::Chat)
+                                    .then(|| {
+                                        chat::lowercase_boundary(
+                                            remaining_buffer_slice,
+                                            match_start..match_end,
+                                        )
+                                    })
+                                    .flatten()
+                            })
+                            .map(|end| (end, BoundaryRule::Terminator))
+                    }),
+            };
+            if let Some((relative_boundary_end, rule)) = candidate {
+                let (before, after) = remaining_buffer_slice.split_at(relative_boundary_end);
+                if self.is_protected(before, after)
+                    || protect::inside_span(
+                        &self.spans,
+                        remaining_buffer_slice,
+                        before.trim_end().len(),
+                    )
+                    || self.inside_no_split(remaining_buffer_slice, before.trim_end().len())
+                {
+                    continue;
+                }
+                if self.inside_quote(&self.buffer[..from + relative_boundary_end]) {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(
+                        offset = self.consumed + relative_boundary_end,
+                        "boundary inside an open quote"
+                    );
+                    continue;
+                }
+                // A citation right after the sentence belongs to it
+                let (absolute_boundary_end, rule) = match self.trailing_citation(after) {
+                    Citation::None => (relative_boundary_end, rule),
+                    Citation::End(len) => (relative_boundary_end + len, BoundaryRule::Citation),
+                    Citation::Incomplete => {
+                        script_boundary_reached = false;
+                        deferred = Some(relative_boundary_end);
                         break; // Need more input
                     }
+                };
+                let is_num_ref = rule == BoundaryRule::NumberedReference;
+                // A chat message is complete once its line break has arrived, and so is a
+                // paragraph once its blank line has
+                let ends_message = (self.options.mode.is_line_based()
+                    && remaining_buffer_slice[before.trim_end().len()..].contains('\n'))
+                    || (self.options.unit == SegmentUnit::Nested && ends_paragraph(before));
+
+                if is_num_ref || ends_message || buffer_len >= absolute_boundary_end + lookahead {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(
+                        offset = self.consumed + absolute_boundary_end,
+                        rule = rule.as_str(),
+                        "boundary accepted"
+                    );
+                    best_boundary = Some((absolute_boundary_end, rule));
+                    break; // Process this boundary
+                } else {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(
+                        offset = self.consumed + absolute_boundary_end,
+                        lookahead,
+                        "boundary deferred until more input arrives"
+                    );
+                    script_boundary_reached = false;
+                    deferred = Some(absolute_boundary_end);
+                    break; // Need more input
                 }
             }
+        }
 
-            if let Some((_relative_end, absolute_end)) = best_boundary {
-                let sentence = self.buffer[..absolute_end].to_string();
-                completed_sentences.push(sentence.trim_matches(' ').to_string());
-                self.buffer.drain(..absolute_end);
-                current_offset = 0;
-                continue;
+        if best_boundary.is_none() && script_boundary_reached {
+            // A line break ends a chat message whatever follows, and so does a message that is
+            // only emoji or an interjection
+            best_boundary = script_boundary.filter(|&(end, rule)| {
+                matches!(
+                    rule,
+                    BoundaryRule::LineBreak
+                        | BoundaryRule::Standalone
+                        | BoundaryRule::ParagraphBreak
+                ) || buffer_len >= end + lookahead
+            });
+        }
+
+        // Custom split rules count where the language's rules have nothing earlier
+        let built_in = best_boundary
+            .map(|(end, _)| end)
+            .or(deferred)
+            .or(script_boundary.map(|(end, _)| end));
+        if let Some((at, confirmed)) = self.custom_split(remaining_buffer_slice, lookahead) {
+            if built_in.is_none_or(|end| at < end) {
+                return confirmed.then_some((from + at, BoundaryRule::Custom));
             }
+        }
+        best_boundary.map(|(end, rule)| (from + end, rule))
+    }
 
-            if !boundary_found_in_iteration && best_boundary.is_none() {
-                break;
+    /// The first boundary a custom split rule puts in `text` after some content, and whether
+    /// `lookahead` bytes follow the match.
+    fn custom_split(&self, text: &str, lookahead: usize) -> Option<(usize, bool)> {
+        self.custom_splits
+            .iter()
+            .filter_map(|(pattern, action)| {
+                pattern.find_iter(text).find_map(|m| {
+                    let at = match action {
+                        BoundaryAction::SplitBefore => m.start(),
+                        _ => m.end(),
+                    };
+                    let confirmed = text.len() >= m.end() + lookahead;
+                    (!text[..at].trim().is_empty()).then_some((at, confirmed))
+                })
+            })
+            .min_by_key(|&(at, _)| at)
+    }
+
+    /// Whether a no-split rule of `custom_boundaries` matches a span of `text` that contains or
+    /// ends at `position`, the end of a candidate boundary's punctuation.
+    fn inside_no_split(&self, text: &str, position: usize) -> bool {
+        self.custom_no_splits.iter().any(|pattern| {
+            pattern
+                .find_iter(text)
+                .take_while(|m| m.start() < position)
+                .any(|m| m.end() >= position)
+        })
+    }
+
+    /// The first boundary found by the script rules ([`scripts`]), at a line break in chat and
+    /// log mode, or after emoji or a standalone emoji or interjection message in chat mode.
+    /// Script and emoji boundaries inside an open quote do not count. Looks at `buffer[from..]`
+    /// and returns a position in it.
+    fn script_boundary(&self, from: usize) -> Option<(usize, BoundaryRule)> {
+        let text = &self.buffer[from..];
+        let language = &self.options.language;
+        let script = if self.options.mixed_scripts {
+            mixed_script_boundary(language, text)
+        } else {
+            script_boundary(language, text)
+        }
+        .map(|end| (end, BoundaryRule::ScriptRule));
+        let chat = self.options.mode == Mode::Chat;
+        let emoji = chat
+            .then(|| chat::emoji_boundary(text))
+            .flatten()
+            .map(|end| (end, BoundaryRule::Emoji));
+        // Stray quotes are common in chats and logs; a record ends at its line break regardless
+        let line = (self.options.mode.is_line_based())
+            .then(|| chat::line_break(text))
+            .flatten()
+            .map(|end| (end, BoundaryRule::LineBreak));
+        let standalone = chat
+            .then(|| chat::standalone_boundary(text))
+            .flatten()
+            .map(|end| (end, BoundaryRule::Standalone));
+        // A blank line ends a sentence without punctuation, such as a heading
+        let paragraph = (self.options.unit == SegmentUnit::Nested)
+            .then(|| paragraph_break(text))
+            .flatten()
+            .map(|end| (end, BoundaryRule::ParagraphBreak));
+        let quoted = [script, emoji]
+            .into_iter()
+            .flatten()
+            .filter(|&(end, _)| !self.inside_quote(&self.buffer[..from + end]));
+        quoted
+            .chain(line)
+            .chain(standalone)
+            .chain(paragraph)
+            .min_by_key(|&(end, _)| end)
+    }
+
+    /// Whether a boundary between `before` and `after`, found by the language rules, is ruled
+    /// out by the abbreviation lists or the [`Protections`](crate::protect::Protections).
+    fn is_protected(&self, before: &str, after: &str) -> bool {
+        let protections = &self.options.protections;
+        self.ends_with_extra_abbreviation(before, after)
+            || (protections.citations && protect::inside_citation(before))
+            || protect::protects_initial(before, after, protections.initials)
+            || protect::protects_roman_numeral(before, protections.roman_numerals)
+    }
+
+    /// The citation following a boundary, if the protections keep it with the sentence.
+    fn trailing_citation(&self, after: &str) -> Citation {
+        if self.options.protections.citations {
+            protect::trailing_citation(after)
+        } else {
+            Citation::None
+        }
+    }
+
+    /// Whether `candidate` ends in one of the `extra_abbreviations`, the `shared_abbreviations`
+    /// or the built-in ones of the language's script, so the period after it does not end a
+    /// sentence that goes on with `after`.
+    fn ends_with_extra_abbreviation(&self, candidate: &str, after: &str) -> bool {
+        let shared = self.options.shared_abbreviations.as_ref();
+        let builtin = scripts::abbreviations(&self.options.language);
+        if self.options.extra_abbreviations.is_empty() && shared.is_none() && builtin.is_empty() {
+            return false;
+        }
+        let Some(word) = last_word(candidate.trim_end()).strip_suffix('.') else {
+            return false;
+        };
+        if self.is_removed_abbreviation(word) {
+            return false;
+        }
+        self.options
+            .extra_abbreviations
+            .iter()
+            .any(|abbreviation| abbreviation.strip_suffix('.').unwrap_or(abbreviation) == word)
+            || shared.is_some_and(|shared| shared.applies(word, after))
+            || builtin.contains(&word)
+    }
+
+    /// Whether `word` (without its period) is one of the `removed_abbreviations`.
+    fn is_removed_abbreviation(&self, word: &str) -> bool {
+        self.options
+            .removed_abbreviations
+            .iter()
+            .any(|abbreviation| abbreviation.strip_suffix('.').unwrap_or(abbreviation) == word)
+    }
+
+    /// The boundary after a period that follows one of the `removed_abbreviations`, which the
+    /// language rules may still treat as an abbreviation: it ends the sentence if whitespace
+    /// follows.
+    fn removed_abbreviation_boundary(&self, text: &str, mtch: &regex::Match) -> Option<usize> {
+        if self.options.removed_abbreviations.is_empty()
+            || !mtch.as_str().starts_with('.')
+            || !self.is_removed_abbreviation(last_word(&text[..mtch.start()]))
+        {
+            return None;
+        }
+        let rest = &text[mtch.end()..];
+        let after_space = rest.trim_start();
+        (after_space.len() < rest.len()).then(|| text.len() - after_space.len())
+    }
+
+    /// Whether the end of `before`, the buffered text up to a candidate boundary, lies inside a
+    /// quote or bracket opened no more than `max_quote_span` bytes earlier.
+    fn inside_quote(&self, before: &str) -> bool {
+        let span = self.options.max_quote_span;
+        if span == 0 {
+            return false;
+        }
+        let mut quotes = self.quotes.clone();
+        quotes.scan(before, self.consumed);
+        let boundary = self.consumed + before.len();
+        quotes
+            .outermost()
+            .is_some_and(|opened| boundary.saturating_sub(opened) <= span)
+    }
+
+    /// Code of the language whose rules are in use.
+    pub fn language(&self) -> &str {
+        &self.options.language
+    }
+
+    /// Bytes of text currently buffered, waiting for a boundary or more context.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// The text currently buffered, e.g. to preview the sentence being written.
+    pub fn buffered_text(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Discards the buffered text and starts a new stream: offsets, lines and sentence indices
+    /// count from the beginning again.
+    pub fn reset(&mut self) {
+        if let Some(pool) = &self.options.memory_pool {
+            pool.release(self.reserved);
+        }
+        self.reserved = 0;
+        self.buffer.clear();
+        self.ready.clear();
+        self.decoder = Utf8Decoder::for_options(&self.options);
+        self.quotes = QuoteState::new(self.options.max_quote_depth, self.options.quote_recovery);
+        self.consumed = 0;
+        self.consumed_chars = 0;
+        self.line = 1;
+        self.column = 1;
+        self.next_index = 0;
+        self.paragraph = 0;
+        if let Some((backend, spans)) = self.initial_rules.take() {
+            self.backend = backend;
+            self.spans = spans;
+            self.options.language = self.initial_language.clone();
+        }
+        self.detecting = self.auto_language;
+    }
+
+    /// Detects the language of the buffered text, for `language: "auto"`, and switches to its
+    /// rules. If it cannot be told or has no rules, the current ones stay.
+    fn detect_language(&mut self) {
+        self.detecting = false;
+        let Some(detected) = detect_language(&self.buffer) else {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(language = %self.options.language, "language not detected");
+            return;
+        };
+        match self.switch_language(detected) {
+            Ok(()) => {
+                #[cfg(feature = "tracing")]
+                tracing::info!(language = %self.options.language, "detected language");
             }
-            if completed_sentences.is_empty() && best_boundary.is_none() {
-                break;
+            Err(_) => {
+                if let Some(warnings) = &self.options.warnings {
+                    warnings.report(
+                        WarningKind::LanguageFallback,
+                        self.consumed,
+                        format!(
+                            "no rules for detected language '{}', using '{}'",
+                            detected, self.options.language
+                        ),
+                    );
+                }
             }
         }
+    }
+
+    /// Under [`SentenceLanguage::Switch`], switches to the rules of the language of
+    /// `buffer[..end]` if it is another one. Returns whether the rules changed.
+    fn follow_sentence_language(&mut self, end: usize) -> bool {
+        if self.options.sentence_language != SentenceLanguage::Switch {
+            return false;
+        }
+        let Some(detected) = self.detect_sentence_language(&self.buffer[..end]) else {
+            return false;
+        };
+        if detected == self.options.language {
+            return false;
+        }
+        let previous = self.options.language.clone();
+        if self.switch_language(detected).is_err() || self.options.language == previous {
+            return false;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(from = %previous, to = %self.options.language, "sentence language changed");
+        true
+    }
 
-        Ok(completed_sentences)
+    /// The language reported for the sentence `text`: the detected one, or, if it is too short
+    /// to tell, the language whose rules segmented it.
+    fn sentence_language(&self, text: &str) -> Option<String> {
+        if self.options.sentence_language == SentenceLanguage::Off {
+            return None;
+        }
+        let detected = self
+            .detect_sentence_language(text)
+            .unwrap_or(&self.options.language);
+        Some(detected.to_string())
+    }
+
+    /// The language of the sentence `text` among the `sentence_languages`, if it can be told.
+    fn detect_sentence_language(&self, text: &str) -> Option<&'static str> {
+        #[cfg(feature = "detect")]
+        return crate::detect::detect_sentence_language(text, &self.options.sentence_languages);
+        #[cfg(not(feature = "detect"))]
+        {
+            let _ = text;
+            None
+        }
+    }
+
+    /// Replaces the backend and protected spans with those of `language`, keeping the initial
+    /// ones for a [`reset`](Self::reset).
+    fn switch_language(&mut self, language: &str) -> Result<()> {
+        let mut options = self.options.clone();
+        options.language = language.to_string();
+        let backend = crate::backend::for_options(&mut options)?;
+        let spans = span_patterns(&options)?;
+        let previous = (
+            std::mem::replace(&mut self.backend, backend),
+            std::mem::replace(&mut self.spans, spans),
+        );
+        self.initial_rules.get_or_insert(previous);
+        self.options.language = options.language;
+        Ok(())
+    }
+
+    /// Detects the language now if it is still pending, because the input ends or pauses before
+    /// `detection_window` bytes arrived.
+    fn settle_language(&mut self) {
+        if self.detecting && !self.buffer.trim().is_empty() {
+            self.detect_language();
+        }
+    }
+
+    /// Removes the buffered text without segmenting it, e.g. to persist it when the input is
+    /// abandoned. Offsets of later sentences still count it.
+    pub fn take_pending(&mut self) -> String {
+        let pending = std::mem::take(&mut self.buffer);
+        self.advance(&pending);
+        pending
     }
 
+    /// Emits all buffered text as one final sentence, subject to the
+    /// [`tail_policy`](SegmentOptions::tail_policy). Prefer [`finish`](Self::finish), which
+    /// first splits off the complete sentences still waiting for lookahead.
     pub fn flush(&mut self) -> Result<Option<String>> {
-        if self.buffer.is_empty() {
-            Ok(None)
+        Ok(self.flush_segment()?.map(|segment| segment.text))
+    }
+
+    /// Like [`flush`](Self::flush), but returns the remaining text with its stream offsets.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(buffered = self.buffer.len()))
+    )]
+    pub fn flush_segment(&mut self) -> Result<Option<Segment>> {
+        self.settle_language();
+        if self.buffer.trim_matches(' ').is_empty() {
+            self.discard_buffer();
+            return Ok(None);
+        }
+        if self.options.tail_policy != TailPolicy::Drop {
+            self.audit_boundary(self.buffer.len(), BoundaryRule::EndOfInput, 0);
+        }
+        match self.options.tail_policy {
+            TailPolicy::Emit => Ok(Some(self.take_segment(self.buffer.len()))),
+            TailPolicy::Drop => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(bytes = self.buffer.len(), "dropping incomplete tail");
+                self.discard_buffer();
+                Ok(None)
+            }
+            TailPolicy::EmitMarkedIncomplete => {
+                let mut segment = self.take_segment(self.buffer.len());
+                segment.incomplete = true;
+                Ok(Some(segment))
+            }
+        }
+    }
+
+    /// Emits everything buffered because the input paused for
+    /// [`flush_after`](SegmentOptions::flush_after): the sentences still waiting for lookahead,
+    /// then the unfinished one, whatever the tail policy (it is marked incomplete under
+    /// [`TailPolicy::EmitMarkedIncomplete`]). Unlike [`finish`](Self::finish), more input may
+    /// follow.
+    pub fn flush_idle(&mut self) -> Result<Vec<Segment>> {
+        self.settle_language();
+        let mut segments = self.process_buffer(0)?;
+        if self.buffer.trim_matches(' ').is_empty() {
+            return Ok(segments);
+        }
+        self.audit_boundary(self.buffer.len(), BoundaryRule::Idle, 0);
+        let mut segment = self.take_segment(self.buffer.len());
+        segment.incomplete = self.options.tail_policy == TailPolicy::EmitMarkedIncomplete;
+        segments.push(segment);
+        Ok(segments)
+    }
+
+    /// Ends the input: no more text is coming, so every boundary in the buffer is accepted
+    /// without waiting for lookahead, and the remaining fragment is handled by the
+    /// [`tail_policy`](SegmentOptions::tail_policy).
+    pub fn finish(&mut self) -> Result<Vec<String>> {
+        Ok(self
+            .finish_segments()?
+            .into_iter()
+            .map(|segment| segment.text)
+            .collect())
+    }
+
+    /// Like [`finish`](Self::finish), but returns each sentence with its stream offsets.
+    pub fn finish_segments(&mut self) -> Result<Vec<Segment>> {
+        Ok(self.finish_with_tail()?.0)
+    }
+
+    /// Segments `text` as a complete document, for callers that have all of it at once: the
+    /// segmenter is [`reset`](Self::reset), fed `text` in bounded slices (so `max_buffer` limits
+    /// the pending text, not the document) and finished. Offsets count from the start of `text`,
+    /// and the segmenter can be reused for the next document.
+    pub fn segment_all(&mut self, text: &str) -> Result<Vec<String>> {
+        Ok(self
+            .segment_all_segments(text)?
+            .into_iter()
+            .map(|segment| segment.text)
+            .collect())
+    }
+
+    /// Like [`segment_all`](Self::segment_all), but returns each sentence with its offsets.
+    pub fn segment_all_segments(&mut self, text: &str) -> Result<Vec<Segment>> {
+        self.reset();
+        let mut segments = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let mut split = rest.len().min(DOCUMENT_CHUNK);
+            while !rest.is_char_boundary(split) {
+                split += 1;
+            }
+            let (chunk, tail) = rest.split_at(split);
+            segments.extend(self.feed_segments(chunk)?);
+            rest = tail;
+        }
+        segments.extend(self.finish_segments()?);
+        Ok(segments)
+    }
+
+    /// [`finish_segments`](Self::finish_segments), also returning the text of the trailing
+    /// fragment, whether or not the tail policy emitted it.
+    pub(crate) fn finish_with_tail(&mut self) -> Result<(Vec<Segment>, Option<String>)> {
+        if let Some(rest) = self.decoder.finish()? {
+            self.push_chunk(&rest)?;
+        }
+        self.settle_language();
+        let mut segments = self.process_buffer(0)?;
+        let tail = Some(self.buffer.trim_matches(' '))
+            .filter(|tail| !tail.is_empty())
+            .map(str::to_string);
+        segments.extend(self.flush_segment()?);
+        Ok((segments, tail))
+    }
+
+    /// Reports the boundary at `buffer[end]`, about to end the next segment, to the audit sink.
+    fn audit_boundary(&self, end: usize, rule: BoundaryRule, available: usize) {
+        let Some(audit) = &self.options.audit else {
+            return;
+        };
+        let required = self.options.lookahead;
+        let confidence = match rule {
+            BoundaryRule::NumberedReference | BoundaryRule::EndOfInput => 1.0,
+            _ if required == 0 => 1.0,
+            _ => 0.5 + 0.5 * available.min(required) as f32 / required as f32,
+        };
+        audit.record(Boundary {
+            index: self.next_index,
+            offset: self.consumed + end,
+            rule,
+            lookahead: available,
+            confidence,
+        });
+    }
+
+    /// Drains `buffer[..end]` and turns it into a trimmed, positioned segment.
+    fn take_segment(&mut self, end: usize) -> Segment {
+        let raw: String = self.buffer.drain(..end).collect();
+        // A paragraph goes without the blank lines around it
+        let paragraphs = self.options.unit == SegmentUnit::Paragraph;
+        let mut leading = match (paragraphs, self.options.keep_indentation) {
+            (false, true) => 0,
+            (false, false) => raw.len() - raw.trim_start_matches(SPACES).len(),
+            (true, true) => {
+                let content = raw.len() - raw.trim_start().len();
+                raw[..content].rfind('\n').map_or(0, |newline| newline + 1)
+            }
+            (true, false) => raw.len() - raw.trim_start().len(),
+        };
+        // Log records start with a time stamp and level that are not part of the message
+        if let Some(prefix) = self.log_prefix.as_ref().filter(|_| self.column == 1) {
+            leading += logs::prefix_len(prefix, &raw[leading..]);
+        }
+        let text = if paragraphs {
+            raw[leading..].trim_end()
         } else {
-            let last_sentence = std::mem::take(&mut self.buffer);
-            Ok(Some(last_sentence.trim_matches(' ').to_string()))
+            raw[leading..].trim_end_matches(SPACES)
+        }
+        .to_string();
+
+        let start = self.consumed + leading;
+        let char_start = self.consumed_chars + raw[..leading].chars().count();
+        // `leading` is spaces, blank lines before a paragraph, and a log prefix
+        let (line, column) = match raw[..leading].rfind('\n') {
+            Some(newline) => (
+                self.line + raw[..leading].matches('\n').count(),
+                raw[newline + 1..leading].chars().count() + 1,
+            ),
+            None => (self.line, self.column + raw[..leading].chars().count()),
+        };
+        let limit = self.options.long_sentence_warning;
+        if let Some(warnings) = self.options.warnings.as_ref().filter(|_| limit > 0) {
+            if text.len() > limit {
+                warnings.report(
+                    WarningKind::LongSentence,
+                    start,
+                    format!("sentence of {} bytes exceeds {}", text.len(), limit),
+                );
+            }
+        }
+        let end = start + text.len();
+        let char_end = char_start + text.chars().count();
+        let language = self.sentence_language(&text);
+        let text = match &self.options.post_process {
+            Some(post_process) => post_process.apply(text),
+            None => text,
+        };
+        let segment = Segment {
+            index: self.next_index,
+            start,
+            end,
+            char_start,
+            char_end,
+            line,
+            column,
+            text,
+            incomplete: false,
+            language,
+            paragraph: (self.options.unit == SegmentUnit::Nested).then_some(self.paragraph),
+        };
+        if ends_paragraph(&raw) {
+            self.paragraph += 1;
+        }
+        self.advance(&raw);
+        self.next_index += 1;
+        segment
+    }
+
+    /// Empties the buffer without emitting it.
+    fn discard_buffer(&mut self) {
+        let discarded = std::mem::take(&mut self.buffer);
+        self.advance(&discarded);
+    }
+
+    /// Drops `buffer[..end]` without emitting it.
+    fn discard_buffer_to(&mut self, end: usize) {
+        let discarded: String = self.buffer.drain(..end).collect();
+        self.advance(&discarded);
+    }
+
+    /// Moves the stream position past `text`, which has left the buffer.
+    fn advance(&mut self, text: &str) {
+        if let Some(pool) = &self.options.memory_pool {
+            let released = text.len().min(self.reserved);
+            pool.release(released);
+            self.reserved -= released;
+        }
+        let span = self.options.max_quote_span;
+        if span > 0 {
+            self.quotes.scan(text, self.consumed);
+            // A quote open for longer than that was never closed
+            self.quotes
+                .expire((self.consumed + text.len()).saturating_sub(span));
+        }
+        self.consumed += text.len();
+        let chars = text.chars().count();
+        self.consumed_chars += chars;
+        match text.rfind('\n') {
+            Some(last_newline) => {
+                self.line += text.matches('\n').count();
+                self.column = 1 + text[last_newline + 1..].chars().count();
+            }
+            None => self.column += chars,
+        }
+    }
+}
+
+impl SentenceSegmenter for Segmenter {
+    fn feed_segments(&mut self, chunk: &str) -> Result<Vec<Segment>> {
+        Segmenter::feed_segments(self, chunk)
+    }
+
+    fn finish_segments(&mut self) -> Result<Vec<Segment>> {
+        Segmenter::finish_segments(self)
+    }
+
+    fn reset(&mut self) {
+        Segmenter::reset(self)
+    }
+
+    fn finish_with_tail(&mut self) -> Result<(Vec<Segment>, Option<String>)> {
+        Segmenter::finish_with_tail(self)
+    }
+
+    fn flush_idle(&mut self) -> Result<Vec<Segment>> {
+        Segmenter::flush_idle(self)
+    }
+
+    fn language(&self) -> &str {
+        Segmenter::language(self)
+    }
+}
+
+impl Drop for Segmenter {
+    fn drop(&mut self) {
+        if let Some(pool) = &self.options.memory_pool {
+            pool.release(self.reserved);
         }
     }
 }
+
+/// The protected-span patterns `options` select.
+fn span_patterns(options: &SegmentOptions) -> Result<Vec<Regex>> {
+    let mut spans = protect::span_patterns(&options.protections, &options.language)?;
+    if options.mode == Mode::Chat {
+        spans.extend(
+            chat::PROTECTED_PATTERNS
+                .iter()
+                .map(|pattern| Regex::new(pattern).expect("valid chat pattern")),
+        );
+    }
+    Ok(spans)
+}
+
+/// End of the first blank line after some text, including the line breaks and indentation
+/// around it: where a paragraph ends.
+fn paragraph_break(text: &str) -> Option<usize> {
+    let mut search = text.len() - text.trim_start().len();
+    while let Some(found) = text[search..].find('\n') {
+        let newline = search + found;
+        let next_line = text[newline + 1..].trim_start_matches([' ', '\t', '\r']);
+        if next_line.starts_with('\n') {
+            return Some(text.len() - text[newline..].trim_start().len());
+        }
+        search = newline + 1;
+    }
+    None
+}
+
+/// Whether `text`, the raw text of a segment, has a paragraph break after its sentence.
+fn ends_paragraph(text: &str) -> bool {
+    let content = text.trim_end();
+    !content.trim_start().is_empty() && text[content.len()..].matches('\n').count() >= 2
+}
+
+/// The language of `text`, if it is long enough to tell.
+fn detect_language(text: &str) -> Option<&'static str> {
+    #[cfg(feature = "detect")]
+    return crate::detect::detect_language(text);
+    #[cfg(not(feature = "detect"))]
+    {
+        let _ = text;
+        None
+    }
+}
+
+/// The language a `language: "auto"` segmenter starts with and keeps if detection fails.
+fn default_detected(options: &SegmentOptions) -> String {
+    options
+        .fallback_language
+        .clone()
+        .unwrap_or_else(|| "en".to_string())
+}
+
+/// The word at the end of `text`, without leading punctuation such as an opening quote.
+fn last_word(text: &str) -> &str {
+    text.rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+}
+
+/// Spaces trimmed from both ends of a sentence: ASCII and the full-width ideographic space.
+const SPACES: [char; 2] = [' ', '\u{3000}'];
+
+/// Punctuation ending a clause when followed by whitespace, where a sentence over
+/// [`max_sentence_len`](SegmentOptions::max_sentence_len) is preferably split.
+const CLAUSE_MARKS: [char; 5] = [',', ';', ':', '—', '–'];
+
+/// Full-width clause punctuation, which needs no whitespace after it.
+const FULL_WIDTH_CLAUSE_MARKS: [char; 4] = ['、', '，', '；', '：'];
+
+/// Size of the slices a whole document is fed to the segmenter in, in bytes.
+const DOCUMENT_CHUNK: usize = 4096;
+
+/// Segments a complete document with a new segmenter; see [`Segmenter::segment_all`].
+#[cfg(any(
+    feature = "tokio",
+    feature = "python",
+    feature = "align",
+    feature = "compare",
+    feature = "comments"
+))]
+pub(crate) fn segment_document(text: &str, options: SegmentOptions) -> Result<Vec<Segment>> {
+    Segmenter::new(options)?.segment_all_segments(text)
+}
@@ -0,0 +1,127 @@
+//! Minimal HTTP endpoint for server modes without an HTTP router of their own (gRPC, WebSocket):
+//! `GET /metrics`, `GET /healthz` and `GET /readyz`. Only `/metrics` requires a token when
+//! authentication is enabled.
+
+use crate::error::Result;
+use crate::server::auth::Auth;
+use crate::server::health::Health;
+use crate::server::metrics::Metrics;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+/// How long a client gets to send its request line and headers.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest request head accepted; the endpoints take no body.
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Serves the admin endpoints on `addr`. Fails only if it cannot bind to it.
+pub async fn serve<A: ToSocketAddrs>(
+    addr: A,
+    metrics: Arc<Metrics>,
+    health: Health,
+    auth: Auth,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    let health = Arc::new(health);
+    loop {
+        let (mut stream, _peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                super::accept_failed(&e).await;
+                continue;
+            }
+        };
+        let metrics = metrics.clone();
+        let health = health.clone();
+        let auth = auth.clone();
+        tokio::spawn(async move {
+            // One request per connection; only the request line and headers matter
+            let head = tokio::time::timeout(REQUEST_TIMEOUT, read_head(&mut stream)).await;
+            let response = match head {
+                Ok(Some(request)) => route(&request, &metrics, &health, &auth),
+                Ok(None) => respond("400 Bad Request", "text/plain", ""),
+                Err(_) => respond("408 Request Timeout", "text/plain", ""),
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
+
+/// The response to one complete request head.
+fn route(request: &str, metrics: &Metrics, health: &Health, auth: &Auth) -> String {
+    let Some((method, path)) = request_line(request) else {
+        return respond("400 Bad Request", "text/plain", "");
+    };
+    match (method == "GET", path) {
+        (true, "/metrics")
+            if !auth.check(
+                header(request, "authorization"),
+                header(request, "x-api-key"),
+            ) =>
+        {
+            respond("401 Unauthorized", "text/plain", "")
+        }
+        (true, "/metrics") => respond("200 OK", "text/plain; version=0.0.4", &metrics.render()),
+        (true, "/healthz") => probe_response(health.liveness()),
+        (true, "/readyz") => probe_response(health.readiness()),
+        _ => respond("404 Not Found", "text/plain", ""),
+    }
+}
+
+/// Reads up to the blank line ending the headers. `None` if the connection ends first or the
+/// head is larger than [`MAX_REQUEST_HEAD`].
+async fn read_head<R: AsyncRead + Unpin>(stream: &mut R) -> Option<String> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_HEAD {
+            return None;
+        }
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    String::from_utf8(head).ok()
+}
+
+/// Method and path of the request line `METHOD TARGET HTTP/x.y`, without the target's query.
+fn request_line(request: &str) -> Option<(&str, &str)> {
+    let line = request.lines().next()?;
+    let mut parts = line.split(' ');
+    let (method, target, version) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() || !version.starts_with("HTTP/") || !target.starts_with('/') {
+        return None;
+    }
+    let path = target.split_once('?').map_or(target, |(path, _query)| path);
+    Some((method, path))
+}
+
+fn probe_response(result: crate::server::health::ProbeResult) -> String {
+    let status = if result.ok {
+        "200 OK"
+    } else {
+        "503 Service Unavailable"
+    };
+    respond(status, "application/json", &result.body)
+}
+
+/// Value of the first header called `name` (case-insensitive) in a raw request.
+fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+    request.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+fn respond(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
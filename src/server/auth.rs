@@ -0,0 +1,85 @@
+//! Bearer-token authentication for the network server modes.
+//!
+//! Clients send `Authorization: Bearer <token>` (or `X-API-Key: <token>`). Tokens come from the
+//! command line or from a file with one token per line; blank lines and `#` comments are
+//! ignored. Health probes stay open so orchestrators can reach them without credentials.
+
+use crate::error::Result;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Accepted API tokens. The default accepts every request.
+#[derive(Debug, Clone, Default)]
+pub struct Auth {
+    tokens: Option<Arc<HashSet<String>>>,
+}
+
+impl Auth {
+    /// No authentication.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Accepts any of `tokens`.
+    pub fn tokens<I, T>(tokens: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self {
+            tokens: Some(Arc::new(tokens.into_iter().map(Into::into).collect())),
+        }
+    }
+
+    /// Reads tokens from `path`, one per line.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(Self::tokens(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#')),
+        ))
+    }
+
+    /// Adds the tokens of `other` to this set.
+    pub fn merge(self, other: Auth) -> Self {
+        match (self.tokens, other.tokens) {
+            (Some(a), Some(b)) => Self::tokens(a.iter().chain(b.iter()).cloned()),
+            (a, b) => Self { tokens: a.or(b) },
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.tokens.is_some()
+    }
+
+    /// Checks the `Authorization` and `X-API-Key` header values of a request.
+    pub fn check(&self, authorization: Option<&str>, api_key: Option<&str>) -> bool {
+        let Some(tokens) = &self.tokens else {
+            return true;
+        };
+        let bearer = authorization.and_then(|value| {
+            let (scheme, token) = value.trim().split_once(' ')?;
+            scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+        });
+        [bearer, api_key.map(str::trim)]
+            .into_iter()
+            .flatten()
+            .any(|candidate| {
+                tokens
+                    .iter()
+                    .any(|token| constant_time_eq(token, candidate))
+            })
+    }
+}
+
+/// Compares two strings without returning early on the first differing byte.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
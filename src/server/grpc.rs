@@ -0,0 +1,263 @@
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use crate::segmenter::{segment_document, Segment, Segmenter};
+use crate::server::auth::Auth;
+use crate::server::limits::{Limits, SessionPermit};
+use crate::server::metrics::{MeteredSession, Metrics};
+use crate::server::tenancy::{Overrides, Policy};
+use futures::{Stream, StreamExt};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Instant;
+use tonic::{Request, Response, Status, Streaming};
+
+/// Types generated from `proto/segmenter.proto`.
+pub mod proto {
+    tonic::include_proto!("async_tqsm.v1");
+}
+
+use proto::segmentation_server::{Segmentation, SegmentationServer};
+use proto::{Document, SegmentedDocument, Sentence, TextChunk};
+
+/// gRPC implementation of the `Segmentation` service; every call gets its own segmenter.
+///
+/// Calls may override the default options with `x-segment-*` metadata, see
+/// [`tenancy`](crate::server::tenancy).
+#[derive(Debug, Clone)]
+pub struct SegmentationService {
+    options: SegmentOptions,
+    metrics: Arc<Metrics>,
+    limits: Limits,
+    policy: Policy,
+}
+
+impl SegmentationService {
+    pub fn new(options: SegmentOptions) -> Self {
+        Self::with_metrics(options, Metrics::new())
+    }
+
+    /// Like [`new`](Self::new), recording every call into `metrics`.
+    pub fn with_metrics(options: SegmentOptions, metrics: Arc<Metrics>) -> Self {
+        Self {
+            options,
+            metrics,
+            limits: Limits::default(),
+            policy: Policy::default(),
+        }
+    }
+
+    /// Enforces `limits` on every call; calls beyond the session limit fail with
+    /// `RESOURCE_EXHAUSTED`.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        limits.clamp(&mut self.options);
+        self.limits = limits;
+        self
+    }
+
+    /// Restricts per-call overrides to `policy`; calls outside it fail with `INVALID_ARGUMENT`.
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The options for one call: the defaults with the call's `x-segment-*` metadata applied.
+    fn call_options<T>(&self, request: &Request<T>) -> std::result::Result<SegmentOptions, Status> {
+        let metadata = request.metadata();
+        let overrides =
+            Overrides::from_headers(|name| metadata.get(name).and_then(|v| v.to_str().ok()))
+                .map_err(to_status)?;
+        if overrides.format.is_some() {
+            return Err(Status::invalid_argument(
+                "the output format cannot be changed over gRPC",
+            ));
+        }
+        let mut options = self
+            .policy
+            .resolve(&self.options, &overrides)
+            .map_err(to_status)?;
+        self.limits.clamp(&mut options);
+        Ok(options)
+    }
+
+    fn open_session(&self) -> std::result::Result<SessionPermit, Status> {
+        self.limits
+            .try_open_session()
+            .ok_or_else(|| Status::resource_exhausted("too many sessions"))
+    }
+
+    /// Wraps the service for registration with a `tonic` server.
+    pub fn into_server(self) -> SegmentationServer<Self> {
+        SegmentationServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl Segmentation for SegmentationService {
+    type SegmentStream = Pin<Box<dyn Stream<Item = std::result::Result<Sentence, Status>> + Send>>;
+
+    async fn segment(
+        &self,
+        request: Request<Streaming<TextChunk>>,
+    ) -> std::result::Result<Response<Self::SegmentStream>, Status> {
+        let permit = self.open_session()?;
+        let options = self.call_options(&request)?;
+        let mut session = MeteredSession::new(self.metrics.clone(), options.language.clone());
+        let mut segmenter = Segmenter::new(options).map_err(to_status)?;
+        let mut throttle = self.limits.throttle();
+        let mut chunks = request.into_inner();
+
+        let sentences = async_stream::try_stream! {
+            let _permit = permit;
+            while let Some(chunk) = chunks.next().await {
+                let chunk = chunk?;
+                throttle.wait(chunk.text.len()).await;
+                for segment in session.feed(&mut segmenter, &chunk.text).map_err(to_status)? {
+                    yield to_sentence(segment);
+                }
+            }
+            for segment in session.finish(&mut segmenter).map_err(to_status)? {
+                yield to_sentence(segment);
+            }
+        };
+        Ok(Response::new(Box::pin(sentences)))
+    }
+
+    async fn segment_document(
+        &self,
+        request: Request<Document>,
+    ) -> std::result::Result<Response<SegmentedDocument>, Status> {
+        let _permit = self.open_session()?;
+        let options = self.call_options(&request)?;
+        let language = options.language.clone();
+        let text = request.into_inner().text;
+        let input_len = text.len();
+        let started = Instant::now();
+        // A large document would hold up the other calls on this worker thread
+        let segments = tokio::task::spawn_blocking(move || segment_document(&text, options))
+            .await
+            .map_err(|e| SegmenterError::StreamError(e.to_string()))
+            .and_then(|segmented| segmented)
+            .map_err(|e| {
+                self.metrics.record_error(&e);
+                to_status(e)
+            })?;
+        self.metrics
+            .record_document(&language, input_len, segments.len(), started.elapsed());
+        Ok(Response::new(SegmentedDocument {
+            sentences: segments.into_iter().map(to_sentence).collect(),
+        }))
+    }
+}
+
+/// Serves the `Segmentation` service on `addr` until the transport fails.
+pub async fn serve(addr: SocketAddr, options: SegmentOptions) -> Result<()> {
+    serve_with(
+        addr,
+        options,
+        Metrics::new(),
+        Limits::default(),
+        Auth::disabled(),
+        Policy::default(),
+    )
+    .await
+}
+
+/// Like [`serve`], recording every call into `metrics` and enforcing `limits`, `auth` and the
+/// per-call option `policy`. Calls without a valid token fail with `UNAUTHENTICATED`.
+pub async fn serve_with(
+    addr: SocketAddr,
+    options: SegmentOptions,
+    metrics: Arc<Metrics>,
+    limits: Limits,
+    auth: Auth,
+    policy: Policy,
+) -> Result<()> {
+    let service = SegmentationService::with_metrics(options, metrics)
+        .with_limits(limits)
+        .with_policy(policy);
+    tonic::transport::Server::builder()
+        .add_service(SegmentationServer::with_interceptor(
+            service,
+            token_interceptor(auth),
+        ))
+        .serve(addr)
+        .await
+        .map_err(|e| SegmenterError::StreamError(e.to_string()))
+}
+
+/// Like [`serve_with`], over TLS.
+#[cfg(feature = "tls")]
+pub async fn serve_tls(
+    addr: SocketAddr,
+    options: SegmentOptions,
+    metrics: Arc<Metrics>,
+    limits: Limits,
+    auth: Auth,
+    policy: Policy,
+    tls: crate::server::tls::TlsConfig,
+) -> Result<()> {
+    let service = SegmentationService::with_metrics(options, metrics)
+        .with_limits(limits)
+        .with_policy(policy);
+    tonic::transport::Server::builder()
+        .tls_config(tls.tonic_config()?)
+        .map_err(|e| SegmenterError::StreamError(e.to_string()))?
+        .add_service(SegmentationServer::with_interceptor(
+            service,
+            token_interceptor(auth),
+        ))
+        .serve(addr)
+        .await
+        .map_err(|e| SegmenterError::StreamError(e.to_string()))
+}
+
+/// Checks the `authorization` / `x-api-key` metadata of every call against `auth`.
+pub fn token_interceptor(
+    auth: Auth,
+) -> impl FnMut(Request<()>) -> std::result::Result<Request<()>, Status> + Clone {
+    move |request: Request<()>| {
+        let metadata = request.metadata();
+        let authorized = auth.check(
+            metadata.get("authorization").and_then(|v| v.to_str().ok()),
+            metadata.get("x-api-key").and_then(|v| v.to_str().ok()),
+        );
+        if authorized {
+            Ok(request)
+        } else {
+            Err(Status::unauthenticated("missing or invalid token"))
+        }
+    }
+}
+
+fn to_sentence(segment: Segment) -> Sentence {
+    Sentence {
+        index: segment.index as u64,
+        start: segment.start as u64,
+        end: segment.end as u64,
+        text: segment.text,
+        char_start: segment.char_start as u64,
+        char_end: segment.char_end as u64,
+    }
+}
+
+/// Maps `e` to a gRPC status, with its stable [`code`](SegmenterError::code) and
+/// [`kind`](SegmenterError::kind) in the `x-tqsm-error-code` and `x-tqsm-error-kind` metadata.
+fn to_status(e: SegmenterError) -> Status {
+    let mut status = match e {
+        SegmenterError::UnsupportedLanguage(_)
+        | SegmenterError::InvalidOptions(_)
+        | SegmenterError::Utf8Error { .. } => Status::invalid_argument(e.to_string()),
+        SegmenterError::BufferOverflow { .. } | SegmenterError::MemoryBudgetExceeded { .. } => {
+            Status::resource_exhausted(e.to_string())
+        }
+        _ => Status::internal(e.to_string()),
+    };
+    let metadata = status.metadata_mut();
+    metadata.insert("x-tqsm-error-code", e.code().into());
+    metadata.insert(
+        "x-tqsm-error-kind",
+        tonic::metadata::MetadataValue::from_static(e.kind()),
+    );
+    status
+}
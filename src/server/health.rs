@@ -0,0 +1,86 @@
+//! Liveness and readiness probes for the server modes, in the shape Kubernetes expects: `200`
+//! when healthy, `503` otherwise, with a small JSON body explaining the verdict.
+
+use crate::config::SegmentOptions;
+use crate::segmenter::segment_document;
+use std::time::Instant;
+
+/// Text segmented by the readiness probe to check that a segmenter actually works.
+const PROBE_TEXT: &str = "This is a probe. It has two sentences.";
+
+/// Result of a probe: whether it passed and the JSON body to return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeResult {
+    pub ok: bool,
+    pub body: String,
+}
+
+/// Health state of one server process.
+#[derive(Debug, Clone)]
+pub struct Health {
+    options: SegmentOptions,
+    started: Instant,
+}
+
+impl Health {
+    pub fn new(options: SegmentOptions) -> Self {
+        Self {
+            options,
+            started: Instant::now(),
+        }
+    }
+
+    /// Liveness: the process is up and answering.
+    pub fn liveness(&self) -> ProbeResult {
+        ProbeResult {
+            ok: true,
+            body: format!(
+                "{{\"status\":\"ok\",\"uptime_seconds\":{}}}",
+                self.started.elapsed().as_secs()
+            ),
+        }
+    }
+
+    /// Readiness: the default language's rules load and a segmenter built from the server's
+    /// options segments a probe text without error.
+    pub fn readiness(&self) -> ProbeResult {
+        let options = &self.options;
+        let language_loaded = match options.backend {
+            _ if options.language == crate::detect::AUTO_LANGUAGE => cfg!(feature = "detect"),
+            crate::backend::Backend::Icu => cfg!(feature = "icu"),
+            crate::backend::Backend::Tqsm => {
+                crate::scripts::language_rules(&options.language)
+                    .or_else(|| {
+                        let fallback = options.fallback_language.as_deref()?;
+                        crate::scripts::language_rules(fallback)
+                    })
+                    .is_some()
+                    || cfg!(feature = "icu")
+            }
+        };
+        let segmenter_ok =
+            language_loaded && segment_document(PROBE_TEXT, self.options.clone()).is_ok();
+        let ok = language_loaded && segmenter_ok;
+
+        ProbeResult {
+            ok,
+            body: format!(
+                "{{\"status\":\"{}\",\"language\":\"{}\",\"language_loaded\":{},\"segmenter\":\"{}\"}}",
+                if ok { "ok" } else { "unavailable" },
+                json_escape(&self.options.language),
+                language_loaded,
+                if segmenter_ok { "ok" } else { "failing" },
+            ),
+        }
+    }
+}
+
+fn json_escape(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| match c {
+            '"' | '\\' => vec!['\\', c],
+            c if c.is_control() => format!("\\u{:04x}", c as u32).chars().collect(),
+            c => vec![c],
+        })
+        .collect()
+}
@@ -0,0 +1,404 @@
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use crate::segmenter::{segment_document, Segmenter};
+use crate::sentences_stream;
+use crate::server::auth::Auth;
+use crate::server::health::{Health, ProbeResult};
+use crate::server::limits::Limits;
+use crate::server::metrics::{LatencyClock, Metrics};
+use crate::server::tenancy::{Overrides, Policy};
+#[cfg(feature = "tls")]
+use crate::server::tls::{TlsConfig, TlsListener};
+use axum::body::Body;
+use axum::extract::Request;
+use axum::extract::{RawQuery, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::{StreamExt, TryStreamExt};
+use std::convert::Infallible;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio_util::io::StreamReader;
+
+/// Response framing for `POST /segment/stream`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamFormat {
+    /// One JSON string per line (`application/x-ndjson`).
+    Ndjson,
+    /// Server-sent events, one `data:` event per sentence.
+    Sse,
+}
+
+impl FromStr for StreamFormat {
+    type Err = SegmenterError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ndjson" => Ok(Self::Ndjson),
+            "sse" => Ok(Self::Sse),
+            _ => Err(SegmenterError::InvalidOptions(format!(
+                "unknown format '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    defaults: SegmentOptions,
+    metrics: Arc<Metrics>,
+    health: Arc<Health>,
+    limits: Limits,
+    auth: Auth,
+    policy: Policy,
+}
+
+/// Builds the HTTP API around `defaults`:
+///
+/// * `POST /segment` segments the whole request body and returns a JSON array of sentences.
+/// * `POST /segment/stream` segments a (chunked) request body and streams sentences back as
+///   NDJSON or, with `?format=sse` or `Accept: text/event-stream`, as server-sent events.
+/// * `GET /metrics` exposes Prometheus metrics for both endpoints.
+/// * `GET /healthz` and `GET /readyz` are liveness and readiness probes.
+///
+/// Each request may override the defaults through the query string or `X-Segment-*` headers,
+/// see [`tenancy`](crate::server::tenancy).
+pub fn router(defaults: SegmentOptions) -> Router {
+    router_with(
+        defaults,
+        Metrics::new(),
+        Limits::default(),
+        Auth::disabled(),
+        Policy::default(),
+    )
+}
+
+/// Like [`router`], recording into an existing `metrics` registry and enforcing `limits`.
+/// Requests beyond the session limit get `503 Service Unavailable`; the buffer cap also bounds
+/// per-request `max_buffer` overrides. With `auth` enabled, every route except the health
+/// probes answers `401 Unauthorized` without a valid token. Overrides outside `policy` get
+/// `400 Bad Request`.
+pub fn router_with(
+    defaults: SegmentOptions,
+    metrics: Arc<Metrics>,
+    limits: Limits,
+    auth: Auth,
+    policy: Policy,
+) -> Router {
+    let health = Arc::new(Health::new(defaults.clone()));
+    let state = AppState {
+        defaults,
+        metrics,
+        health,
+        limits,
+        auth,
+        policy,
+    };
+    let protected = Router::new()
+        .route("/segment", post(handle_segment))
+        .route("/segment/stream", post(handle_segment_stream))
+        .route("/metrics", get(handle_metrics))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token));
+    Router::new()
+        .merge(protected)
+        .route("/healthz", get(handle_healthz))
+        .route("/readyz", get(handle_readyz))
+        .with_state(state)
+}
+
+/// Serves [`router`] on `addr` until an I/O error occurs.
+pub async fn serve<A: ToSocketAddrs>(addr: A, defaults: SegmentOptions) -> Result<()> {
+    serve_with(
+        addr,
+        defaults,
+        Limits::default(),
+        Auth::disabled(),
+        Policy::default(),
+    )
+    .await
+}
+
+/// Like [`serve`], enforcing `limits`, `auth` and the per-request option `policy`.
+pub async fn serve_with<A: ToSocketAddrs>(
+    addr: A,
+    defaults: SegmentOptions,
+    limits: Limits,
+    auth: Auth,
+    policy: Policy,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    axum::serve(
+        listener,
+        router_with(defaults, Metrics::new(), limits, auth, policy),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Like [`serve_with`], over TLS.
+#[cfg(feature = "tls")]
+pub async fn serve_tls<A: ToSocketAddrs>(
+    addr: A,
+    defaults: SegmentOptions,
+    limits: Limits,
+    auth: Auth,
+    policy: Policy,
+    tls: TlsConfig,
+) -> Result<()> {
+    let listener = TlsListener::new(
+        TcpListener::bind(addr).await?,
+        tls.acceptor()?,
+        limits.clone(),
+    )?;
+    axum::serve(
+        listener,
+        router_with(defaults, Metrics::new(), limits, auth, policy),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn require_token(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    let authorized = state.auth.check(
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok()),
+        headers.get("x-api-key").and_then(|v| v.to_str().ok()),
+    );
+    if authorized {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(serde_json::json!({ "error": "missing or invalid token" })),
+        )
+            .into_response()
+    }
+}
+
+async fn handle_metrics(State(state): State<AppState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+        .into_response()
+}
+
+async fn handle_healthz(State(state): State<AppState>) -> Response {
+    probe_response(state.health.liveness())
+}
+
+async fn handle_readyz(State(state): State<AppState>) -> Response {
+    probe_response(state.health.readiness())
+}
+
+fn probe_response(result: ProbeResult) -> Response {
+    let status = if result.ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        [(header::CONTENT_TYPE, "application/json")],
+        result.body,
+    )
+        .into_response()
+}
+
+async fn handle_segment(
+    State(state): State<AppState>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let Some(_permit) = state.limits.try_open_session() else {
+        return too_many_sessions();
+    };
+    let (options, _) = match request_options(&state, query.as_deref(), &headers) {
+        Ok(resolved) => resolved,
+        Err(e) => return error_response(e),
+    };
+    let language = options.language.clone();
+    let started = Instant::now();
+    let input_len = body.len();
+    // A large document would hold up the other requests on this worker thread
+    let segmented = tokio::task::spawn_blocking(move || segment_document(&body, options))
+        .await
+        .map_err(|e| SegmenterError::StreamError(e.to_string()))
+        .and_then(|segmented| segmented);
+    match segmented {
+        Ok(segments) => {
+            state
+                .metrics
+                .record_document(&language, input_len, segments.len(), started.elapsed());
+            let sentences: Vec<String> = segments.into_iter().map(|s| s.text).collect();
+            Json(sentences).into_response()
+        }
+        Err(e) => {
+            state.metrics.record_error(&e);
+            error_response(e)
+        }
+    }
+}
+
+async fn handle_segment_stream(
+    State(state): State<AppState>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    let Some(permit) = state.limits.try_open_session() else {
+        return too_many_sessions();
+    };
+    let resolved =
+        request_options(&state, query.as_deref(), &headers).and_then(|(options, format)| {
+            Ok((
+                options,
+                stream_format(&state.policy, format.as_deref(), &headers)?,
+            ))
+        });
+    let (options, format) = match resolved {
+        Ok(resolved) => resolved,
+        Err(e) => return error_response(e),
+    };
+    // Fail fast on bad options instead of opening a stream that only carries an error
+    if let Err(e) = Segmenter::new(options.clone()) {
+        state.metrics.record_error(&e);
+        return error_response(e);
+    }
+
+    // The reader hides the segmenter, so only input bytes and sentence latency are recorded here
+    let language = options.language.clone();
+    let clock = Arc::new(Mutex::new(LatencyClock::default()));
+    let metrics = state.metrics.clone();
+    let input_clock = clock.clone();
+    let data = body.into_data_stream().inspect_ok(move |bytes| {
+        input_clock
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .on_input();
+        metrics.record_input(&language, bytes.len(), None);
+    });
+    let reader = state
+        .limits
+        .throttle_reader(StreamReader::new(data.map_err(std::io::Error::other)));
+
+    let language = options.language.clone();
+    let metrics = state.metrics.clone();
+    let sentences = sentences_stream(reader, options).inspect(move |result| {
+        // Captured so the session slot stays taken while the response streams
+        let _permit = &permit;
+        match result {
+            Ok(_) => {
+                let latency = clock
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .on_sentence();
+                metrics.record_sentence(&language, latency);
+            }
+            Err(e) => metrics.record_error(e),
+        }
+    });
+
+    match format {
+        StreamFormat::Ndjson => {
+            let lines = sentences.map(|result| {
+                let value = match result {
+                    Ok(sentence) => serde_json::Value::String(sentence),
+                    Err(e) => error_body(&e),
+                };
+                Ok::<_, Infallible>(format!("{}\n", value))
+            });
+            (
+                [(header::CONTENT_TYPE, "application/x-ndjson")],
+                Body::from_stream(lines),
+            )
+                .into_response()
+        }
+        StreamFormat::Sse => {
+            let events = sentences.map(|result| {
+                Ok::<_, Infallible>(match result {
+                    Ok(sentence) => Event::default().data(sentence),
+                    Err(e) => Event::default()
+                        .event("error")
+                        .data(error_body(&e).to_string()),
+                })
+            });
+            Sse::new(events).into_response()
+        }
+    }
+}
+
+/// The defaults with this request's overrides applied, and the output format it asked for.
+fn request_options(
+    state: &AppState,
+    query: Option<&str>,
+    headers: &HeaderMap,
+) -> Result<(SegmentOptions, Option<String>)> {
+    let mut overrides =
+        Overrides::from_headers(|name| headers.get(name).and_then(|v| v.to_str().ok()))?;
+    if let Some(query) = query {
+        overrides.apply_query(query)?;
+    }
+    let mut options = state.policy.resolve(&state.defaults, &overrides)?;
+    state.limits.clamp(&mut options);
+    Ok((options, overrides.format))
+}
+
+/// `format` if the request named one, otherwise SSE for `Accept: text/event-stream` and NDJSON
+/// for everything else.
+fn stream_format(
+    policy: &Policy,
+    format: Option<&str>,
+    headers: &HeaderMap,
+) -> Result<StreamFormat> {
+    let wants_sse = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"));
+    let requested = format.or(wants_sse.then_some("sse"));
+    match requested {
+        Some(format) => {
+            let parsed = format.parse()?;
+            policy.check_format(format)?;
+            Ok(parsed)
+        }
+        None => Ok(StreamFormat::Ndjson),
+    }
+}
+
+fn too_many_sessions() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({ "error": "too many sessions" })),
+    )
+        .into_response()
+}
+
+fn error_response(e: SegmenterError) -> Response {
+    let status = match e {
+        SegmenterError::UnsupportedLanguage(_)
+        | SegmenterError::InvalidOptions(_)
+        | SegmenterError::Utf8Error { .. } => StatusCode::BAD_REQUEST,
+        SegmenterError::BufferOverflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        SegmenterError::MemoryBudgetExceeded { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(error_body(&e))).into_response()
+}
+
+/// JSON body describing `e`, with its stable [`code`](SegmenterError::code) and
+/// [`kind`](SegmenterError::kind) next to the message.
+fn error_body(e: &SegmenterError) -> serde_json::Value {
+    serde_json::json!({ "error": e.to_string(), "code": e.code(), "kind": e.kind() })
+}
@@ -0,0 +1,225 @@
+//! Resource limits for the server modes, so one misbehaving client cannot exhaust the process:
+//!
+//! * a global cap on concurrent sessions (connections, streaming calls, requests),
+//! * a per-session input rate, enforced by delaying reads rather than dropping data,
+//! * a per-session cap on `max_buffer`, which also bounds per-request overrides,
+//! * a memory budget shared by all sessions, during whose pressure reads are held back.
+
+use crate::config::SegmentOptions;
+use crate::memory::MemoryPool;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Sleep;
+
+/// Limits shared by all sessions of one server. The default imposes no limits.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    sessions: Option<Arc<Semaphore>>,
+    bytes_per_sec: Option<u64>,
+    max_session_buffer: Option<usize>,
+    memory_pool: Option<MemoryPool>,
+}
+
+/// Held for the lifetime of a session; dropping it frees the slot.
+#[derive(Debug)]
+pub struct SessionPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows at most `max` sessions at once; further sessions are refused.
+    pub fn max_sessions(mut self, max: usize) -> Self {
+        self.sessions = Some(Arc::new(Semaphore::new(max)));
+        self
+    }
+
+    /// Limits each session's input to `rate` bytes per second, with bursts of up to one second.
+    pub fn bytes_per_sec(mut self, rate: u64) -> Self {
+        self.bytes_per_sec = Some(rate);
+        self
+    }
+
+    /// Caps every session's `max_buffer` at `max` bytes.
+    pub fn max_session_buffer(mut self, max: usize) -> Self {
+        self.max_session_buffer = Some(max);
+        self
+    }
+
+    /// Accounts the text buffered by all sessions against `pool`. While it is under pressure,
+    /// sessions wait up to [`PRESSURE_WAIT`] before reading more input; what happens when it
+    /// is full is up to the pool's [`MemoryPolicy`](crate::memory::MemoryPolicy).
+    pub fn memory_pool(mut self, pool: MemoryPool) -> Self {
+        self.memory_pool = Some(pool);
+        self
+    }
+
+    /// Claims a session slot, or returns `None` if the server is at capacity.
+    pub fn try_open_session(&self) -> Option<SessionPermit> {
+        match &self.sessions {
+            Some(semaphore) => {
+                semaphore
+                    .clone()
+                    .try_acquire_owned()
+                    .ok()
+                    .map(|permit| SessionPermit {
+                        _permit: Some(permit),
+                    })
+            }
+            None => Some(SessionPermit { _permit: None }),
+        }
+    }
+
+    /// Applies the buffer cap and the memory pool to `options`.
+    pub fn clamp(&self, options: &mut SegmentOptions) {
+        if let Some(max) = self.max_session_buffer {
+            options.max_buffer = options.max_buffer.min(max);
+        }
+        if let Some(pool) = &self.memory_pool {
+            options.memory_pool = Some(pool.clone());
+        }
+    }
+
+    /// A fresh rate limiter for one session's input.
+    pub fn throttle(&self) -> Throttle {
+        Throttle {
+            bucket: self.bytes_per_sec.map(TokenBucket::new),
+            memory_pool: self.memory_pool.clone(),
+        }
+    }
+
+    /// Wraps a session's reader so it is read no faster than the configured rate.
+    pub fn throttle_reader<R>(&self, reader: R) -> ThrottledReader<R> {
+        ThrottledReader {
+            inner: reader,
+            bucket: self.bytes_per_sec.map(TokenBucket::new),
+            sleep: None,
+            memory_pool: self.memory_pool.clone(),
+            pressure_waits: 0,
+        }
+    }
+}
+
+/// How long a session waits for the memory pool to drain before reading anyway.
+pub const PRESSURE_WAIT: Duration = Duration::from_secs(1);
+
+/// Interval at which a waiting session checks the memory pool again.
+const PRESSURE_POLL: Duration = Duration::from_millis(10);
+
+/// Classic token bucket holding at most one second's worth of bytes. Taking more than is
+/// available drives the balance negative; the caller then waits until it is paid back.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last: Instant::now(),
+        }
+    }
+
+    /// Takes `bytes` tokens and returns how long the caller must wait before continuing.
+    fn take(&mut self, bytes: usize) -> Duration {
+        let now = Instant::now();
+        let refill = now.duration_since(self.last).as_secs_f64() * self.rate;
+        self.tokens = (self.tokens + refill).min(self.rate);
+        self.last = now;
+
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 || self.rate <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// Per-session rate limiter for message-based protocols.
+#[derive(Debug)]
+pub struct Throttle {
+    bucket: Option<TokenBucket>,
+    memory_pool: Option<MemoryPool>,
+}
+
+impl Throttle {
+    /// Accounts for `bytes` of input, sleeping if the session is over its rate or the memory
+    /// pool is under pressure.
+    pub async fn wait(&mut self, bytes: usize) {
+        if let Some(bucket) = &mut self.bucket {
+            let delay = bucket.take(bytes);
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+        }
+        if let Some(pool) = &self.memory_pool {
+            let started = Instant::now();
+            while pool.under_pressure() && started.elapsed() < PRESSURE_WAIT {
+                tokio::time::sleep(PRESSURE_POLL).await;
+            }
+        }
+    }
+}
+
+/// [`AsyncRead`] adapter that pauses between reads to keep within a byte rate, and while the
+/// memory pool is under pressure.
+#[derive(Debug)]
+pub struct ThrottledReader<R> {
+    inner: R,
+    bucket: Option<TokenBucket>,
+    sleep: Option<Pin<Box<Sleep>>>,
+    memory_pool: Option<MemoryPool>,
+    /// Polls of the memory pool since the last read.
+    pressure_waits: u32,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ThrottledReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        loop {
+            if let Some(sleep) = &mut self.sleep {
+                ready!(sleep.as_mut().poll(cx));
+                self.sleep = None;
+            }
+            let max_waits = PRESSURE_WAIT.as_millis() / PRESSURE_POLL.as_millis();
+            let pressure = self
+                .memory_pool
+                .as_ref()
+                .is_some_and(MemoryPool::under_pressure);
+            if !pressure || u128::from(self.pressure_waits) >= max_waits {
+                break;
+            }
+            self.pressure_waits += 1;
+            self.sleep = Some(Box::pin(tokio::time::sleep(PRESSURE_POLL)));
+        }
+        self.pressure_waits = 0;
+
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        let read = buf.filled().len() - before;
+
+        if let Some(bucket) = &mut self.bucket {
+            let delay = bucket.take(read);
+            if !delay.is_zero() {
+                self.sleep = Some(Box::pin(tokio::time::sleep(delay)));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,281 @@
+//! Prometheus metrics shared by the HTTP, gRPC and WebSocket servers.
+//!
+//! The registry is small and hand-rolled: a handful of counters and two histograms behind one
+//! mutex, rendered in the Prometheus text exposition format. Per-language series are labelled
+//! with the language code each session was opened with.
+
+use crate::error::{Result, SegmenterError};
+use crate::segmenter::{Segment, Segmenter};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the sentence latency buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 10] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0, 30.0];
+/// Upper bounds of the buffered-bytes buckets.
+const BUFFER_BUCKETS: [f64; 8] = [0.0, 64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0];
+
+/// Server-wide metrics registry; clone the `Arc` into every session.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<Registry>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    languages: BTreeMap<String, LanguageStats>,
+    errors: BTreeMap<&'static str, u64>,
+}
+
+#[derive(Debug)]
+struct LanguageStats {
+    bytes_in: u64,
+    sentences_out: u64,
+    latency: Histogram,
+    buffered: Histogram,
+}
+
+impl Default for LanguageStats {
+    fn default() -> Self {
+        Self {
+            bytes_in: 0,
+            sentences_out: 0,
+            latency: Histogram::new(&LATENCY_BUCKETS),
+            buffered: Histogram::new(&BUFFER_BUCKETS),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(&mut self.counts) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, language: &str) {
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{language=\"{language}\",le=\"{bound}\"}} {count}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{language=\"{language}\",le=\"+Inf\"}} {}",
+            self.count
+        );
+        let _ = writeln!(out, "{name}_sum{{language=\"{language}\"}} {}", self.sum);
+        let _ = writeln!(
+            out,
+            "{name}_count{{language=\"{language}\"}} {}",
+            self.count
+        );
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Counts `bytes` of input text and samples the text still buffered after processing it.
+    pub fn record_input(&self, language: &str, bytes: usize, buffered: Option<usize>) {
+        self.with_language(language, |stats| {
+            stats.bytes_in += bytes as u64;
+            if let Some(buffered) = buffered {
+                stats.buffered.observe(buffered as f64);
+            }
+        });
+    }
+
+    /// Counts one emitted sentence and how long its text waited in the pipeline.
+    pub fn record_sentence(&self, language: &str, latency: Duration) {
+        self.with_language(language, |stats| {
+            stats.sentences_out += 1;
+            stats.latency.observe(latency.as_secs_f64());
+        });
+    }
+
+    /// Records a whole document segmented in one call; each sentence's latency is the time the
+    /// call took.
+    pub fn record_document(&self, language: &str, bytes: usize, sentences: usize, took: Duration) {
+        self.with_language(language, |stats| {
+            stats.bytes_in += bytes as u64;
+            stats.sentences_out += sentences as u64;
+            for _ in 0..sentences {
+                stats.latency.observe(took.as_secs_f64());
+            }
+        });
+    }
+
+    /// Counts an error under its [`SegmenterError::kind`].
+    pub fn record_error(&self, error: &SegmenterError) {
+        let mut registry = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        *registry.errors.entry(error.kind()).or_default() += 1;
+    }
+
+    /// Renders all series in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let registry = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+
+        out.push_str("# HELP async_tqsm_bytes_in_total Bytes of text received.\n");
+        out.push_str("# TYPE async_tqsm_bytes_in_total counter\n");
+        for (language, stats) in &registry.languages {
+            let _ = writeln!(
+                out,
+                "async_tqsm_bytes_in_total{{language=\"{language}\"}} {}",
+                stats.bytes_in
+            );
+        }
+
+        out.push_str("# HELP async_tqsm_sentences_out_total Sentences emitted.\n");
+        out.push_str("# TYPE async_tqsm_sentences_out_total counter\n");
+        for (language, stats) in &registry.languages {
+            let _ = writeln!(
+                out,
+                "async_tqsm_sentences_out_total{{language=\"{language}\"}} {}",
+                stats.sentences_out
+            );
+        }
+
+        out.push_str("# HELP async_tqsm_errors_total Segmentation errors by kind.\n");
+        out.push_str("# TYPE async_tqsm_errors_total counter\n");
+        for (kind, count) in &registry.errors {
+            let _ = writeln!(out, "async_tqsm_errors_total{{kind=\"{kind}\"}} {count}");
+        }
+
+        out.push_str(
+            "# HELP async_tqsm_sentence_latency_seconds Time from the arrival of a sentence's \
+             first buffered input until it was emitted.\n",
+        );
+        out.push_str("# TYPE async_tqsm_sentence_latency_seconds histogram\n");
+        for (language, stats) in &registry.languages {
+            stats
+                .latency
+                .render(&mut out, "async_tqsm_sentence_latency_seconds", language);
+        }
+
+        out.push_str(
+            "# HELP async_tqsm_buffered_bytes Text left buffered after each processed chunk.\n",
+        );
+        out.push_str("# TYPE async_tqsm_buffered_bytes histogram\n");
+        for (language, stats) in &registry.languages {
+            stats
+                .buffered
+                .render(&mut out, "async_tqsm_buffered_bytes", language);
+        }
+        out
+    }
+
+    fn with_language(&self, language: &str, update: impl FnOnce(&mut LanguageStats)) {
+        let mut registry = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if !registry.languages.contains_key(language) {
+            registry
+                .languages
+                .insert(language.to_string(), LanguageStats::default());
+        }
+        update(
+            registry
+                .languages
+                .get_mut(language)
+                .expect("inserted above"),
+        );
+    }
+}
+
+/// Tracks how long the oldest buffered input of a session has been waiting.
+#[derive(Debug, Default)]
+pub struct LatencyClock {
+    pending_since: Option<Instant>,
+    last_input: Option<Instant>,
+}
+
+impl LatencyClock {
+    /// Notes that a chunk of input arrived.
+    pub fn on_input(&mut self) {
+        let now = Instant::now();
+        self.pending_since.get_or_insert(now);
+        self.last_input = Some(now);
+    }
+
+    /// Returns the latency of a sentence emitted now. The text left behind is attributed to the
+    /// latest input, since that is where the next sentence starts in the common case.
+    pub fn on_sentence(&mut self) -> Duration {
+        let latency = self
+            .pending_since
+            .map(|since| since.elapsed())
+            .unwrap_or_default();
+        self.pending_since = self.last_input;
+        latency
+    }
+}
+
+/// Records metrics for one session driving its own [`Segmenter`].
+#[derive(Debug)]
+pub struct MeteredSession {
+    metrics: Arc<Metrics>,
+    language: String,
+    clock: LatencyClock,
+}
+
+impl MeteredSession {
+    pub fn new(metrics: Arc<Metrics>, language: impl Into<String>) -> Self {
+        Self {
+            metrics,
+            language: language.into(),
+            clock: LatencyClock::default(),
+        }
+    }
+
+    /// [`Segmenter::feed_segments`] with metrics.
+    pub fn feed(&mut self, segmenter: &mut Segmenter, chunk: &str) -> Result<Vec<Segment>> {
+        self.clock.on_input();
+        let result = segmenter.feed_segments(chunk);
+        self.metrics
+            .record_input(&self.language, chunk.len(), Some(segmenter.buffered()));
+        self.observe(result)
+    }
+
+    /// [`Segmenter::finish_segments`] with metrics.
+    pub fn finish(&mut self, segmenter: &mut Segmenter) -> Result<Vec<Segment>> {
+        let result = segmenter.finish_segments();
+        self.observe(result)
+    }
+
+    fn observe(&mut self, result: Result<Vec<Segment>>) -> Result<Vec<Segment>> {
+        match &result {
+            Ok(segments) => {
+                for _ in segments {
+                    let latency = self.clock.on_sentence();
+                    self.metrics.record_sentence(&self.language, latency);
+                }
+            }
+            Err(e) => self.metrics.record_error(e),
+        }
+        result
+    }
+}
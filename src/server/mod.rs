@@ -0,0 +1,43 @@
+//! Long-running server modes that keep one warm process and serve many independent
+//! segmentation sessions.
+
+#[cfg(any(feature = "grpc", feature = "ws"))]
+pub mod admin;
+#[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+pub mod auth;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+pub mod health;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod limits;
+#[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+pub mod metrics;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(any(feature = "http", feature = "grpc", feature = "ws"))]
+pub mod tenancy;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(unix)]
+pub mod unix;
+#[cfg(feature = "ws")]
+pub mod ws;
+
+/// Logs an error that ended one session without stopping the server. It goes through `tracing`
+/// only; without the `tracing` feature it is dropped rather than written to stderr.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn report_session_error(context: &str, error: &crate::SegmenterError) {
+    #[cfg(feature = "tracing")]
+    tracing::error!(error_kind = error.kind(), "{}: {}", context, error);
+}
+
+/// Logs a failed `accept` and waits before the next one. It usually means the process is out
+/// of file descriptors, so the listener backs off instead of spinning or shutting down.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) async fn accept_failed(error: &std::io::Error) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!("failed to accept a connection: {}", error);
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+}
@@ -0,0 +1,256 @@
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use crate::segmenter::{segment_document, Segmenter};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SEGMENTATION_ERROR: i64 = -32000;
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A JSON-RPC error object. Segmentation errors carry the error's stable `code` and `kind` in
+/// `data`.
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+/// Option overrides accepted by `segment` and `configure`.
+#[derive(Debug, Default, Deserialize)]
+struct OptionParams {
+    language: Option<String>,
+    lookahead: Option<usize>,
+    max_buffer: Option<usize>,
+    /// Appended to the base options' `extra_abbreviations`.
+    abbreviations: Option<Vec<String>>,
+}
+
+impl OptionParams {
+    fn apply(self, base: &SegmentOptions) -> SegmentOptions {
+        let mut options = base.clone();
+        if let Some(language) = self.language {
+            options.language = language;
+        }
+        if let Some(lookahead) = self.lookahead {
+            options.lookahead = lookahead;
+        }
+        if let Some(max_buffer) = self.max_buffer {
+            options.max_buffer = max_buffer;
+        }
+        if let Some(abbreviations) = self.abbreviations {
+            options.extra_abbreviations.extend(abbreviations);
+        }
+        options
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TextParams {
+    text: String,
+    #[serde(flatten)]
+    options: OptionParams,
+}
+
+/// One JSON-RPC session: the options set by `configure` and the segmenter fed by `feed`.
+struct Session {
+    options: SegmentOptions,
+    segmenter: Segmenter,
+}
+
+/// Speaks newline-delimited JSON-RPC 2.0 over `reader`/`writer` until `reader` reaches EOF.
+///
+/// Methods:
+///
+/// * `segment {text, language?, lookahead?, max_buffer?, abbreviations?}`: segments a complete
+///   text with a fresh segmenter and returns the sentences.
+/// * `feed {text}`: appends text to the session and returns the sentences completed by it.
+/// * `flush`: ends the session's input and returns its remaining sentences, including those
+///   still held back for lookahead and the unterminated tail.
+/// * `configure {language?, lookahead?, max_buffer?, abbreviations?}`: replaces the session
+///   options (discarding buffered text) and returns the effective options.
+///
+/// Requests without an `id` are notifications and get no response.
+pub async fn serve<R, W>(reader: R, writer: W, options: SegmentOptions) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut session = Session {
+        segmenter: Segmenter::new(options.clone())?,
+        options,
+    };
+    let mut lines = BufReader::new(reader).lines();
+    let mut writer = writer;
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&mut session, message),
+            Err(e) => Some(error_response(
+                Value::Null,
+                RpcError::new(PARSE_ERROR, e.to_string()),
+            )),
+        };
+        if let Some(response) = response {
+            writer.write_all(response.to_string().as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+            writer.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+/// Serves the protocol on the process's stdin/stdout.
+pub async fn serve_stdio(options: SegmentOptions) -> Result<()> {
+    serve(tokio::io::stdin(), tokio::io::stdout(), options).await
+}
+
+fn handle_message(session: &mut Session, message: Value) -> Option<Value> {
+    let request: RpcRequest = match serde_json::from_value(message) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(INVALID_REQUEST, e.to_string()),
+            ))
+        }
+    };
+    let id = request.id.clone();
+    let result = dispatch(session, request);
+
+    let id = id?; // Notification: no response
+    Some(match result {
+        Ok(value) => json!({ "jsonrpc": "2.0", "id": id, "result": value }),
+        Err(error) => error_response(id, error),
+    })
+}
+
+fn dispatch(session: &mut Session, request: RpcRequest) -> std::result::Result<Value, RpcError> {
+    match request.method.as_str() {
+        "segment" => {
+            let params: TextParams = parse_params(request.params)?;
+            let options = params.options.apply(&session.options);
+            let segments = segment_document(&params.text, options).map_err(segmentation_error)?;
+            let sentences: Vec<String> = segments.into_iter().map(|s| s.text).collect();
+            Ok(json!(sentences))
+        }
+        "feed" => {
+            let params: TextParams = parse_params(request.params)?;
+            let sentences = session
+                .segmenter
+                .feed(&params.text)
+                .map_err(segmentation_error)?;
+            Ok(json!(sentences))
+        }
+        "flush" => {
+            let sentences = session.segmenter.finish().map_err(segmentation_error)?;
+            Ok(json!(sentences))
+        }
+        "configure" => {
+            let params: OptionParams = parse_params(request.params)?;
+            let options = params.apply(&session.options);
+            session.segmenter = Segmenter::new(options.clone()).map_err(segmentation_error)?;
+            session.options = options;
+            Ok(json!({
+                "language": session.options.language,
+                "lookahead": session.options.lookahead,
+                "max_buffer": session.options.max_buffer,
+            }))
+        }
+        other => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{}'", other),
+        )),
+    }
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> std::result::Result<T, RpcError> {
+    // Methods whose parameters are all optional may be called without `params`
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn segmentation_error(e: SegmenterError) -> RpcError {
+    RpcError {
+        code: SEGMENTATION_ERROR,
+        message: e.to_string(),
+        data: Some(json!({ "code": e.code(), "kind": e.kind() })),
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    let mut body = json!({ "code": error.code, "message": error.message });
+    if let Some(data) = error.data {
+        body["data"] = data;
+    }
+    json!({ "jsonrpc": "2.0", "id": id, "error": body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_feed_flush_and_errors() -> anyhow::Result<()> {
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"configure","params":{"max_buffer":4096}}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"feed","params":{"text":"One. Two"}}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"feed","params":{"text":" more"}}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":3,"method":"flush"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":4,"method":"nope"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":5,"method":"configure","params":{"language":"tlh"}}"#,
+            "\n",
+        );
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output, SegmentOptions::default()).await?;
+
+        let responses: Vec<Value> = String::from_utf8(output)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<std::result::Result<_, _>>()?;
+
+        assert_eq!(responses.len(), 5); // The notification gets no response
+                                        // The default lookahead holds "One." back until the input ends
+        assert_eq!(responses[1]["result"], json!([]));
+        assert_eq!(responses[2]["result"], json!(["One.", "Two more"]));
+        assert_eq!(responses[3]["error"]["code"], json!(METHOD_NOT_FOUND));
+        assert_eq!(responses[4]["error"]["code"], json!(SEGMENTATION_ERROR));
+        assert_eq!(
+            responses[4]["error"]["data"],
+            json!({ "code": 1, "kind": "unsupported_language" })
+        );
+
+        Ok(())
+    }
+}
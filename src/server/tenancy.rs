@@ -0,0 +1,227 @@
+//! Per-request options for multi-tenant servers: every HTTP request, WebSocket connection and
+//! gRPC call may pick its own language, lookahead, buffer size, extra abbreviations and output
+//! format, within the allow-lists the server was started with.
+//!
+//! Options come from a query string (`?language=de&abbreviations=approx.,ca.`) or from
+//! `X-Segment-*` headers / gRPC metadata (`x-segment-language`, `x-segment-lookahead`,
+//! `x-segment-max-buffer`, `x-segment-abbreviations`, `x-segment-format`).
+
+use crate::config::SegmentOptions;
+use crate::error::{Result, SegmenterError};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Options a client asked for; unset fields keep the server's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overrides {
+    pub language: Option<String>,
+    pub lookahead: Option<usize>,
+    pub max_buffer: Option<usize>,
+    /// Added to the server's `extra_abbreviations`.
+    pub abbreviations: Option<Vec<String>>,
+    /// Output format name; which names exist depends on the server.
+    pub format: Option<String>,
+}
+
+/// Option names, as used in query strings, and the matching header / metadata names.
+const OPTIONS: [(&str, &str); 5] = [
+    ("language", "x-segment-language"),
+    ("lookahead", "x-segment-lookahead"),
+    ("max_buffer", "x-segment-max-buffer"),
+    ("abbreviations", "x-segment-abbreviations"),
+    ("format", "x-segment-format"),
+];
+
+impl Overrides {
+    /// Sets option `name` from its textual form; `abbreviations` is comma-separated. Unknown
+    /// names are ignored, so a query string may carry unrelated parameters such as
+    /// `access_token`.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        match name {
+            "language" => self.language = Some(value.to_string()),
+            "lookahead" => self.lookahead = Some(parse_number(name, value)?),
+            "max_buffer" => self.max_buffer = Some(parse_number(name, value)?),
+            "abbreviations" => {
+                self.abbreviations = Some(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|word| !word.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                )
+            }
+            "format" => self.format = Some(value.to_ascii_lowercase()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Reads the `X-Segment-*` headers through `header`, which looks up a lowercase header
+    /// (or metadata) name.
+    pub fn from_headers<'a>(header: impl Fn(&str) -> Option<&'a str>) -> Result<Self> {
+        let mut overrides = Self::default();
+        for (name, header_name) in OPTIONS {
+            if let Some(value) = header(header_name) {
+                overrides.set(name, value)?;
+            }
+        }
+        Ok(overrides)
+    }
+
+    /// Applies the options in a URL query string (`a=1&b=2`, percent-encoded) on top of the
+    /// current ones.
+    pub fn apply_query(&mut self, query: &str) -> Result<()> {
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            if OPTIONS.iter().any(|(option, _)| *option == name) {
+                self.set(name, &percent_decode(value)?)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Allow-lists for [`Overrides`]. The default allows every override.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    languages: Option<Arc<HashSet<String>>>,
+    max_lookahead: Option<usize>,
+    abbreviations: bool,
+    formats: Option<Arc<HashSet<String>>>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            languages: None,
+            max_lookahead: None,
+            abbreviations: true,
+            formats: None,
+        }
+    }
+}
+
+impl Policy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only these language codes may be requested; the server's own language is always allowed.
+    pub fn allow_languages<I, S>(mut self, languages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.languages = Some(Arc::new(languages.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Rejects requested lookaheads above `max`.
+    pub fn max_lookahead(mut self, max: usize) -> Self {
+        self.max_lookahead = Some(max);
+        self
+    }
+
+    /// Whether clients may add their own abbreviations.
+    pub fn allow_abbreviations(mut self, allow: bool) -> Self {
+        self.abbreviations = allow;
+        self
+    }
+
+    /// Only these output formats may be requested.
+    pub fn allow_formats<I, S>(mut self, formats: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.formats = Some(Arc::new(
+            formats
+                .into_iter()
+                .map(|format| format.into().to_ascii_lowercase())
+                .collect(),
+        ));
+        self
+    }
+
+    /// Applies `overrides` to `defaults`, failing with [`SegmenterError::InvalidOptions`] if
+    /// one of them is not allowed. Output formats are checked
+    /// separately by [`check_format`](Self::check_format).
+    pub fn resolve(
+        &self,
+        defaults: &SegmentOptions,
+        overrides: &Overrides,
+    ) -> Result<SegmentOptions> {
+        let mut options = defaults.clone();
+        if let Some(language) = &overrides.language {
+            let allowed = *language == defaults.language
+                || self.languages.as_ref().is_none_or(|l| l.contains(language));
+            if !allowed {
+                return Err(not_allowed(format!("language '{}'", language)));
+            }
+            options.language = language.clone();
+        }
+        if let Some(lookahead) = overrides.lookahead {
+            if self.max_lookahead.is_some_and(|max| lookahead > max) {
+                return Err(not_allowed(format!("lookahead {}", lookahead)));
+            }
+            options.lookahead = lookahead;
+        }
+        if let Some(max_buffer) = overrides.max_buffer {
+            options.max_buffer = max_buffer;
+        }
+        if let Some(abbreviations) = &overrides.abbreviations {
+            if !self.abbreviations && !abbreviations.is_empty() {
+                return Err(not_allowed("custom abbreviations".to_string()));
+            }
+            options
+                .extra_abbreviations
+                .extend(abbreviations.iter().cloned());
+        }
+        Ok(options)
+    }
+
+    /// Fails with [`SegmenterError::InvalidOptions`] unless `format` is allowed. Servers only
+    /// check formats a client asked for, so their default format is always available.
+    pub fn check_format(&self, format: &str) -> Result<()> {
+        match &self.formats {
+            Some(formats) if !formats.contains(&format.to_ascii_lowercase()) => {
+                Err(not_allowed(format!("format '{}'", format)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn not_allowed(what: String) -> SegmenterError {
+    SegmenterError::InvalidOptions(format!("{} is not allowed on this server", what))
+}
+
+fn parse_number(name: &str, value: &str) -> Result<usize> {
+    value
+        .parse()
+        .map_err(|_| SegmenterError::InvalidOptions(format!("invalid {} '{}'", name, value)))
+}
+
+/// Decodes `%XX` escapes and `+` (space) in a query component.
+pub(crate) fn percent_decode(value: &str) -> Result<String> {
+    let invalid = || SegmenterError::InvalidOptions(format!("invalid query value '{}'", value));
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [
+                    input.next().ok_or_else(invalid)?,
+                    input.next().ok_or_else(invalid)?,
+                ];
+                let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+                bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
@@ -0,0 +1,183 @@
+//! rustls-based TLS for the network server modes, with optional client-certificate
+//! authentication (mTLS).
+
+use crate::error::{Result, SegmenterError};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+/// How long a client gets to complete the TLS handshake before its connection is dropped.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// PEM files for a TLS server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// Certificate chain, leaf first.
+    pub cert: PathBuf,
+    /// Private key for the leaf certificate.
+    pub key: PathBuf,
+    /// CA certificates for client authentication. When set, clients must present a certificate
+    /// signed by one of them.
+    pub client_ca: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    pub fn new(cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        Self {
+            cert: cert.into(),
+            key: key.into(),
+            client_ca: None,
+        }
+    }
+
+    /// Requires clients to authenticate with a certificate signed by a CA in `path`.
+    pub fn client_ca(mut self, path: impl Into<PathBuf>) -> Self {
+        self.client_ca = Some(path.into());
+        self
+    }
+
+    /// Loads the PEM files into an acceptor for TCP connections.
+    pub fn acceptor(&self) -> Result<TlsAcceptor> {
+        let provider = Arc::new(ring::default_provider());
+        let certs = CertificateDer::pem_file_iter(&self.cert)
+            .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+            .map_err(tls_error)?;
+        let key = PrivateKeyDer::from_pem_file(&self.key).map_err(tls_error)?;
+
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?;
+        let builder = match &self.client_ca {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in CertificateDer::pem_file_iter(path).map_err(tls_error)? {
+                    roots.add(cert.map_err(tls_error)?).map_err(tls_error)?;
+                }
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()
+                        .map_err(tls_error)?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder.with_single_cert(certs, key).map_err(tls_error)?;
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+
+    /// The same files as a `tonic` server TLS configuration.
+    #[cfg(feature = "grpc")]
+    pub(crate) fn tonic_config(&self) -> Result<tonic::transport::ServerTlsConfig> {
+        use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+        let cert = std::fs::read(&self.cert)?;
+        let key = std::fs::read(&self.key)?;
+        let mut config = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+        if let Some(path) = &self.client_ca {
+            config = config.client_ca_root(Certificate::from_pem(std::fs::read(path)?));
+        }
+        Ok(config)
+    }
+}
+
+fn tls_error(e: impl std::fmt::Display) -> SegmenterError {
+    SegmenterError::StreamError(format!("TLS configuration error: {}", e))
+}
+
+/// Performs the TLS handshake on `stream`, failing with `TimedOut` after [`HANDSHAKE_TIMEOUT`].
+pub(crate) async fn handshake(
+    acceptor: TlsAcceptor,
+    stream: TcpStream,
+) -> std::io::Result<TlsStream<TcpStream>> {
+    tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream))
+        .await
+        .unwrap_or_else(|_| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "TLS handshake timed out",
+            ))
+        })
+}
+
+/// `axum` listener that performs TLS handshakes in the background, so a slow client cannot hold
+/// up accepting others. Each handshake holds a session slot of the server's [`Limits`], so
+/// connections beyond `max_sessions` are dropped before any handshake work is done.
+///
+/// [`Limits`]: crate::server::limits::Limits
+#[cfg(feature = "http")]
+pub(crate) struct TlsListener {
+    incoming: tokio::sync::mpsc::Receiver<(TlsStream<TcpStream>, std::net::SocketAddr)>,
+    local_addr: std::net::SocketAddr,
+}
+
+#[cfg(feature = "http")]
+impl TlsListener {
+    /// Number of completed handshakes waiting to be picked up by the server.
+    const BACKLOG: usize = 64;
+
+    pub(crate) fn new(
+        listener: tokio::net::TcpListener,
+        acceptor: TlsAcceptor,
+        limits: crate::server::limits::Limits,
+    ) -> Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (sender, incoming) = tokio::sync::mpsc::channel(Self::BACKLOG);
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(_) => {
+                        // Usually out of file descriptors; back off instead of spinning
+                        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                        continue;
+                    }
+                };
+                // The request handlers claim their own slots; this one only covers the handshake
+                let Some(permit) = limits.try_open_session() else {
+                    continue;
+                };
+                let acceptor = acceptor.clone();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    let handshake = handshake(acceptor, stream).await;
+                    drop(permit);
+                    if let Ok(stream) = handshake {
+                        let _ = sender.send((stream, peer)).await;
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            incoming,
+            local_addr,
+        })
+    }
+}
+
+#[cfg(feature = "http")]
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = std::net::SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(accepted) => accepted,
+            // The accept task never exits, so this is unreachable in practice
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
@@ -0,0 +1,75 @@
+use crate::config::SegmentOptions;
+use crate::error::Result;
+use crate::sentences_stream;
+use crate::server::limits::Limits;
+use futures::StreamExt;
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::net::{UnixListener, UnixStream};
+
+/// Listens on the Unix domain socket at `path` and serves every accepted connection as an
+/// independent segmentation session.
+///
+/// A client writes text and reads sentences back, one per line, as soon as they are found.
+/// Shutting down its write half flushes the remaining text. A stale socket left at `path` by a
+/// previous run is removed before binding; any other kind of file is left alone.
+pub async fn serve(path: &Path, options: SegmentOptions) -> Result<()> {
+    serve_with(path, options, Limits::default()).await
+}
+
+/// Like [`serve`], enforcing `limits`. Connections beyond the session limit are closed
+/// immediately.
+pub async fn serve_with(path: &Path, mut options: SegmentOptions, limits: Limits) -> Result<()> {
+    limits.clamp(&mut options);
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if metadata.file_type().is_socket() {
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    let listener = UnixListener::bind(path)?;
+    loop {
+        let (stream, _addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                super::accept_failed(&e).await;
+                continue;
+            }
+        };
+        let Some(permit) = limits.try_open_session() else {
+            drop(stream);
+            continue;
+        };
+        let options = options.clone();
+        let limits = limits.clone();
+        tokio::spawn(async move {
+            let _permit = permit;
+            if let Err(e) = handle_connection(stream, options, &limits).await {
+                super::report_session_error("Unix socket session error", &e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    stream: UnixStream,
+    options: SegmentOptions,
+    limits: &Limits,
+) -> Result<()> {
+    let (reader, writer) = stream.into_split();
+    let mut writer = BufWriter::new(writer);
+
+    let stream = sentences_stream(limits.throttle_reader(reader), options);
+    futures::pin_mut!(stream);
+
+    while let Some(sentence_result) = stream.next().await {
+        let sentence = sentence_result?;
+        writer.write_all(sentence.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        writer.flush().await?; // Deliver each sentence immediately
+    }
+
+    writer.shutdown().await?;
+    Ok(())
+}
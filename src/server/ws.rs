@@ -0,0 +1,310 @@
+use crate::config::SegmentOptions;
+use crate::decode::Utf8Decoder;
+use crate::error::{Result, SegmenterError};
+use crate::segmenter::{Segment, Segmenter};
+use crate::server::auth::Auth;
+use crate::server::limits::Limits;
+use crate::server::metrics::{MeteredSession, Metrics};
+use crate::server::tenancy::{percent_decode, Overrides, Policy};
+use futures::{SinkExt, StreamExt};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Accepts WebSocket connections on `addr` and serves each one as an independent segmentation
+/// session.
+///
+/// Every text message from the client is appended to the session's text stream. Each sentence is
+/// sent back as soon as it is found, as a JSON text message carrying its `index`, byte offsets
+/// (`start`, `end`) and `text`. An empty message ends the input: the remaining text is flushed
+/// and sent, then the server closes the connection. Nothing can be sent after a Close frame from
+/// the client, so text still buffered when the client closes first is dropped.
+///
+/// The handshake request may override the options through its query string or `X-Segment-*`
+/// headers (see [`tenancy`](crate::server::tenancy)); `format=text` sends plain sentence text
+/// instead of JSON.
+pub async fn serve<A: ToSocketAddrs>(addr: A, options: SegmentOptions) -> Result<()> {
+    serve_with(
+        addr,
+        options,
+        Metrics::new(),
+        Limits::default(),
+        Auth::disabled(),
+        Policy::default(),
+    )
+    .await
+}
+
+/// Like [`serve`], recording every session into `metrics` and enforcing `limits`, `auth` and the
+/// per-connection option `policy`. Connections beyond the session limit are closed with status
+/// 1013 (try again later); the handshake of a client without a valid token fails with
+/// `401 Unauthorized`, one asking for options outside `policy` with `400 Bad Request`.
+pub async fn serve_with<A: ToSocketAddrs>(
+    addr: A,
+    options: SegmentOptions,
+    metrics: Arc<Metrics>,
+    limits: Limits,
+    auth: Auth,
+    policy: Policy,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    let plain = |stream| async { Ok(stream) };
+    let server = Server {
+        defaults: options,
+        metrics,
+        limits,
+        auth,
+        policy,
+    };
+    accept_loop(listener, server, plain).await
+}
+
+/// Like [`serve_with`], over TLS.
+#[cfg(feature = "tls")]
+pub async fn serve_tls<A: ToSocketAddrs>(
+    addr: A,
+    options: SegmentOptions,
+    metrics: Arc<Metrics>,
+    limits: Limits,
+    auth: Auth,
+    policy: Policy,
+    tls: crate::server::tls::TlsConfig,
+) -> Result<()> {
+    let acceptor = tls.acceptor()?;
+    let listener = TcpListener::bind(addr).await?;
+    let handshake = move |stream| crate::server::tls::handshake(acceptor.clone(), stream);
+    let server = Server {
+        defaults: options,
+        metrics,
+        limits,
+        auth,
+        policy,
+    };
+    accept_loop(listener, server, handshake).await
+}
+
+/// Settings shared by every connection of one server.
+#[derive(Clone)]
+struct Server {
+    defaults: SegmentOptions,
+    metrics: Arc<Metrics>,
+    limits: Limits,
+    auth: Auth,
+    policy: Policy,
+}
+
+/// What a session sends back for each sentence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    /// A JSON object with `index`, `start`, `end` and `text`.
+    Json,
+    /// The sentence text alone.
+    Text,
+}
+
+/// Runs `handshake` on every accepted connection, then serves it as a WebSocket session.
+async fn accept_loop<H, F, S>(listener: TcpListener, server: Server, handshake: H) -> Result<()>
+where
+    H: Fn(TcpStream) -> F,
+    F: Future<Output = std::io::Result<S>> + Send + 'static,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    loop {
+        let (stream, _peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                super::accept_failed(&e).await;
+                continue;
+            }
+        };
+        let handshake = handshake(stream);
+        let permit = server.limits.try_open_session();
+        let server = server.clone();
+        tokio::spawn(async move {
+            let stream = match handshake.await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    super::report_session_error("WebSocket handshake error", &e.into());
+                    return;
+                }
+            };
+            let Some(_permit) = permit else {
+                refuse_connection(stream).await;
+                return;
+            };
+            if let Err(e) = handle_connection(stream, &server).await {
+                super::report_session_error("WebSocket session error", &e);
+            }
+        });
+    }
+}
+
+async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    server: &Server,
+) -> Result<()> {
+    let mut negotiated = None;
+    // The error type is fixed by tungstenite's handshake callback
+    #[allow(clippy::result_large_err)]
+    let check_request = |request: &Request, response: Response| {
+        let auth = &server.auth;
+        if !(auth.check(
+            header(request, "authorization"),
+            header(request, "x-api-key"),
+        ) || query_token(request).is_some_and(|token| auth.check(None, Some(&token))))
+        {
+            return Err(reject(StatusCode::UNAUTHORIZED, "missing or invalid token"));
+        }
+        match session_options(request, server) {
+            Ok(resolved) => {
+                negotiated = Some(resolved);
+                Ok(response)
+            }
+            Err(e) => Err(reject(StatusCode::BAD_REQUEST, &e.to_string())),
+        }
+    };
+    let websocket = tokio_tungstenite::accept_hdr_async(stream, check_request)
+        .await
+        .map_err(ws_error)?;
+    let Some((options, format)) = negotiated else {
+        return Err(SegmenterError::StreamError(
+            "handshake finished without options".to_string(),
+        ));
+    };
+    let (mut outgoing, mut incoming) = websocket.split();
+    let mut session = MeteredSession::new(server.metrics.clone(), options.language.clone());
+    let mut throttle = server.limits.throttle();
+    let mut decoder = Utf8Decoder::for_options(&options);
+    let mut segmenter = Segmenter::new(options)?;
+    let mut closed = false;
+
+    while let Some(message) = incoming.next().await {
+        let message = message.map_err(ws_error)?;
+        throttle.wait(message.len()).await;
+        let segments = match message {
+            Message::Text(text) if text.is_empty() => break,
+            Message::Binary(bytes) if bytes.is_empty() => break,
+            Message::Text(text) => session.feed(&mut segmenter, text.as_str())?,
+            Message::Binary(bytes) => session.feed(&mut segmenter, &decoder.decode(&bytes)?)?,
+            Message::Close(_) => {
+                closed = true;
+                break;
+            }
+            _ => continue, // Ping/pong are answered by tungstenite itself
+        };
+        for segment in segments {
+            send_segment(&mut outgoing, &segment, format).await?;
+        }
+    }
+
+    let segments = flush_session(&mut session, &mut segmenter, &mut decoder)?;
+    // tungstenite has already queued its reply to the client's Close frame
+    if !closed {
+        for segment in segments {
+            send_segment(&mut outgoing, &segment, format).await?;
+        }
+    }
+    outgoing.close().await.map_err(ws_error)
+}
+
+/// The sentences left at the end of a session: those of a partial character held back by the
+/// decoder, then the flushed buffer.
+fn flush_session(
+    session: &mut MeteredSession,
+    segmenter: &mut Segmenter,
+    decoder: &mut Utf8Decoder,
+) -> Result<Vec<Segment>> {
+    let mut segments = match decoder.finish()? {
+        Some(tail) => session.feed(segmenter, &tail)?,
+        None => Vec::new(),
+    };
+    segments.extend(session.finish(segmenter)?);
+    Ok(segments)
+}
+
+/// The session's options and output format, from the handshake's query string and
+/// `X-Segment-*` headers.
+fn session_options(request: &Request, server: &Server) -> Result<(SegmentOptions, OutputFormat)> {
+    let mut overrides = Overrides::from_headers(|name| header(request, name))?;
+    if let Some(query) = request.uri().query() {
+        overrides.apply_query(query)?;
+    }
+    let mut options = server.policy.resolve(&server.defaults, &overrides)?;
+    server.limits.clamp(&mut options);
+    let format = match overrides.format.as_deref() {
+        None => OutputFormat::Json,
+        Some(name) => {
+            let format = match name {
+                "json" => OutputFormat::Json,
+                "text" => OutputFormat::Text,
+                _ => {
+                    return Err(SegmenterError::InvalidOptions(format!(
+                        "unknown format '{}'",
+                        name
+                    )))
+                }
+            };
+            server.policy.check_format(name)?;
+            format
+        }
+    };
+    // Fail the handshake rather than the session on an unsupported language
+    Segmenter::new(options.clone())?;
+    Ok((options, format))
+}
+
+fn reject(status: StatusCode, reason: &str) -> ErrorResponse {
+    let mut rejection = ErrorResponse::new(Some(reason.to_string()));
+    *rejection.status_mut() = status;
+    rejection
+}
+
+fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Browsers cannot set headers on WebSocket requests, so the token may also come as
+/// `?access_token=...`, percent-encoded.
+fn query_token(request: &Request) -> Option<String> {
+    let token = request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("access_token="))?;
+    percent_decode(token).ok()
+}
+
+async fn refuse_connection<S: AsyncRead + AsyncWrite + Unpin>(stream: S) {
+    if let Ok(mut websocket) = tokio_tungstenite::accept_async(stream).await {
+        let frame = CloseFrame {
+            code: CloseCode::Again,
+            reason: "too many sessions".into(),
+        };
+        let _ = websocket.close(Some(frame)).await;
+    }
+}
+
+async fn send_segment<S>(outgoing: &mut S, segment: &Segment, format: OutputFormat) -> Result<()>
+where
+    S: futures::Sink<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin,
+{
+    let message = match format {
+        OutputFormat::Json => serde_json::to_string(segment)
+            .map_err(|e| SegmenterError::StreamError(e.to_string()))?,
+        OutputFormat::Text => segment.text.clone(),
+    };
+    outgoing
+        .send(Message::text(message))
+        .await
+        .map_err(ws_error)
+}
+
+fn ws_error(e: tokio_tungstenite::tungstenite::Error) -> SegmenterError {
+    SegmenterError::StreamError(e.to_string())
+}
@@ -0,0 +1,188 @@
+//! Pull-based alternative to the sentence streams: [`SentenceSource::next_sentence`] is a plain
+//! `async fn`, so callers need neither pinning nor `StreamExt`.
+//!
+//! ```no_run
+//! # async fn run() -> async_tqsm::Result<()> {
+//! use async_tqsm::{source::SentenceSource, SegmentOptions};
+//!
+//! let mut source = SentenceSource::new(tokio::io::stdin(), SegmentOptions::default())?;
+//! while let Some(sentence) = source.next_sentence().await {
+//!     println!("{}", sentence?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::chunking::ChunkSizer;
+use crate::config::SegmentOptions;
+use crate::decode::Utf8Decoder;
+use crate::error::{Result, SegmenterError};
+use crate::retry::RetryPolicy;
+use crate::segmenter::{Segment, Segmenter};
+use futures::stream::Stream;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::time::Sleep;
+
+/// Reads and segments `reader` on demand, one sentence per call. It is also a [`Stream`] of
+/// [`Segment`]s.
+///
+/// Errors follow the streams: an I/O error, or invalid UTF-8 under
+/// [`Utf8Policy::Strict`](crate::decode::Utf8Policy::Strict), ends the input without flushing;
+/// a segmentation error is returned once and reading goes on.
+///
+/// A caller that stops early can recover the input it has not received as sentences with
+/// [`take_pending`](Self::take_pending) or [`into_remaining`](Self::into_remaining).
+pub struct SentenceSource<R> {
+    reader: R,
+    segmenter: Segmenter,
+    decoder: Utf8Decoder,
+    sizer: ChunkSizer,
+    retry: RetryPolicy,
+    buffer: Vec<u8>,
+    /// Sentences found but not yet returned.
+    ready: VecDeque<Segment>,
+    /// Set once the reader is exhausted or failed; only `ready` is left.
+    done: bool,
+    /// Consecutive failed reads, and the delay before the next attempt.
+    failures: u32,
+    backoff: Option<Pin<Box<Sleep>>>,
+}
+
+/// Input a [`SentenceSource`] has read but not returned.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Remaining {
+    /// Sentences already found but not yet returned, in order.
+    pub sentences: Vec<Segment>,
+    /// Text after them that is still waiting for a sentence boundary.
+    pub text: String,
+    /// Start of a UTF-8 character whose remaining bytes have not been read yet.
+    pub bytes: Vec<u8>,
+}
+
+impl Remaining {
+    pub fn is_empty(&self) -> bool {
+        self.sentences.is_empty() && self.text.is_empty() && self.bytes.is_empty()
+    }
+}
+
+impl<R: AsyncRead + Unpin> SentenceSource<R> {
+    pub fn new(reader: R, options: SegmentOptions) -> Result<Self> {
+        let sizer = ChunkSizer::for_options(&options);
+        Ok(Self {
+            reader,
+            decoder: Utf8Decoder::for_options(&options),
+            retry: options.retry.clone(),
+            buffer: vec![0; sizer.max_size()],
+            sizer,
+            segmenter: Segmenter::new(options)?,
+            ready: VecDeque::new(),
+            done: false,
+            failures: 0,
+            backoff: None,
+        })
+    }
+
+    /// The next sentence, reading as much input as it takes; `None` once the input is
+    /// exhausted and the tail flushed.
+    pub async fn next_sentence(&mut self) -> Option<Result<String>> {
+        Some(self.next_segment().await?.map(|segment| segment.text))
+    }
+
+    /// Like [`next_sentence`](Self::next_sentence), but returns the sentence with its offsets.
+    pub async fn next_segment(&mut self) -> Option<Result<Segment>> {
+        std::future::poll_fn(|cx| self.poll_next_segment(cx)).await
+    }
+
+    /// Removes everything read but not yet returned, without segmenting the incomplete text
+    /// at the end; reading continues after it on the next call. Offsets of later sentences
+    /// still count the removed text.
+    pub fn take_pending(&mut self) -> Remaining {
+        Remaining {
+            sentences: self.ready.drain(..).collect(),
+            text: self.segmenter.take_pending(),
+            bytes: self.decoder.take_pending(),
+        }
+    }
+
+    /// Ends segmentation early, returning what was read but not returned, and the reader,
+    /// positioned after the last read.
+    pub fn into_remaining(mut self) -> (Remaining, R) {
+        (self.take_pending(), self.reader)
+    }
+
+    fn poll_next_segment(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Segment>>> {
+        loop {
+            if let Some(segment) = self.ready.pop_front() {
+                return Poll::Ready(Some(Ok(segment)));
+            }
+            if self.done {
+                return Poll::Ready(None);
+            }
+            if let Some(backoff) = &mut self.backoff {
+                ready!(backoff.as_mut().poll(cx));
+                self.backoff = None;
+            }
+
+            let started = std::time::Instant::now();
+            let size = self.sizer.size();
+            let mut buf = ReadBuf::new(&mut self.buffer[..size]);
+            let read = ready!(Pin::new(&mut self.reader).poll_read(cx, &mut buf));
+            let n = buf.filled().len();
+            let result = match read {
+                Ok(()) if n == 0 => self.finish(),
+                Ok(()) => {
+                    self.failures = 0;
+                    self.sizer.record(n, started.elapsed());
+                    match self.decoder.decode(&self.buffer[..n]) {
+                        Ok(text) => self.feed(&text),
+                        Err(e) => {
+                            self.done = true;
+                            Err(e)
+                        }
+                    }
+                }
+                Err(e) => {
+                    self.failures += 1;
+                    if self.retry.should_retry(e.kind(), self.failures) {
+                        let delay = self.retry.delay(self.failures);
+                        self.backoff = Some(Box::pin(tokio::time::sleep(delay)));
+                        continue;
+                    }
+                    self.done = true;
+                    Err(SegmenterError::IoError(e))
+                }
+            };
+            if let Err(e) = result {
+                return Poll::Ready(Some(Err(e)));
+            }
+        }
+    }
+
+    fn feed(&mut self, text: &str) -> Result<()> {
+        self.ready.extend(self.segmenter.feed_segments(text)?);
+        Ok(())
+    }
+
+    /// Ends the input: decodes a held-back partial character and flushes the segmenter.
+    fn finish(&mut self) -> Result<()> {
+        self.done = true;
+        let fed = match self.decoder.finish()? {
+            Some(tail) => self.feed(&tail),
+            None => Ok(()),
+        };
+        self.ready.extend(self.segmenter.finish_segments()?);
+        fed
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for SentenceSource<R> {
+    type Item = Result<Segment>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_segment(cx)
+    }
+}
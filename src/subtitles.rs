@@ -0,0 +1,308 @@
+//! Splitting long sentences into visually balanced lines, and subtitle (SRT) cues built from
+//! them.
+//!
+//! [`balance_lines`] breaks a sentence into a given number of lines of similar width, preferring
+//! breaks after clause punctuation and avoiding lines that end in, or consist of, a dangling
+//! short word. [`wrap_balanced`] picks the fewest lines that fit a maximum width.
+
+use crate::duration::estimate_duration;
+use std::fmt;
+use std::time::Duration;
+
+/// Punctuation after which a line break reads naturally.
+const CLAUSE_ENDS: [char; 11] = [',', ';', ':', '–', '—', '，', '、', '；', '：', '.', '。'];
+
+/// Punctuation that must stay on the line of the character before it.
+const CLOSING: [char; 14] = [
+    '.', ',', ';', ':', '!', '?', ')', '»', '”', '’', '。', '，', '、', '」',
+];
+
+/// One unbreakable piece of a line.
+#[derive(Debug)]
+struct Token<'a> {
+    text: &'a str,
+    /// Whether a space separates the token from the previous one.
+    space_before: bool,
+    width: usize,
+}
+
+/// Display width of `c` in terminal columns: 2 for East Asian wide characters, else 1.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Display width of `text` in terminal columns, counting East Asian wide characters twice.
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Splits `text` at spaces and, in scripts written without spaces, between wide characters.
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut start = None;
+    let mut space_before = false;
+    for (i, c) in text.char_indices() {
+        let wide = char_width(c) == 2;
+        if c.is_whitespace() || (wide && !CLOSING.contains(&c)) {
+            if let Some(s) = start.take() {
+                tokens.push(Token {
+                    text: &text[s..i],
+                    space_before,
+                    width: display_width(&text[s..i]),
+                });
+                space_before = false;
+            }
+        }
+        if c.is_whitespace() {
+            space_before = !tokens.is_empty();
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(Token {
+            text: &text[s..],
+            space_before,
+            width: display_width(&text[s..]),
+        });
+    }
+    tokens
+}
+
+fn line_width(tokens: &[Token]) -> usize {
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(i, t)| t.width + usize::from(i > 0 && t.space_before))
+        .sum()
+}
+
+/// Three-letter articles, conjunctions and prepositions of common languages; shorter lower-case
+/// words are treated alike.
+const SHORT_WORDS: [&str; 17] = [
+    "the", "and", "but", "for", "nor", "der", "die", "das", "und", "les", "des", "une", "los",
+    "las", "del", "con", "per",
+];
+
+/// Whether `word` is a short lower-case word such as an article or preposition, which reads
+/// badly at the end of a line or alone on one.
+fn is_short_word(word: &str) -> bool {
+    let lower = word.chars().all(|c| c.is_alphabetic() && c.is_lowercase());
+    lower && (word.chars().count() <= 2 || SHORT_WORDS.contains(&word))
+}
+
+/// Badness of a line made of `tokens` when lines should be `target` columns wide.
+fn line_cost(tokens: &[Token], target: f64, last: bool) -> f64 {
+    let deviation = line_width(tokens) as f64 - target;
+    let mut cost = deviation * deviation;
+    let Some(end) = tokens.last() else {
+        return cost;
+    };
+    if tokens.len() == 1 && is_short_word(end.text) {
+        cost += target * target;
+    }
+    if !last {
+        if end.text.ends_with(CLAUSE_ENDS) {
+            cost -= (target / 3.0).powi(2);
+        } else if is_short_word(end.text) {
+            cost += (target / 2.0).powi(2);
+        }
+    }
+    cost
+}
+
+fn join(tokens: &[Token]) -> String {
+    let mut line = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && token.space_before {
+            line.push(' ');
+        }
+        line.push_str(token.text);
+    }
+    line
+}
+
+fn balance_tokens(tokens: &[Token], lines: usize) -> Vec<String> {
+    let n = tokens.len();
+    let lines = lines.clamp(1, n.max(1));
+    if n == 0 {
+        return Vec::new();
+    }
+    let target = line_width(tokens) as f64 / lines as f64;
+    // best[l][j]: cost of the first j tokens in l lines, and where the last of them starts
+    let mut best = vec![vec![(f64::INFINITY, 0); n + 1]; lines + 1];
+    best[0][0] = (0.0, 0);
+    for l in 1..=lines {
+        for j in l..=n - (lines - l) {
+            for i in (l - 1)..j {
+                let previous = best[l - 1][i].0;
+                if previous.is_finite() {
+                    let cost = previous + line_cost(&tokens[i..j], target, l == lines);
+                    if cost < best[l][j].0 {
+                        best[l][j] = (cost, i);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut breaks = Vec::with_capacity(lines);
+    let mut end = n;
+    for l in (1..=lines).rev() {
+        let start = best[l][end].1;
+        breaks.push(start..end);
+        end = start;
+    }
+    breaks.into_iter().rev().map(|r| join(&tokens[r])).collect()
+}
+
+/// Splits `text` into `lines` lines of similar display width (fewer if it has fewer words).
+///
+/// Breaks fall between words, or between characters in scripts written without spaces, and
+/// prefer positions after commas and other clause punctuation. A line ending in a short word
+/// such as "a" or "the", or consisting of one, is avoided.
+pub fn balance_lines(text: &str, lines: usize) -> Vec<String> {
+    balance_tokens(&tokenize(text), lines)
+}
+
+/// Splits `text` into the fewest balanced lines no wider than `width` columns. A single word
+/// wider than `width` gets a line of its own.
+pub fn wrap_balanced(text: &str, width: usize) -> Vec<String> {
+    let tokens = tokenize(text);
+    let total = line_width(&tokens);
+    let mut lines = total.div_ceil(width.max(1)).max(1);
+    loop {
+        let balanced = balance_tokens(&tokens, lines);
+        if lines >= tokens.len() || balanced.iter().all(|l| display_width(l) <= width) {
+            return balanced;
+        }
+        lines += 1;
+    }
+}
+
+/// Layout and timing of subtitle cues.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleOptions {
+    /// Maximum line width in columns.
+    pub width: usize,
+    /// Maximum lines per cue; longer sentences are spread over several cues.
+    pub max_lines: usize,
+    /// Language for the speaking-rate estimate that times the cues.
+    pub language: String,
+    /// Words (or characters) per minute, see [`estimate_duration`]; `None` uses the language's
+    /// rate.
+    pub rate: Option<f64>,
+}
+
+impl Default for SubtitleOptions {
+    fn default() -> Self {
+        Self {
+            width: 42,
+            max_lines: 2,
+            language: "en".to_string(),
+            rate: None,
+        }
+    }
+}
+
+/// One subtitle: up to `max_lines` lines shown from `start` to `end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cue {
+    /// Sequence number, counting from 1.
+    pub number: usize,
+    pub start: Duration,
+    pub end: Duration,
+    pub lines: Vec<String>,
+}
+
+/// Writes the cue as an SRT block, ending with an empty line.
+impl fmt::Display for Cue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn timestamp(time: Duration) -> String {
+            let ms = time.as_millis();
+            format!(
+                "{:02}:{:02}:{:02},{:03}",
+                ms / 3_600_000,
+                ms / 60_000 % 60,
+                ms / 1000 % 60,
+                ms % 1000
+            )
+        }
+        writeln!(f, "{}", self.number)?;
+        writeln!(f, "{} --> {}", timestamp(self.start), timestamp(self.end))?;
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Turns consecutive sentences into subtitle cues, played back to back at the estimated speaking
+/// rate.
+#[derive(Debug, Clone)]
+pub struct Subtitles {
+    options: SubtitleOptions,
+    clock: Duration,
+    count: usize,
+}
+
+impl Subtitles {
+    pub fn new(options: SubtitleOptions) -> Self {
+        Self {
+            options,
+            clock: Duration::ZERO,
+            count: 0,
+        }
+    }
+
+    /// The cues for the next sentence. A sentence that needs more than `max_lines` lines is
+    /// first split into balanced parts, one per cue, which share its speaking time by width.
+    pub fn cues(&mut self, sentence: &str) -> Vec<Cue> {
+        let tokens = tokenize(sentence);
+        if tokens.is_empty() {
+            return Vec::new();
+        }
+        let width = self.options.width.max(1);
+        let max_lines = self.options.max_lines.max(1);
+        let mut parts = 1;
+        let layout = loop {
+            let layout: Vec<Vec<String>> = balance_tokens(&tokens, parts)
+                .iter()
+                .map(|part| wrap_balanced(part, width))
+                .collect();
+            if parts >= tokens.len() || layout.iter().all(|lines| lines.len() <= max_lines) {
+                break layout;
+            }
+            parts += 1;
+        };
+
+        let duration = estimate_duration(sentence, &self.options.language, self.options.rate);
+        let total: usize = layout.iter().flatten().map(|l| display_width(l)).sum();
+        layout
+            .into_iter()
+            .map(|lines| {
+                let part: usize = lines.iter().map(|l| display_width(l)).sum();
+                let start = self.clock;
+                self.clock += duration.mul_f64(part as f64 / total.max(1) as f64);
+                self.count += 1;
+                Cue {
+                    number: self.count,
+                    start,
+                    end: self.clock,
+                    lines,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,290 @@
+//! Spelling out what a speech synthesizer would otherwise read wrongly or not at all, e.g.
+//! "Dr." → "Doctor" and "3.5" → "three point five", right after segmentation.
+//!
+//! A [`Verbalizer`] rewrites one sentence; [`PostProcess::verbalize`] runs it on every sentence a
+//! segmenter emits. [`English`] is a small built-in rule set, and any
+//! `Fn(&str) -> String` is a verbalizer too.
+
+use crate::postprocess::PostProcess;
+
+/// Rewrites a sentence into the words it should be spoken as.
+pub trait Verbalizer: Send + Sync {
+    fn verbalize(&self, sentence: &str) -> String;
+}
+
+impl<F: Fn(&str) -> String + Send + Sync> Verbalizer for F {
+    fn verbalize(&self, sentence: &str) -> String {
+        self(sentence)
+    }
+}
+
+impl PostProcess {
+    /// Runs `verbalizer` on every sentence.
+    pub fn verbalize(verbalizer: impl Verbalizer + 'static) -> Self {
+        Self::new(move |sentence| verbalizer.verbalize(&sentence))
+    }
+}
+
+/// Abbreviations expanded by [`English`], matched case-sensitively as whole words.
+const ENGLISH_ABBREVIATIONS: [(&str, &str); 16] = [
+    ("Dr.", "Doctor"),
+    ("Mr.", "Mister"),
+    ("Mrs.", "Missus"),
+    ("Ms.", "Miz"),
+    ("Prof.", "Professor"),
+    ("St.", "Saint"),
+    ("Jr.", "Junior"),
+    ("Sr.", "Senior"),
+    ("Gen.", "General"),
+    ("Capt.", "Captain"),
+    ("vs.", "versus"),
+    ("etc.", "et cetera"),
+    ("e.g.", "for example"),
+    ("i.e.", "that is"),
+    ("approx.", "approximately"),
+    ("Mt.", "Mount"),
+];
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const SCALES: [(u64, &str); 4] = [
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+/// Numbers with more digits than this are read digit by digit.
+const MAX_CARDINAL_DIGITS: usize = 15;
+
+/// English verbalization: common titles and Latin abbreviations, cardinal numbers (with
+/// thousands separators and a sign), decimals, percentages and ordinals such as "21st".
+///
+/// Numbers glued to letters ("mp3", "4K") are left alone, as are years, which are read as
+/// plain cardinals.
+#[derive(Debug, Clone)]
+pub struct English {
+    abbreviations: Vec<(String, String)>,
+}
+
+impl Default for English {
+    fn default() -> Self {
+        ENGLISH_ABBREVIATIONS.iter().fold(
+            Self {
+                abbreviations: Vec::new(),
+            },
+            |english, &(short, long)| english.with_abbreviation(short, long),
+        )
+    }
+}
+
+impl English {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also expands `abbreviation` (e.g. `"Rd."`) to `expansion`, replacing a built-in entry for
+    /// the same abbreviation.
+    pub fn with_abbreviation(mut self, abbreviation: &str, expansion: &str) -> Self {
+        self.abbreviations
+            .retain(|(short, _)| short != abbreviation);
+        self.abbreviations
+            .push((abbreviation.to_string(), expansion.to_string()));
+        // Longest first, so "Mrs." is not read as "Mr." followed by "s."
+        self.abbreviations
+            .sort_by_key(|(short, _)| std::cmp::Reverse(short.len()));
+        self
+    }
+
+    /// The expansion of the abbreviation starting `rest`, and its length.
+    fn abbreviation(&self, rest: &str) -> Option<(&str, usize)> {
+        self.abbreviations.iter().find_map(|(short, long)| {
+            let after = rest.strip_prefix(short.as_str())?;
+            ends_word(after).then_some((long.as_str(), short.len()))
+        })
+    }
+}
+
+impl Verbalizer for English {
+    fn verbalize(&self, sentence: &str) -> String {
+        let mut out = String::with_capacity(sentence.len() * 2);
+        let mut previous: Option<char> = None;
+        let mut i = 0;
+        while i < sentence.len() {
+            let rest = &sentence[i..];
+            let word_start = previous.is_none_or(|c| !c.is_alphanumeric());
+            if word_start {
+                if let Some((expansion, len)) = self.abbreviation(rest) {
+                    out.push_str(expansion);
+                    // The period of an abbreviation ending the sentence ends the sentence too
+                    if rest[len..].trim().is_empty() && rest[..len].ends_with('.') {
+                        out.push('.');
+                    }
+                    previous = rest[..len].chars().last();
+                    i += len;
+                    continue;
+                }
+                let signed = rest.starts_with('-') && previous.is_none_or(char::is_whitespace);
+                if let Some((words, len)) = number(&rest[usize::from(signed)..]) {
+                    if signed {
+                        out.push_str("minus ");
+                    }
+                    out.push_str(&words);
+                    let len = len + usize::from(signed);
+                    previous = rest[..len].chars().last();
+                    i += len;
+                    continue;
+                }
+            }
+            let c = rest.chars().next().expect("not at the end");
+            out.push(c);
+            previous = Some(c);
+            i += c.len_utf8();
+        }
+        out
+    }
+}
+
+/// The words for the number starting `text`, with its percent sign or ordinal suffix, and its
+/// length; `None` if `text` does not start with a standalone number.
+fn number(text: &str) -> Option<(String, usize)> {
+    let digits = |s: &str| s.bytes().take_while(u8::is_ascii_digit).count();
+    let mut len = digits(text);
+    if len == 0 {
+        return None;
+    }
+    let mut integer = text[..len].to_string();
+    // Thousands separators, only after at most three digits and in groups of three
+    if len <= 3 {
+        while let Some(group) = text[len..].strip_prefix(',') {
+            if digits(group) != 3 {
+                break;
+            }
+            integer.push_str(&group[..3]);
+            len += 4;
+        }
+    }
+    let mut fraction = "";
+    if text[len..].starts_with('.') {
+        let count = digits(&text[len + 1..]);
+        if count > 0 {
+            fraction = &text[len + 1..len + 1 + count];
+            len += 1 + count;
+        }
+    }
+
+    let after = &text[len..];
+    let suffix = ["st", "nd", "rd", "th"]
+        .into_iter()
+        .find(|s| after.starts_with(s))
+        .filter(|s| fraction.is_empty() && ends_word(&after[s.len()..]));
+    if let Some(suffix) = suffix {
+        return Some((ordinal(&cardinal(&integer)), len + suffix.len()));
+    }
+    if !ends_word(after) {
+        return None;
+    }
+
+    let mut words = cardinal(&integer);
+    if !fraction.is_empty() {
+        words.push_str(" point");
+        for digit in fraction.bytes() {
+            words.push(' ');
+            words.push_str(ONES[usize::from(digit - b'0')]);
+        }
+    }
+    if after.starts_with('%') {
+        words.push_str(" percent");
+        len += 1;
+    }
+    Some((words, len))
+}
+
+/// Whether `rest` does not continue the word before it.
+fn ends_word(rest: &str) -> bool {
+    rest.chars().next().is_none_or(|c| !c.is_alphanumeric())
+}
+
+/// `digits` as a cardinal number, or digit by digit if it is too long.
+fn cardinal(digits: &str) -> String {
+    match digits.parse::<u64>() {
+        Ok(n) if digits.len() <= MAX_CARDINAL_DIGITS => spell(n),
+        _ => digits
+            .bytes()
+            .map(|d| ONES[usize::from(d - b'0')])
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+fn spell(n: u64) -> String {
+    if n < 20 {
+        return ONES[n as usize].to_string();
+    }
+    if n < 100 {
+        let tens = TENS[(n / 10) as usize];
+        return match n % 10 {
+            0 => tens.to_string(),
+            ones => format!("{}-{}", tens, ONES[ones as usize]),
+        };
+    }
+    if n < 1000 {
+        let hundreds = format!("{} hundred", ONES[(n / 100) as usize]);
+        return match n % 100 {
+            0 => hundreds,
+            rest => format!("{} {}", hundreds, spell(rest)),
+        };
+    }
+    let (scale, name) = SCALES
+        .into_iter()
+        .find(|&(scale, _)| n >= scale)
+        .expect("n is at least a thousand");
+    let head = format!("{} {}", spell(n / scale), name);
+    match n % scale {
+        0 => head,
+        rest => format!("{} {}", head, spell(rest)),
+    }
+}
+
+/// Turns the last word of a cardinal into its ordinal: "twenty-one" → "twenty-first".
+fn ordinal(cardinal: &str) -> String {
+    let split = cardinal.rfind([' ', '-']).map_or(0, |i| i + 1);
+    let (head, last) = cardinal.split_at(split);
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        word if word.ends_with('y') => format!("{}ieth", &word[..word.len() - 1]),
+        word => format!("{}th", word),
+    };
+    format!("{}{}", head, last)
+}
@@ -0,0 +1,101 @@
+//! Non-fatal anomalies reported next to the sentence stream instead of failing it, e.g. input
+//! that was decoded lossily or a sentence that is suspiciously long.
+//!
+//! Set [`SegmentOptions::warnings`](crate::SegmentOptions::warnings) to a [`Warnings`] sink; every
+//! segmenter and stream built from those options reports into it.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// What went wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// Invalid UTF-8 was skipped or replaced under a lenient `Utf8Policy`.
+    LossyDecode,
+    /// A sentence exceeded `SegmentOptions::long_sentence_warning` bytes.
+    LongSentence,
+    /// A segmenter flushed its buffer early because the shared memory pool was full.
+    MemoryPressure,
+    /// The language has no rules; those of `SegmentOptions::fallback_language`, or ICU4X's, are
+    /// used.
+    LanguageFallback,
+    /// Text without a sentence boundary was split to respect a length limit:
+    /// `SegmentOptions::max_sentence_len`, or `max_buffer` under `overflow_policy`.
+    ForcedSplit,
+}
+
+impl WarningKind {
+    /// Short, stable name, e.g. for log fields.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WarningKind::LossyDecode => "lossy_decode",
+            WarningKind::LongSentence => "long_sentence",
+            WarningKind::MemoryPressure => "memory_pressure",
+            WarningKind::LanguageFallback => "language_fallback",
+            WarningKind::ForcedSplit => "forced_split",
+        }
+    }
+}
+
+/// One reported anomaly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Warning {
+    pub kind: WarningKind,
+    /// Absolute byte offset in the input the warning refers to.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+/// Where warnings go; cheap to clone, clones report to the same place.
+#[derive(Clone)]
+pub struct Warnings {
+    sink: Arc<dyn Fn(Warning) + Send + Sync>,
+}
+
+impl Warnings {
+    /// Calls `sink` for every warning, on the thread that ran into it.
+    pub fn new(sink: impl Fn(Warning) + Send + Sync + 'static) -> Self {
+        Self {
+            sink: Arc::new(sink),
+        }
+    }
+
+    /// Sends warnings to the returned receiver; they are dropped once it is gone.
+    #[cfg(feature = "tokio")]
+    pub fn channel() -> (Self, tokio::sync::mpsc::UnboundedReceiver<Warning>) {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let warnings = Self::new(move |warning| {
+            let _ = sender.send(warning);
+        });
+        (warnings, receiver)
+    }
+
+    pub(crate) fn report(&self, kind: WarningKind, offset: usize, message: String) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(kind = kind.as_str(), offset, "{}", message);
+        (self.sink)(Warning {
+            kind,
+            offset,
+            message,
+        });
+    }
+}
+
+impl fmt::Debug for Warnings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Warnings").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,47 @@
+//! WebAssembly bindings, built as a `cdylib` with the `wasm` feature (see the README).
+//!
+//! Only the synchronous core is exposed: JavaScript drives the stream by calling `feed()` for
+//! each chunk and `finish()` at the end.
+
+use crate::config::SegmentOptions;
+use crate::segmenter::Segmenter;
+use wasm_bindgen::prelude::*;
+
+/// Streaming sentence segmenter, exported to JavaScript as `Segmenter`.
+#[wasm_bindgen(js_name = Segmenter)]
+pub struct WasmSegmenter {
+    inner: Segmenter,
+}
+
+#[wasm_bindgen(js_class = Segmenter)]
+impl WasmSegmenter {
+    /// `new Segmenter(language?, lookahead?, maxBuffer?)`; omitted values use the defaults.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        language: Option<String>,
+        lookahead: Option<usize>,
+        max_buffer: Option<usize>,
+    ) -> Result<WasmSegmenter, JsError> {
+        let defaults = SegmentOptions::default();
+        let options = SegmentOptions {
+            language: language.unwrap_or(defaults.language),
+            lookahead: lookahead.unwrap_or(defaults.lookahead),
+            max_buffer: max_buffer.unwrap_or(defaults.max_buffer),
+            ..defaults
+        };
+        Ok(Self {
+            inner: Segmenter::new(options)?,
+        })
+    }
+
+    /// Appends a chunk and returns the sentences it completed as a `string[]`.
+    pub fn feed(&mut self, chunk: &str) -> Result<Vec<String>, JsError> {
+        Ok(self.inner.feed(chunk)?)
+    }
+
+    /// Ends the input and returns the remaining sentences as a `string[]`, including those
+    /// still held back for lookahead and the unterminated tail.
+    pub fn finish(&mut self) -> Result<Vec<String>, JsError> {
+        Ok(self.inner.finish()?)
+    }
+}